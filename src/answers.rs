@@ -0,0 +1,98 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tera::Context;
+
+use crate::{
+    cache::Cache,
+    error::{Error, Result},
+};
+
+/// The answers of the last run of a template, offered as the prompt defaults
+/// of the next run with `--remember`.
+///
+/// They are stored as JSON next to the cached templates, keyed by the
+/// template name, e.g. `answers-rust-cli.json`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers(pub serde_json::Map<String, tera::Value>);
+
+impl Answers {
+    pub fn path(template_name: &str) -> PathBuf {
+        let name = template_name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        Cache::cache_dir().join(format!("answers-{}.json", name))
+    }
+
+    /// Takes the answers of `context`, leaving out `excluded` ones like
+    /// secrets.
+    pub fn from_context(context: &Context, excluded: &BTreeSet<String>) -> Self {
+        let mut answers = match context.clone().into_json() {
+            tera::Value::Object(answers) => answers,
+            _ => serde_json::Map::new(),
+        };
+        answers.retain(|name, _| !excluded.contains(name));
+        Self(answers)
+    }
+
+    /// Returns `None` when nothing was remembered, a file which cannot be
+    /// read is taken as nothing remembered too.
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok().map(Self)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.0).unwrap();
+        path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, content))
+            .map_err(|e| Error::CannotWriteFile {
+                source: e,
+                path: path.to_path_buf(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_answers() {
+        let dir = tempdir::TempDir::new("answers").unwrap();
+        let path = dir.path().join("answers-demo.json");
+        assert_eq!(Answers::load(&path), None);
+
+        let mut context = Context::new();
+        context.insert("project_name", "demo");
+        context.insert("features", &["cli", "docs"]);
+        context.insert("api_token", "hunter2");
+        let answers = Answers::from_context(&context, &BTreeSet::from(["api_token".to_string()]));
+        answers.save(&path).unwrap();
+
+        let loaded = Answers::load(&path).unwrap();
+        assert_eq!(loaded, answers);
+        assert_eq!(loaded.0["project_name"], "demo");
+        assert_eq!(loaded.0["features"], serde_json::json!(["cli", "docs"]));
+        assert!(!loaded.0.contains_key("api_token"));
+
+        fs::write(&path, "{").unwrap();
+        assert_eq!(Answers::load(&path), None);
+    }
+
+    #[test]
+    fn test_answers_path() {
+        assert!(Answers::path("my-org/rust cli").ends_with("answers-my-org_rust_cli.json"));
+    }
+}
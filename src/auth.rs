@@ -0,0 +1,167 @@
+use std::fmt::Debug;
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+
+use crate::error::{Error, Result};
+
+/// The environment variables which take precedence over `--auth`, so the
+/// password doesn't end up in the shell history.
+pub const USERNAME_ENV: &str = "PETRIDISH_GIT_USERNAME";
+pub const PASSWORD_ENV: &str = "PETRIDISH_GIT_PASSWORD";
+/// The environment variable which takes precedence over `--token`.
+pub const TOKEN_ENV: &str = "PETRIDISH_TOKEN";
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+impl Credentials {
+    /// Assembles the git credentials from the environment variables, falling
+    /// back to `auth` given like `<username>:<password>` for each of them.
+    /// Asking for them interactively is up to the caller.
+    pub fn resolve(
+        auth: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Option<Self>> {
+        let (cli_username, cli_password) = match auth {
+            Some(auth) => {
+                let (username, password) = auth.split_once(':').ok_or_else(|| {
+                    Error::ArgsError("auth is invalid, should be like <username>:<password>".into())
+                })?;
+                (Some(username.to_string()), Some(password.to_string()))
+            }
+            None => (None, None),
+        };
+
+        match (
+            env(USERNAME_ENV).or(cli_username),
+            env(PASSWORD_ENV).or(cli_password),
+        ) {
+            (Some(username), Some(password)) => Ok(Some(Self { username, password })),
+            (Some(_), None) => Err(Error::AuthMissingPassword("git".into())),
+            (None, Some(_)) => Err(Error::AuthMissingUsername("git".into())),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// The credentials sent with http(s) downloads, e.g. of a registry index on
+/// a private server.
+#[derive(PartialEq, Eq)]
+pub enum HttpAuth {
+    Basic(Credentials),
+    Bearer(String),
+}
+
+impl Debug for HttpAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpAuth::Basic(credentials) => f.debug_tuple("Basic").field(credentials).finish(),
+            HttpAuth::Bearer(_) => f.debug_tuple("Bearer").field(&"***").finish(),
+        }
+    }
+}
+
+impl HttpAuth {
+    /// Picks the token (`PETRIDISH_TOKEN` first, then `token`) over the
+    /// credentials, as a token is given for http downloads only.
+    pub fn resolve(
+        credentials: Option<Credentials>,
+        token: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<Self> {
+        match env(TOKEN_ENV).or_else(|| token.map(String::from)) {
+            Some(token) => Some(HttpAuth::Bearer(token)),
+            None => credentials.map(HttpAuth::Basic),
+        }
+    }
+
+    /// The value of the `Authorization` header.
+    pub fn header(&self) -> String {
+        match self {
+            HttpAuth::Basic(credentials) => format!(
+                "Basic {}",
+                BASE64_STANDARD
+                    .encode(format!("{}:{}", credentials.username, credentials.password))
+            ),
+            HttpAuth::Bearer(token) => format!("Bearer {}", token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn credentials(username: &str, password: &str) -> Option<Credentials> {
+        Some(Credentials {
+            username: username.into(),
+            password: password.into(),
+        })
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let no_env = |_: &str| None;
+        assert_eq!(Credentials::resolve(None, no_env).unwrap(), None);
+        assert_eq!(
+            Credentials::resolve(Some("cli:p:ss"), no_env).unwrap(),
+            credentials("cli", "p:ss")
+        );
+
+        let env = HashMap::from([(USERNAME_ENV, "env"), (PASSWORD_ENV, "secret")]);
+        let env = |key: &str| env.get(key).map(|v| v.to_string());
+        assert_eq!(
+            Credentials::resolve(Some("cli:pass"), env).unwrap(),
+            credentials("env", "secret")
+        );
+
+        let password_env = |key: &str| (key == PASSWORD_ENV).then(|| "secret".to_string());
+        assert_eq!(
+            Credentials::resolve(Some("cli:pass"), password_env).unwrap(),
+            credentials("cli", "secret")
+        );
+        assert!(matches!(
+            Credentials::resolve(None, password_env),
+            Err(Error::AuthMissingUsername(_))
+        ));
+        assert!(Credentials::resolve(Some("cli"), no_env).is_err());
+    }
+
+    #[test]
+    fn test_debug_hides_password() {
+        let debug = format!("{:?}", credentials("user", "secret").unwrap());
+        assert!(!debug.contains("secret"));
+    }
+
+    #[test]
+    fn test_http_auth_header() {
+        let no_env = |_: &str| None;
+        let basic = HttpAuth::resolve(credentials("user", "p:ss"), None, no_env).unwrap();
+        assert_eq!(basic.header(), "Basic dXNlcjpwOnNz");
+
+        let bearer = HttpAuth::resolve(credentials("user", "pass"), Some("abc"), no_env).unwrap();
+        assert_eq!(bearer.header(), "Bearer abc");
+        assert_eq!(format!("{:?}", bearer), r#"Bearer("***")"#);
+
+        let env = |key: &str| (key == TOKEN_ENV).then(|| "env".to_string());
+        assert_eq!(
+            HttpAuth::resolve(None, Some("abc"), env),
+            Some(HttpAuth::Bearer("env".into()))
+        );
+        assert_eq!(HttpAuth::resolve(None, None, no_env), None);
+    }
+}
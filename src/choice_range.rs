@@ -0,0 +1,164 @@
+use serde::{Deserialize, Deserializer};
+
+/// Choices written as a range, either `"1..20"` or
+/// `{ start = 1, end = 20, step = 1 }`. Both ends are inclusive.
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct ChoiceRange {
+    start: f64,
+    end: f64,
+    #[serde(default = "default_step")]
+    step: f64,
+}
+
+/// The most choices a range may generate, a larger one is most likely a typo.
+const MAX_RANGE_CHOICES: f64 = 10_000.0;
+
+fn default_step() -> f64 {
+    1.0
+}
+
+impl ChoiceRange {
+    fn parse(range: &str) -> Result<Self, String> {
+        let (start, end) = range
+            .split_once("..")
+            .ok_or_else(|| format!("invalid range '{}', should be like 1..20", range))?;
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<f64>()
+                .map_err(|_| format!("invalid range '{}', should be like 1..20", range))
+        };
+
+        Ok(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+            step: default_step(),
+        })
+    }
+
+    fn expand(&self) -> Result<Vec<f64>, String> {
+        if let Some(value) = [self.start, self.end, self.step]
+            .into_iter()
+            .find(|v| !v.is_finite())
+        {
+            return Err(format!("range value {} must be a finite number", value));
+        }
+        if self.start > self.end {
+            return Err(format!(
+                "range start {} must not be greater than end {}",
+                self.start, self.end
+            ));
+        }
+        if self.step <= 0.0 {
+            return Err(format!("range step {} must be greater than 0", self.step));
+        }
+
+        // the tolerance keeps an end like 0.3 in 0.1 steps despite rounding
+        let steps = ((self.end - self.start) / self.step + 1e-9).floor();
+        if steps >= MAX_RANGE_CHOICES {
+            return Err(format!(
+                "range {}..{} with step {} generates more than {} choices",
+                self.start, self.end, self.step, MAX_RANGE_CHOICES
+            ));
+        }
+
+        Ok((0..=steps as u32)
+            .map(|i| self.start + f64::from(i) * self.step)
+            .collect())
+    }
+}
+
+/// Choice types which can be generated from a [`ChoiceRange`].
+pub trait FromRange: Sized {
+    fn from_range(range: ChoiceRange) -> Result<Vec<Self>, String>;
+}
+
+impl FromRange for f64 {
+    fn from_range(range: ChoiceRange) -> Result<Vec<Self>, String> {
+        range.expand()
+    }
+}
+
+impl FromRange for String {
+    fn from_range(_: ChoiceRange) -> Result<Vec<Self>, String> {
+        Err("range choices are only supported by number prompts".into())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Choices<T> {
+    List(Vec<T>),
+    Range(ChoiceRange),
+    Shorthand(String),
+}
+
+pub fn deserialize_choices<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromRange,
+{
+    let range = match Choices::<T>::deserialize(deserializer)? {
+        Choices::List(choices) => return Ok(choices),
+        Choices::Range(range) => range,
+        Choices::Shorthand(range) => {
+            ChoiceRange::parse(&range).map_err(serde::de::Error::custom)?
+        }
+    };
+
+    T::from_range(range).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    struct Numbers {
+        #[serde(deserialize_with = "deserialize_choices")]
+        choices: Vec<f64>,
+    }
+
+    fn parse(config: &str) -> Result<Vec<f64>, toml::de::Error> {
+        toml::from_str::<Numbers>(config).map(|n| n.choices)
+    }
+
+    #[test]
+    fn test_range_shorthand() {
+        assert_eq!(
+            parse(r#"choices = "1..5""#).unwrap(),
+            vec![1_f64, 2_f64, 3_f64, 4_f64, 5_f64]
+        );
+        assert_eq!(parse(r#"choices = "3..3""#).unwrap(), vec![3_f64]);
+    }
+
+    #[test]
+    fn test_range_table() {
+        assert_eq!(
+            parse("choices = { start = 2000, end = 2020, step = 10 }").unwrap(),
+            vec![2000_f64, 2010_f64, 2020_f64]
+        );
+        assert_eq!(
+            parse("choices = { start = 0, end = 1, step = 0.5 }").unwrap(),
+            vec![0_f64, 0.5, 1_f64]
+        );
+        assert_eq!(
+            parse("choices = { start = 0, end = 0.3, step = 0.1 }")
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_invalid_range() {
+        assert!(parse(r#"choices = "5..1""#).is_err());
+        assert!(parse(r#"choices = "1-5""#).is_err());
+        assert!(parse("choices = { start = 1, end = 5, step = 0 }").is_err());
+        assert!(parse("choices = { start = 1, end = 5, step = -1 }").is_err());
+        assert!(parse("choices = { start = 1, end = inf }").is_err());
+        assert!(parse("choices = { start = 1, end = 5, step = nan }").is_err());
+        assert!(parse(r#"choices = "1..1e300""#).is_err());
+        assert!(parse("choices = { start = 0, end = 1, step = 1e-300 }").is_err());
+        assert_eq!(parse(r#"choices = "1..10000""#).unwrap().len(), 10_000);
+    }
+}
@@ -1,21 +1,39 @@
 use enum_dispatch::enum_dispatch;
-use std::fmt::Display;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+    fs::read_to_string,
+    path::Path,
+    str::FromStr,
+};
 
-use inquire::{list_option::ListOption, validator::Validation};
-use serde::{Deserialize, Serialize};
+use inquire::{list_option::ListOption, validator::Validation, PasswordDisplayMode};
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use serde::{Deserialize, Deserializer, Serialize};
 use tera::{Context, Tera};
 
-use crate::{error::Result, literal_value::LiteralTrue};
+use crate::{
+    choice_range::{deserialize_choices, FromRange},
+    error::{Error, Result},
+    filters::register_filters,
+    literal_value::LiteralTrue,
+    locale::Locale,
+    render::{
+        glob, template_file, Delimiters, LineEndings, Symlinks, DEFAULT_EXCLUDE_RENDER_PATHS,
+    },
+    requirement::Requirement,
+};
 
-#[derive(Deserialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    #[serde(default, rename(deserialize = "petridish"))]
+    #[serde(default, rename = "petridish")]
     pub petridish_config: PetridishConfig,
     #[serde(default)]
+    #[schemars(with = "Vec<PromptItem>")]
     pub prompts: Vec<PromptType>,
 }
 
-#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, JsonSchema)]
 pub struct PetridishConfig {
     #[serde(default = "default_prompt_message_for_project_name")]
     pub project_prompt: String,
@@ -23,8 +41,31 @@ pub struct PetridishConfig {
     pub project_var_name: String,
     pub short_description: Option<String>,
     pub long_description: Option<String>,
-    #[serde(default)]
+    #[serde(default, alias = "copy_without_render")]
     pub exclude_render_paths: Vec<String>,
+    pub render_suffix: Option<String>,
+    #[serde(default)]
+    pub delimiters: Delimiters,
+    #[serde(default)]
+    pub trim_whitespace: bool,
+    #[serde(default)]
+    pub line_endings: LineEndings,
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    pub requires: Vec<Requirement>,
+    #[serde(default)]
+    pub strip_gitkeep: bool,
+    #[serde(default = "default_dir_marker")]
+    pub dir_marker: String,
+    pub output_subdir: Option<String>,
+    pub extends: Option<String>,
+    /// Also copy common tool configs like `.editorconfig` without rendering,
+    /// their braces tend to trip Tera, see `DEFAULT_EXCLUDE_RENDER_PATHS`.
+    #[serde(default = "default_true")]
+    pub exclude_render_defaults: bool,
+    /// What to generate for the symlinks of the template, see `Symlinks`.
+    #[serde(default)]
+    pub symlinks: Symlinks,
 }
 
 fn default_prompt_message_for_project_name() -> String {
@@ -35,6 +76,41 @@ fn default_project_var_name() -> String {
     "project_name".into()
 }
 
+fn default_dir_marker() -> String {
+    ".gitkeep".into()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The templated values of prompts, e.g. defaults, are reported against
+/// `petridish.toml`.
+fn config_render_error(source: tera::Error) -> Error {
+    Error::RenderError {
+        source,
+        path: "petridish.toml".into(),
+    }
+}
+
+/// Renders the `<< name >>` and `<% if ... %>` tags of `petridish.toml` with
+/// `context` and the environment as `env`, e.g. to list choices given by
+/// `--define`. Tera's own delimiters are kept as they are for the prompts.
+fn render_config(content: &str, context: &Context) -> Result<String> {
+    if !content.contains("<<") && !content.contains("<%") {
+        return Ok(content.to_string());
+    }
+    let delimiters = Delimiters {
+        variable_start: "<<".into(),
+        variable_end: ">>".into(),
+        block_start: "<%".into(),
+        block_end: "%>".into(),
+    };
+    let mut context = context.clone();
+    context.insert("env", &std::env::vars().collect::<BTreeMap<_, _>>());
+    Tera::one_off(&delimiters.translate(content), &context, false).map_err(config_render_error)
+}
+
 impl Default for PetridishConfig {
     fn default() -> Self {
         Self {
@@ -43,6 +119,214 @@ impl Default for PetridishConfig {
             short_description: None,
             long_description: None,
             exclude_render_paths: vec![],
+            render_suffix: None,
+            delimiters: Delimiters::default(),
+            trim_whitespace: false,
+            line_endings: LineEndings::Preserve,
+            requires: vec![],
+            strip_gitkeep: false,
+            dir_marker: default_dir_marker(),
+            output_subdir: None,
+            extends: None,
+            exclude_render_defaults: true,
+            symlinks: Symlinks::Copy,
+        }
+    }
+}
+
+impl PetridishConfig {
+    /// Checks the globs of `exclude_render_paths`, the ones using template
+    /// variables are checked once rendered.
+    pub fn check_globs(&self) -> Result<()> {
+        for pattern in &self.exclude_render_paths {
+            if !pattern.contains("{{") && !pattern.contains("{%") {
+                glob(pattern)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The paths copied without rendering, `exclude_render_paths` along with
+    /// the defaults unless they are turned off.
+    pub fn render_excludes(&self) -> Vec<String> {
+        let mut paths = self.exclude_render_paths.clone();
+        if self.exclude_render_defaults {
+            paths.extend(DEFAULT_EXCLUDE_RENDER_PATHS.iter().map(|p| p.to_string()));
+        }
+        paths
+    }
+}
+
+impl Config {
+    /// Loads `petridish.toml` of the template dir, with the choices of
+    /// `choices_file` read from the template.
+    pub fn load(template_dir: &Path) -> Result<Self> {
+        Self::load_with_context(template_dir, &Context::new())
+    }
+
+    /// Loads `petridish.toml` like `load`, rendering it first with the values
+    /// known before prompting, e.g. `--define`s, see `render_config`.
+    pub fn load_with_context(template_dir: &Path, context: &Context) -> Result<Self> {
+        let path = template_dir.join("petridish.toml");
+        let content = read_to_string(&path).map_err(|e| Error::PathNotFound { source: e, path })?;
+        let content = render_config(&content, context)?;
+        let mut config = toml::from_str::<Config>(&content)?;
+        for prompt in config.prompts.iter_mut() {
+            prompt.load_choices(template_dir)?;
+        }
+        config.prompts = sort_prompts(config.prompts)?;
+        config.petridish_config.check_globs()?;
+
+        Ok(config)
+    }
+
+    /// The config as `petridish.toml`, e.g. to check what's left after
+    /// rendering, inheriting and overlaying prompts.
+    pub fn to_toml(&self) -> String {
+        let value = toml::Value::try_from(self).unwrap();
+        toml::to_string_pretty(&value).unwrap()
+    }
+
+    /// Takes the prompts of the `base` template this one extends which it
+    /// doesn't declare itself, they are asked first.
+    pub fn inherit_prompts(&mut self, base: Config) -> Result<()> {
+        let mut prompts = base
+            .prompts
+            .into_iter()
+            .filter(|base_prompt| self.prompts.iter().all(|p| p.name() != base_prompt.name()))
+            .collect::<Vec<_>>();
+        prompts.append(&mut self.prompts);
+        self.prompts = sort_prompts(prompts)?;
+        Ok(())
+    }
+
+    /// Appends the prompts of another prompts file, e.g. ones every template
+    /// of an organization should ask, they are asked after the template's
+    /// own. A prompt named like one of the template's is an error.
+    pub fn overlay_prompts(&mut self, path: &Path) -> Result<()> {
+        let content = read_to_string(path).map_err(|e| Error::PathNotFound {
+            source: e,
+            path: path.to_path_buf(),
+        })?;
+        let overlay = toml::from_str::<Config>(&content)?;
+        let mut names = BTreeSet::new();
+        for_each_prompt(&self.prompts, &mut |prompt| {
+            names.insert(prompt.name());
+        });
+        let mut duplicate = None;
+        for_each_prompt(&overlay.prompts, &mut |prompt| {
+            if names.contains(prompt.name()) {
+                duplicate.get_or_insert(prompt.name());
+            }
+        });
+        if let Some(name) = duplicate {
+            return Err(Error::DuplicatePrompt(name.to_string()));
+        }
+
+        let overlay_dir = path.parent().unwrap_or(Path::new(""));
+        let mut prompts = std::mem::take(&mut self.prompts);
+        for mut prompt in overlay.prompts {
+            prompt.load_choices(overlay_dir)?;
+            prompts.push(prompt);
+        }
+        self.prompts = sort_prompts(prompts)?;
+        Ok(())
+    }
+
+    /// The names of every prompt, including follow-up prompts and the
+    /// project name.
+    pub fn prompt_names(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::from([self.petridish_config.project_var_name.clone()]);
+        for_each_prompt(&self.prompts, &mut |prompt| {
+            names.insert(prompt.name().to_string());
+        });
+        names
+    }
+
+    /// The names of the `secret` prompts, including follow-up prompts.
+    pub fn secret_names(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        for_each_prompt(&self.prompts, &mut |prompt| {
+            if let PromptType::String(StringPrompt::Input(input)) = prompt {
+                if input.secret {
+                    names.insert(input.name.clone());
+                }
+            }
+        });
+        names
+    }
+
+    /// The names of the prompts whose answers are not remembered by
+    /// `--remember`, i.e. secrets and file contents.
+    pub fn unremembered_names(&self) -> BTreeSet<String> {
+        let mut names = self.secret_names();
+        for_each_prompt(&self.prompts, &mut |prompt| {
+            if let PromptType::File(file) = prompt {
+                names.insert(file.name.clone());
+            }
+        });
+        names
+    }
+
+    /// Takes remembered `answers` as the defaults of the prompts, including
+    /// follow-up prompts.
+    pub fn remember(&mut self, answers: &serde_json::Map<String, tera::Value>) {
+        remember_answers(&mut self.prompts, answers);
+    }
+
+    /// Takes the defines and answers in `values` as the defaults of the
+    /// prompts, including follow-up prompts, see [`ValueSource`]. The values
+    /// answering prompts are taken when asking.
+    pub fn prefill(&mut self, values: &PromptValues) {
+        let mut defaults = serde_json::Map::new();
+        for_each_prompt(&self.prompts, &mut |prompt| {
+            if let Some((_, value)) = values.given_default(prompt.name()) {
+                defaults.insert(prompt.name().to_string(), value);
+            }
+        });
+        self.remember(&defaults);
+    }
+
+    /// Builds the context without prompting, taking the values of the
+    /// prompts resolved from `values`. Prompts without any are left out.
+    pub fn context_from(&self, values: &PromptValues) -> Result<Context> {
+        let mut context = Context::new();
+        let project_var_name = &self.petridish_config.project_var_name;
+        if let Some((_, project_name)) = values.given(project_var_name) {
+            context.insert(project_var_name, &project_name);
+        }
+        for prompt in &self.prompts {
+            let mut prompt = Some(prompt);
+            while let Some(current) = prompt {
+                match values.resolve(current, &context)? {
+                    Some((source, tera::Value::String(value))) if source.skips_prompt() => {
+                        current.answer(&value, &mut context)?
+                    }
+                    Some((ValueSource::Default, _)) => current.skip(&mut context)?,
+                    Some((_, value)) => context.insert(current.name(), &value),
+                    None => {}
+                }
+                prompt = current.follow_up(&context);
+            }
+        }
+
+        Ok(context)
+    }
+
+    /// Returns the JSON schema of `petridish.toml`, so editors can validate
+    /// and autocomplete template configs.
+    pub fn json_schema() -> RootSchema {
+        schema_for!(Config)
+    }
+
+    /// Overrides prompt messages and choice labels with the translations in
+    /// `locale`, keeping the built-in ones when no translation exists.
+    pub fn localize(&mut self, locale: &Locale) {
+        if let Some(message) = locale.message(&self.petridish_config.project_var_name) {
+            self.petridish_config.project_prompt = message.to_string();
+        }
+        for prompt in self.prompts.iter_mut() {
+            prompt.localize(locale);
         }
     }
 }
@@ -50,18 +334,586 @@ impl Default for PetridishConfig {
 #[enum_dispatch]
 pub trait Prompt {
     fn prompt(self, context: &mut Context) -> Result<()>;
+    fn name(&self) -> &str;
+    /// The section the prompt is grouped under, shown as a header when it
+    /// starts.
+    fn section(&self) -> Option<&str>;
+    /// The names of the prompts which have to be answered before this one,
+    /// e.g. because its default refers to them.
+    fn depends_on(&self) -> &[String];
+    fn localize(&mut self, locale: &Locale);
+    /// Reads the choices of `choices_file` relative to the template dir.
+    fn load_choices(&mut self, _template_dir: &Path) -> Result<()> {
+        Ok(())
+    }
+    /// Answers the prompt with `value` instead of asking for it, coercing it
+    /// to the type of the prompt.
+    fn answer(&self, value: &str, context: &mut Context) -> Result<()>;
+    /// The declared default of the prompt rendered against the context,
+    /// `None` if it has none.
+    fn default_value(&self, context: &Context) -> Result<Option<tera::Value>>;
+    /// Takes `value`, e.g. the answer of the last run, as the default. A
+    /// value which doesn't fit the prompt is ignored.
+    fn set_default(&mut self, _value: &tera::Value) {}
+}
+
+impl PromptType {
+    /// The environment variable answering the prompt, e.g. `PETRIDISH_VAR_AGE`
+    /// for the prompt `age`.
+    pub fn env_var(&self) -> String {
+        env_var(self.name())
+    }
+
+    /// Answers the prompt from its environment variable if set, otherwise asks
+    /// for it. A value which doesn't fit the prompt is an error when `strict`,
+    /// or else is reported and the prompt is asked as usual.
+    pub fn prompt_or_env(self, context: &mut Context, strict: bool) -> Result<()> {
+        let values = PromptValues::default().with_env();
+        self.prompt_or_timeout(context, strict, &values, &mut |_| Ok(true))
+    }
+
+    /// Like [`PromptType::prompt_or_env`], answering from any of the `values`
+    /// which skip the prompt, and only asking once `wait` reports input
+    /// activity. When it times out instead, the declared default is taken,
+    /// which fails for prompts without one.
+    pub fn prompt_or_timeout(
+        mut self,
+        context: &mut Context,
+        strict: bool,
+        values: &PromptValues,
+        wait: &mut dyn FnMut(&Self) -> Result<bool>,
+    ) -> Result<()> {
+        let follow_ups = self.take_follow_ups();
+        let name = self.name().to_string();
+        self.answer_or_ask(context, strict, values, wait)?;
+
+        match selected_follow_up(follow_ups, &name, context) {
+            Some(follow_up) => follow_up.prompt_or_timeout(context, strict, values, wait),
+            None => Ok(()),
+        }
+    }
+
+    fn answer_or_ask(
+        self,
+        context: &mut Context,
+        strict: bool,
+        values: &PromptValues,
+        wait: &mut dyn FnMut(&Self) -> Result<bool>,
+    ) -> Result<()> {
+        if let Some((source, value)) = values.answer(self.name()) {
+            match self.answer(&value, context) {
+                Ok(()) => return Ok(()),
+                Err(e) if strict => return Err(e),
+                Err(e) => match source {
+                    ValueSource::Env => eprintln!("ignored `{}`: {}", self.env_var(), e),
+                    _ => eprintln!("ignored `{}={}`: {}", self.name(), value, e),
+                },
+            }
+        }
+
+        if wait(&self)? {
+            self.prompt(context)
+        } else {
+            self.skip(context)
+        }
+    }
+
+    /// The follow-up prompt of the choice answered for this select, if any.
+    pub fn follow_up(&self, context: &Context) -> Option<&PromptType> {
+        let follow_ups = match self {
+            PromptType::String(StringPrompt::Select(select)) => &select.follow_up,
+            PromptType::Number(NumberPrompt::Select(select)) => &select.follow_up,
+            _ => return None,
+        };
+        follow_ups.get(choice_key(follow_ups, context.get(self.name())?)?)
+    }
+
+    /// Takes out the follow-up prompts of a select, keyed by the choice which
+    /// asks them.
+    pub fn take_follow_ups(&mut self) -> BTreeMap<String, PromptType> {
+        match self {
+            PromptType::String(StringPrompt::Select(select)) => {
+                std::mem::take(&mut select.follow_up)
+            }
+            PromptType::Number(NumberPrompt::Select(select)) => {
+                std::mem::take(&mut select.follow_up)
+            }
+            _ => BTreeMap::new(),
+        }
+    }
+
+    /// Asks the prompt through `input` instead of the terminal, e.g. for
+    /// scripted answers. `input` gets the prompt name and returns the raw
+    /// answer, or `None` to accept the default like pressing enter. Answers
+    /// which don't fit the prompt are asked for again.
+    pub fn prompt_with(
+        &self,
+        context: &mut Context,
+        mut input: impl FnMut(&str) -> Result<Option<String>>,
+    ) -> Result<()> {
+        loop {
+            let value = match input(self.name())? {
+                Some(value) => value,
+                None => {
+                    let default = self.empty_answer(context)?;
+                    context.insert(self.name(), &default);
+                    return Ok(());
+                }
+            };
+            match self.answer(&value, context) {
+                Err(Error::InvalidAnswer { .. }) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// The answer when accepting the prompt without typing anything, the same
+    /// as at the terminal.
+    fn empty_answer(&self, context: &Context) -> Result<tera::Value> {
+        if let Some(default) = self.select_default(context)? {
+            return Ok(default);
+        }
+        if let Some(default) = self.default_value(context)? {
+            return Ok(default);
+        }
+
+        Ok(match self {
+            PromptType::Number(NumberPrompt::Input(input)) => input.min.unwrap_or_default().into(),
+            PromptType::Bool(_) => false.into(),
+            _ => "".into(),
+        })
+    }
+
+    /// The answer of select prompts without asking, i.e. the declared default,
+    /// or else the first choice of selects and no choice of multi selects.
+    /// `None` for the other prompts.
+    pub fn select_default(&self, context: &Context) -> Result<Option<tera::Value>> {
+        let value = match self {
+            PromptType::String(StringPrompt::Select(select)) => select.resolve_default(context)?,
+            PromptType::Number(NumberPrompt::Select(select)) => select.resolve_default(context)?,
+            PromptType::String(StringPrompt::MultiSelect(select)) => {
+                select.resolve_default(context)?
+            }
+            PromptType::Number(NumberPrompt::MultiSelect(select)) => {
+                select.resolve_default(context)?
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(value))
+    }
+
+    /// Skips the prompt, taking its declared default as the answer. The
+    /// default goes through `transform` like a typed answer.
+    pub fn skip(&self, context: &mut Context) -> Result<()> {
+        let Some(default) = self.default_value(context)? else {
+            return Err(Error::MissingDefault(self.name().to_string()));
+        };
+        let default = match (self, default) {
+            (PromptType::String(StringPrompt::Input(input)), tera::Value::String(default)) => {
+                transform_answer(input.transform.as_deref(), default, context)?.into()
+            }
+            (_, default) => default,
+        };
+        context.insert(self.name(), &default);
+        Ok(())
+    }
+}
+
+/// Orders the prompts so the ones in `depends_on` are asked first, otherwise
+/// keeping the declared order. Dependencies which are not prompts, e.g. the
+/// project name, are ignored.
+pub fn sort_prompts(prompts: Vec<PromptType>) -> Result<Vec<PromptType>> {
+    let names = prompts
+        .iter()
+        .map(|p| p.name().to_string())
+        .collect::<Vec<_>>();
+    let indices = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.clone(), i))
+        .collect::<HashMap<_, _>>();
+    let mut pending = prompts.into_iter().map(Some).collect::<Vec<_>>();
+    let mut asked = vec![false; pending.len()];
+    let mut sorted = Vec::with_capacity(pending.len());
+
+    while sorted.len() < pending.len() {
+        // the first prompt whose dependencies are all asked
+        let next = (0..pending.len()).find(|&i| {
+            !asked[i]
+                && pending[i]
+                    .as_ref()
+                    .unwrap()
+                    .depends_on()
+                    .iter()
+                    .all(|dep| indices.get(dep).is_none_or(|&j| asked[j]))
+        });
+        match next {
+            Some(i) => {
+                asked[i] = true;
+                sorted.push(pending[i].take().unwrap());
+            }
+            None => {
+                let cycle = names
+                    .into_iter()
+                    .zip(asked)
+                    .filter(|(_, asked)| !asked)
+                    .map(|(name, _)| name)
+                    .collect();
+                return Err(Error::PromptDependencyCycle(cycle));
+            }
+        }
+    }
+
+    Ok(sorted)
+}
+
+fn invalid_answer(name: &str, value: &str, reason: impl Into<String>) -> Error {
+    Error::InvalidAnswer {
+        name: name.to_string(),
+        value: value.to_string(),
+        reason: reason.into(),
+    }
+}
+
+/// Runs the `validate_command` of a prompt for `value`. The command is split
+/// into arguments before `{{ value }}` is rendered into each of them, and no
+/// shell is involved, so the value can't inject other commands.
+fn run_validate_command(command: &str, value: &str) -> std::result::Result<(), String> {
+    let mut context = Context::new();
+    context.insert("value", value);
+    // whitespace inside of tags like `{{ value }}` doesn't split arguments
+    let args = regex::Regex::new(r"(?:\{\{.*?\}\}|\{%.*?%\}|\S)+")
+        .unwrap()
+        .find_iter(command)
+        .map(|arg| arg.as_str())
+        .map(|arg| Tera::one_off(arg, &context, false).map_err(|e| e.to_string()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| "validate_command is empty".to_string())?;
+
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("cannot run `{}`: {}", program, e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if stderr.is_empty() {
+        Err(format!("`{}` failed with {}", program, output.status))
+    } else {
+        Err(stderr)
+    }
+}
+
+// a `String` always parses, only number prompts can fail here
+fn parse_answer<T: FromStr>(name: &str, value: &str) -> Result<T> {
+    value
+        .trim()
+        .parse::<T>()
+        .map_err(|_| invalid_answer(name, value, "expected a number"))
+}
+
+/// Reads one choice per line from `choices_file`, skipping blank lines. The
+/// file should be in the template.
+fn read_choices_file<T: FromStr>(template_dir: &Path, choices_file: &str) -> Result<Vec<T>> {
+    let path = template_dir.join(choices_file);
+    let not_found = |e| Error::PathNotFound {
+        source: e,
+        path: path.clone(),
+    };
+    let file = template_file(template_dir, choices_file)
+        .map_err(not_found)?
+        .ok_or_else(|| Error::InvalidChoicesFile {
+            path: path.clone(),
+            reason: "it is outside of the template".into(),
+        })?;
+    let content = read_to_string(file).map_err(not_found)?;
+    let choices = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.parse::<T>().map_err(|_| Error::InvalidChoicesFile {
+                path: path.clone(),
+                reason: format!("invalid choice '{}'", line),
+            })
+        })
+        .collect::<Result<Vec<T>>>()?;
+    if choices.is_empty() {
+        return Err(Error::InvalidChoicesFile {
+            path,
+            reason: "no choice in it".into(),
+        });
+    }
+
+    Ok(choices)
+}
+
+/// The variable of the only templated dir in the template root, e.g.
+/// `project` for `{{ project }}`, a likely `project_var_name` when the project
+/// dir is not found.
+pub fn suggest_project_var_name(template_dir: &Path) -> Option<String> {
+    let mut names = std::fs::read_dir(template_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let name = file_name
+                .to_str()?
+                .strip_prefix("{{")?
+                .strip_suffix("}}")?
+                .trim();
+            (!name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+                .then(|| name.to_string())
+        });
+    match (names.next(), names.next()) {
+        (Some(name), None) => Some(name),
+        _ => None,
+    }
+}
+
+/// Where the value of a prompt comes from, from the highest precedence to the
+/// lowest. The values which answer a prompt win over the ones which are only
+/// offered as its default, e.g. `author=bob` answers `author` even when
+/// `--define author=ci` is given too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ValueSource {
+    /// `key=value` on the command line.
+    ExtraContext,
+    /// `PETRIDISH_VAR_<NAME>`.
+    Env,
+    /// `--define`/`--define-json`.
+    Define,
+    /// `--answers` or the answers remembered by `--remember`.
+    Answers,
+    /// The declared default of the prompt.
+    Default,
+}
+
+impl ValueSource {
+    /// Whether a value from here answers the prompt without asking, the
+    /// others are only offered as its default. Defines never skip a prompt.
+    pub fn skips_prompt(self) -> bool {
+        matches!(self, ValueSource::ExtraContext | ValueSource::Env)
+    }
+}
+
+/// The values given for prompts besides asking for them, resolved by the
+/// precedence of [`ValueSource`].
+#[derive(Debug, Default, Clone)]
+pub struct PromptValues {
+    pub defines: HashMap<String, tera::Value>,
+    pub extra_context: HashMap<String, String>,
+    pub answers: serde_json::Map<String, tera::Value>,
+    /// The `PETRIDISH_VAR_<NAME>` variables by variable name.
+    pub env: HashMap<String, String>,
+}
+
+impl PromptValues {
+    /// Takes the `PETRIDISH_VAR_<NAME>` variables of the environment.
+    pub fn with_env(mut self) -> Self {
+        self.env = std::env::vars()
+            .filter(|(key, _)| key.starts_with("PETRIDISH_VAR_"))
+            .collect();
+        self
+    }
+
+    /// The given value of the prompt `name` of the highest precedence, `None`
+    /// when it is left to its default.
+    pub fn given(&self, name: &str) -> Option<(ValueSource, tera::Value)> {
+        match self.answer(name) {
+            Some((source, value)) => Some((source, value.into())),
+            None => self.given_default(name),
+        }
+    }
+
+    /// The value of `prompt` of the highest precedence, falling back to its
+    /// declared default rendered against the context.
+    pub fn resolve(
+        &self,
+        prompt: &PromptType,
+        context: &Context,
+    ) -> Result<Option<(ValueSource, tera::Value)>> {
+        match self.given(prompt.name()) {
+            Some(given) => Ok(Some(given)),
+            None => Ok(prompt
+                .default_value(context)?
+                .map(|default| (ValueSource::Default, default))),
+        }
+    }
+
+    /// The given value of the prompt `name` which answers it without asking,
+    /// as the string it is given as.
+    pub fn answer(&self, name: &str) -> Option<(ValueSource, String)> {
+        if let Some(value) = self.extra_context.get(name) {
+            return Some((ValueSource::ExtraContext, value.clone()));
+        }
+        self.env
+            .get(&env_var(name))
+            .map(|value| (ValueSource::Env, value.clone()))
+    }
+
+    /// The given value of the prompt `name` which is only offered as its
+    /// default, e.g. when it isn't answered otherwise or its answer doesn't fit.
+    pub fn given_default(&self, name: &str) -> Option<(ValueSource, tera::Value)> {
+        if let Some(value) = self.defines.get(name) {
+            return Some((ValueSource::Define, value.clone()));
+        }
+        self.answers
+            .get(name)
+            .map(|value| (ValueSource::Answers, value.clone()))
+    }
+}
+
+/// The environment variable answering the prompt `name`.
+fn env_var(name: &str) -> String {
+    format!("PETRIDISH_VAR_{}", name.to_uppercase())
+}
+
+/// Calls `f` with the prompts and their follow-up prompts.
+fn for_each_prompt<'a>(
+    prompts: impl IntoIterator<Item = &'a PromptType>,
+    f: &mut dyn FnMut(&'a PromptType),
+) {
+    for prompt in prompts {
+        f(prompt);
+        for_each_prompt(follow_ups(prompt), f);
+    }
+}
+
+/// The follow-up prompts of a select.
+fn follow_ups(prompt: &PromptType) -> Vec<&PromptType> {
+    match prompt {
+        PromptType::String(StringPrompt::Select(select)) => select.follow_up.values().collect(),
+        PromptType::Number(NumberPrompt::Select(select)) => select.follow_up.values().collect(),
+        _ => vec![],
+    }
+}
+
+fn remember_answers<'a>(
+    prompts: impl IntoIterator<Item = &'a mut PromptType>,
+    answers: &serde_json::Map<String, tera::Value>,
+) {
+    for prompt in prompts {
+        if let Some(answer) = answers.get(prompt.name()) {
+            prompt.set_default(answer);
+        }
+        match prompt {
+            PromptType::String(StringPrompt::Select(select)) => {
+                remember_answers(select.follow_up.values_mut(), answers)
+            }
+            PromptType::Number(NumberPrompt::Select(select)) => {
+                remember_answers(select.follow_up.values_mut(), answers)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A template rendering to `value` as it is.
+fn literal_template(value: &str) -> String {
+    if ["{{", "{%", "{#"].iter().any(|d| value.contains(d)) {
+        format!("{{% raw %}}{}{{% endraw %}}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// The text of an answer as typed, e.g. `rust` rather than `"rust"`.
+fn answer_text(value: &tera::Value) -> String {
+    match value {
+        tera::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn parse_choice<T>(name: &str, value: &str, choices: &[T]) -> Result<T>
+where
+    T: FromStr + PartialEq + Display,
+{
+    let choice = parse_answer::<T>(name, value)?;
+    if !choices.contains(&choice) {
+        let choices = choices.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        return Err(invalid_answer(
+            name,
+            value,
+            format!("expected one of {}", choices.join(", ")),
+        ));
+    }
+
+    Ok(choice)
 }
 
-#[derive(Deserialize, Debug, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "lowercase")]
 #[enum_dispatch(Prompt)]
 pub enum PromptType {
     String(StringPrompt),
     Number(NumberPrompt),
     Bool(BoolPrompt),
+    File(FileInput),
+    Path(PathInput),
+}
+
+/// `PromptType` as written in `petridish.toml`, which is tagged by `type`.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum TaggedPromptType {
+    String(StringPrompt),
+    Number(NumberPrompt),
+    Bool(BoolPrompt),
+    File(FileInput),
+    Path(PathInput),
+}
+
+/// A prompt can be written as just its name, which is a string input, e.g.
+/// `prompts = ["author", { name = "age", type = "number" }]`.
+#[allow(dead_code, clippy::large_enum_variant)]
+#[derive(JsonSchema)]
+#[serde(untagged)]
+enum PromptItem {
+    Name(String),
+    Prompt(PromptType),
+}
+
+impl<'de> Deserialize<'de> for PromptType {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let prompt = match toml::Value::deserialize(deserializer)? {
+            toml::Value::String(name) => {
+                return Ok(PromptType::String(StringPrompt::Input(StringInput {
+                    name,
+                    section: None,
+                    depends_on: vec![],
+                    prompt: None,
+                    default: None,
+                    regex: None,
+                    validate_command: None,
+                    secret: false,
+                    transform: None,
+                    required_when: None,
+                })))
+            }
+            value => TaggedPromptType::deserialize(value).map_err(serde::de::Error::custom)?,
+        };
+
+        Ok(match prompt {
+            TaggedPromptType::String(prompt) => PromptType::String(prompt),
+            TaggedPromptType::Number(prompt) => PromptType::Number(prompt),
+            TaggedPromptType::Bool(prompt) => PromptType::Bool(prompt),
+            TaggedPromptType::File(prompt) => PromptType::File(prompt),
+            TaggedPromptType::Path(prompt) => PromptType::Path(prompt),
+        })
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
 #[serde(untagged)]
 #[enum_dispatch(Prompt)]
 pub enum StringPrompt {
@@ -70,7 +922,7 @@ pub enum StringPrompt {
     Input(StringInput),
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Serialize, Debug, Clone, PartialEq, JsonSchema)]
 #[serde(untagged)]
 #[enum_dispatch(Prompt)]
 pub enum NumberPrompt {
@@ -79,31 +931,176 @@ pub enum NumberPrompt {
     Input(NumberInput),
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 #[serde(untagged)]
 #[enum_dispatch(Prompt)]
 pub enum BoolPrompt {
     Confirm(Confirm),
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+type PromptParser<T> = fn(toml::Value) -> std::result::Result<T, toml::de::Error>;
+
+// The prompt kinds are picked by their keys instead of trying each one
+// like `#[serde(untagged)]` does, so that an unknown field is reported by
+// the matched kind rather than as "data did not match any variant".
+fn deserialize_prompt<'de, D, T>(
+    deserializer: D,
+    kind: impl FnOnce(&toml::value::Table) -> PromptParser<T>,
+) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut value = toml::Value::deserialize(deserializer)?;
+    let table = value
+        .as_table_mut()
+        .ok_or_else(|| serde::de::Error::custom("prompt should be a table"))?;
+    // `multi = false` is the same as leaving it out
+    if table.get("multi") == Some(&toml::Value::Boolean(false)) {
+        table.remove("multi");
+    }
+    let parse = kind(table);
+    parse(value).map_err(serde::de::Error::custom)
+}
+
+/// Checks the `regex` of a prompt when loading the config rather than
+/// failing on the first answer.
+fn deserialize_regex<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pattern = Option::<String>::deserialize(deserializer)?;
+    if let Some(pattern) = &pattern {
+        regex::Regex::new(pattern)
+            .map_err(|e| serde::de::Error::custom(format!("invalid regex '{}': {}", pattern, e)))?;
+    }
+    Ok(pattern)
+}
+
+impl<'de> Deserialize<'de> for StringPrompt {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_prompt(deserializer, |table| {
+            if table.contains_key("multi") {
+                |v| MultiSelect::deserialize(v).map(Self::MultiSelect)
+            } else if ["choices", "range", "choices_file"]
+                .iter()
+                .any(|key| table.contains_key(*key))
+            {
+                |v| Select::deserialize(v).map(Self::Select)
+            } else {
+                |v| StringInput::deserialize(v).map(Self::Input)
+            }
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for NumberPrompt {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_prompt(deserializer, |table| {
+            if table.contains_key("multi") {
+                |v| MultiSelect::deserialize(v).map(Self::MultiSelect)
+            } else if ["choices", "range", "choices_file"]
+                .iter()
+                .any(|key| table.contains_key(*key))
+            {
+                |v| Select::deserialize(v).map(Self::Select)
+            } else {
+                |v| NumberInput::deserialize(v).map(Self::Input)
+            }
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for BoolPrompt {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_prompt(deserializer, |_| {
+            |v| Confirm::deserialize(v).map(Self::Confirm)
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct StringInput {
     name: String,
+    section: Option<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
     prompt: Option<String>,
     default: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_regex")]
     regex: Option<String>,
+    /// A command run with the entered value, e.g. `check-name {{ value }}`,
+    /// a non-zero exit rejects the value with the command's stderr.
+    validate_command: Option<String>,
+    /// Asks without echoing the value, which is masked in the summary of
+    /// `--format json`.
+    #[serde(default)]
+    secret: bool,
+    /// Post-processes the answer with the raw answer as `value`, e.g.
+    /// `{{ value | snake_case }}`, the result is stored instead.
+    transform: Option<String>,
+    /// A predicate on the answers so far, e.g. `{{ use_api }}`, an empty
+    /// value is rejected when it holds and accepted otherwise.
+    required_when: Option<String>,
 }
 
-impl Prompt for StringInput {
-    fn prompt(self, context: &mut Context) -> Result<()> {
-        let StringInput {
-            name,
-            prompt,
-            default,
+/// The message shown for a prompt, rendered with the answers so far, e.g.
+/// `What port should {{ service_name }} listen on?`. A message which cannot be
+/// rendered is shown as it is.
+fn prompt_message(prompt: Option<&str>, name: &str, context: &Context) -> String {
+    match prompt {
+        Some(prompt) => Tera::one_off(prompt, context, false).unwrap_or_else(|_| prompt.into()),
+        None => name.into(),
+    }
+}
+
+/// The answer as stored, i.e. rendered by `transform` with the raw answer as
+/// `value` if any.
+fn transform_answer(transform: Option<&str>, value: String, context: &Context) -> Result<String> {
+    let Some(transform) = transform else {
+        return Ok(value);
+    };
+    let mut context = context.clone();
+    context.insert("value", &value);
+    let mut tera = Tera::default();
+    register_filters(&mut tera);
+    tera.render_str(transform, &context)
+        .map_err(config_render_error)
+}
+
+impl StringInput {
+    fn is_required(&self, context: &Context) -> Result<bool> {
+        match &self.required_when {
+            Some(predicate) => predicate_holds(predicate, context),
+            None => Ok(false),
+        }
+    }
+}
+
+impl Prompt for StringInput {
+    fn prompt(self, context: &mut Context) -> Result<()> {
+        let required = self.is_required(context)?;
+        let StringInput {
+            name,
+            prompt,
+            default,
             regex,
+            validate_command,
+            secret,
+            transform,
+            ..
         } = self;
 
-        let prompt = prompt.unwrap_or_else(|| name.clone());
+        let prompt = prompt_message(prompt.as_deref(), &name, context);
         let mut prompt = inquire::Text::new(&prompt);
         let prompt_default = default.map(|d| {
             let mut tera = Tera::default();
@@ -111,9 +1108,11 @@ impl Prompt for StringInput {
         });
         prompt.default = prompt_default.as_deref();
 
-        let value = if let Some(pattern) = &regex {
-            let help_msg = format!("should match regex '{}'", pattern);
-            let regex = regex::Regex::new(pattern).unwrap();
+        let help_msg = regex
+            .as_ref()
+            .map(|pattern| format!("should match regex '{}'", pattern));
+        if let Some(pattern) = &regex {
+            let regex = regex::Regex::new(pattern).expect("checked when loading the config");
             let validator = move |v: &'_ str| {
                 if regex.is_match(v) {
                     Ok(Validation::Valid)
@@ -125,21 +1124,113 @@ impl Prompt for StringInput {
             };
 
             prompt.validators.push(Box::new(validator));
-            prompt.help_message = Some(&help_msg);
-            prompt.prompt()?
+            prompt.help_message = help_msg.as_deref();
+        }
+        if let Some(command) = validate_command {
+            let validator = move |v: &'_ str| match run_validate_command(&command, v) {
+                Ok(()) => Ok(Validation::Valid),
+                Err(message) => Ok(Validation::Invalid(message.into())),
+            };
+            prompt.validators.push(Box::new(validator));
+        }
+        // an empty value takes the default, see below for secrets
+        if required && prompt.default.is_none_or(|d| d.trim().is_empty()) {
+            prompt.validators.push(Box::new(|v: &'_ str| {
+                if v.trim().is_empty() {
+                    Ok(Validation::Invalid("a value is required".into()))
+                } else {
+                    Ok(Validation::Valid)
+                }
+            }));
+        }
+        let value = if secret {
+            // a password prompt has no default, an empty value takes it
+            let value = inquire::Password::new(prompt.message)
+                .with_validators(&prompt.validators)
+                .with_display_mode(PasswordDisplayMode::Masked)
+                .prompt()?;
+            match prompt.default {
+                Some(default) if value.is_empty() => default.to_string(),
+                _ => value,
+            }
         } else {
             prompt.prompt()?
         };
+        let value = transform_answer(transform.as_deref(), value, context)?;
 
         context.insert(name, &value);
 
         Ok(())
     }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
+
+    fn answer(&self, value: &str, context: &mut Context) -> Result<()> {
+        if value.trim().is_empty() && self.is_required(context)? {
+            return Err(invalid_answer(&self.name, value, "a value is required"));
+        }
+        if let Some(pattern) = &self.regex {
+            let regex = regex::Regex::new(pattern).expect("checked when loading the config");
+            if !regex.is_match(value) {
+                return Err(invalid_answer(
+                    &self.name,
+                    value,
+                    format!("should match regex '{}'", pattern),
+                ));
+            }
+        }
+        if let Some(command) = &self.validate_command {
+            run_validate_command(command, value)
+                .map_err(|message| invalid_answer(&self.name, value, message))?;
+        }
+
+        let value = transform_answer(self.transform.as_deref(), value.to_string(), context)?;
+        context.insert(&self.name, &value);
+        Ok(())
+    }
+
+    fn localize(&mut self, locale: &Locale) {
+        if let Some(message) = locale.message(&self.name) {
+            self.prompt = Some(message.to_string());
+        }
+    }
+
+    fn default_value(&self, context: &Context) -> Result<Option<tera::Value>> {
+        match &self.default {
+            Some(default) => Ok(Some(
+                Tera::default()
+                    .render_str(default, context)
+                    .map_err(config_render_error)?
+                    .into(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn set_default(&mut self, value: &tera::Value) {
+        if let Some(value) = value.as_str() {
+            self.default = Some(literal_template(value));
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct NumberInput {
     name: String,
+    section: Option<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
     prompt: Option<String>,
     default: Option<f64>,
     min: Option<f64>,
@@ -148,7 +1239,7 @@ pub struct NumberInput {
 
 impl Prompt for NumberInput {
     fn prompt(self, context: &mut Context) -> Result<()> {
-        let prompt = self.prompt.unwrap_or_else(|| self.name.clone());
+        let prompt = prompt_message(self.prompt.as_deref(), &self.name, context);
         let default = self.default.or(self.min).unwrap_or_default();
 
         let value = match (self.min, self.max) {
@@ -200,22 +1291,120 @@ impl Prompt for NumberInput {
         context.insert(self.name, &value);
         Ok(())
     }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
+
+    fn answer(&self, value: &str, context: &mut Context) -> Result<()> {
+        let number = parse_answer::<f64>(&self.name, value)?;
+        if self.min.is_some_and(|min| number < min) || self.max.is_some_and(|max| number > max) {
+            return Err(invalid_answer(&self.name, value, "out of range"));
+        }
+
+        context.insert(&self.name, &number);
+        Ok(())
+    }
+
+    fn localize(&mut self, locale: &Locale) {
+        if let Some(message) = locale.message(&self.name) {
+            self.prompt = Some(message.to_string());
+        }
+    }
+
+    fn default_value(&self, _context: &Context) -> Result<Option<tera::Value>> {
+        Ok(self.default.map(tera::Value::from))
+    }
+
+    fn set_default(&mut self, value: &tera::Value) {
+        if let Some(value) = value.as_f64() {
+            self.default = Some(value);
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Select<T> {
     name: String,
+    section: Option<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
     prompt: Option<String>,
+    #[serde(
+        default,
+        alias = "range",
+        deserialize_with = "deserialize_choices",
+        bound(deserialize = "T: Deserialize<'de> + FromRange")
+    )]
     choices: Vec<T>,
+    choices_file: Option<String>,
     default: Option<T>,
+    page_size: Option<usize>,
+    #[serde(skip)]
+    labels: HashMap<String, String>,
+    /// Prompts asked only when their choice is selected, e.g. a free text
+    /// license after picking `custom`.
+    #[serde(default)]
+    follow_up: BTreeMap<String, PromptType>,
+    /// Predicates of choices which are only shown when they hold, e.g.
+    /// `"eu-west-1" = "{{ cloud == 'aws' }}"`.
+    #[serde(default)]
+    when: BTreeMap<String, String>,
+}
+
+impl<T: Serialize + PartialEq> Select<T> {
+    /// Whether `choice` is shown for the answers in `context`, i.e. it has no
+    /// `when` predicate or it holds.
+    fn is_visible(&self, choice: &T, context: &Context) -> Result<bool> {
+        match choice_key(&self.when, &tera::to_value(choice).unwrap()) {
+            Some(key) => predicate_holds(&self.when[key], context),
+            None => Ok(true),
+        }
+    }
+
+    /// The declared default if it's shown, or else the first shown choice.
+    fn visible_default(&self, context: &Context) -> Result<Option<&T>> {
+        if let Some(default) = &self.default {
+            if self.choices.contains(default) && self.is_visible(default, context)? {
+                return Ok(Some(default));
+            }
+        }
+        for choice in &self.choices {
+            if self.is_visible(choice, context)? {
+                return Ok(Some(choice));
+            }
+        }
+        Ok(None)
+    }
+
+    fn resolve_default(&self, context: &Context) -> Result<tera::Value> {
+        Ok(tera::to_value(self.visible_default(context)?).unwrap())
+    }
 }
 
 impl<T> Prompt for Select<T>
 where
-    T: Serialize + PartialEq + Display,
+    T: Serialize + PartialEq + Display + FromStr,
 {
-    fn prompt(self, context: &mut Context) -> Result<()> {
-        let prompt = self.prompt.unwrap_or_else(|| self.name.clone());
+    fn prompt(mut self, context: &mut Context) -> Result<()> {
+        let mut choices = vec![];
+        for choice in std::mem::take(&mut self.choices) {
+            if self.is_visible(&choice, context)? {
+                choices.push(choice);
+            }
+        }
+        self.choices = choices;
+
+        let prompt = prompt_message(self.prompt.as_deref(), &self.name, context);
         let default: usize = match self.default {
             Some(default) => self
                 .choices
@@ -224,136 +1413,768 @@ where
                 .unwrap_or_default(),
             None => 0,
         };
-        let value = inquire::Select::new(&prompt, self.choices)
-            .with_starting_cursor(default)
-            .prompt()?;
+        let choices = Labeled::wrap(self.choices, &self.labels);
+        let fuzzy = choices.len() > FUZZY_FILTER_THRESHOLD;
+        let mut select = inquire::Select::new(&prompt, choices).with_starting_cursor(default);
+        if let Some(page_size) = self.page_size {
+            select = select.with_page_size(page_size);
+        }
+        if fuzzy {
+            select = select.with_filter(&fuzzy_filter);
+        }
+        let value = select.prompt()?;
 
-        context.insert(self.name, &value);
+        context.insert(self.name, &value.value);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
+
+    fn answer(&self, value: &str, context: &mut Context) -> Result<()> {
+        let choice = parse_choice(&self.name, value, &self.choices)?;
+        if !self.is_visible(&choice, context)? {
+            return Err(invalid_answer(
+                &self.name,
+                value,
+                "the choice is not available for the other answers",
+            ));
+        }
+        context.insert(&self.name, &choice);
+        Ok(())
+    }
+
+    fn localize(&mut self, locale: &Locale) {
+        if let Some(message) = locale.message(&self.name) {
+            self.prompt = Some(message.to_string());
+        }
+        self.labels = locale.choice_labels(&self.name);
+        for follow_up in self.follow_up.values_mut() {
+            follow_up.localize(locale);
+        }
+    }
+
+    fn load_choices(&mut self, template_dir: &Path) -> Result<()> {
+        if let Some(choices_file) = &self.choices_file {
+            self.choices = read_choices_file(template_dir, choices_file)?;
+        }
+        for follow_up in self.follow_up.values_mut() {
+            follow_up.load_choices(template_dir)?;
+        }
         Ok(())
     }
+
+    fn default_value(&self, context: &Context) -> Result<Option<tera::Value>> {
+        if self.default.is_none() {
+            return Ok(None);
+        }
+        self.resolve_default(context).map(Some)
+    }
+
+    fn set_default(&mut self, value: &tera::Value) {
+        if let Ok(choice) = parse_choice(&self.name, &answer_text(value), &self.choices) {
+            self.default = Some(choice);
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct MultiSelect<T> {
     multi: LiteralTrue,
     name: String,
+    section: Option<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
     prompt: Option<String>,
+    #[serde(
+        default,
+        alias = "range",
+        deserialize_with = "deserialize_choices",
+        bound(deserialize = "T: Deserialize<'de> + FromRange")
+    )]
     choices: Vec<T>,
-    default: Option<Vec<T>>,
+    choices_file: Option<String>,
+    default: Option<Vec<Templated<T>>>,
     #[serde(default)]
     emptyable: bool,
+    min_choices: Option<usize>,
+    max_choices: Option<usize>,
+    page_size: Option<usize>,
+    #[serde(skip)]
+    labels: HashMap<String, String>,
+}
+
+impl<T> MultiSelect<T>
+where
+    T: PartialEq + Display + FromStr,
+{
+    /// Returns the indices of the choices selected by default, with the
+    /// defaults rendered against the context first.
+    fn default_indices(&self, context: &Context) -> Result<Vec<usize>> {
+        let defaults = match &self.default {
+            Some(default) => default
+                .iter()
+                .map(|d| d.render(context))
+                .collect::<Result<Vec<_>>>()?,
+            None => return Ok(vec![]),
+        };
+
+        Ok(self
+            .choices
+            .iter()
+            .enumerate()
+            .filter(|(_, choice)| defaults.iter().any(|d| d.as_ref() == Some(*choice)))
+            .map(|(idx, _)| idx)
+            .collect())
+    }
+
+    fn resolve_default(&self, context: &Context) -> Result<tera::Value>
+    where
+        T: Serialize,
+    {
+        let selections = self
+            .default_indices(context)?
+            .into_iter()
+            .map(|idx| &self.choices[idx])
+            .collect::<Vec<_>>();
+        Ok(tera::to_value(selections).unwrap())
+    }
 }
 
 impl<T> Prompt for MultiSelect<T>
 where
-    T: Serialize + PartialEq + Display,
+    T: Serialize + PartialEq + Display + FromStr,
 {
     fn prompt(self, context: &mut Context) -> Result<()> {
-        let prompt = self.prompt.unwrap_or_else(|| self.name.clone());
-        let defaults = {
-            match self.default {
-                Some(default) => self
-                    .choices
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, choice)| default.contains(choice))
-                    .map(|(idx, _)| idx)
-                    .collect(),
-                None => vec![],
-            }
-        };
+        let defaults = self.default_indices(context)?;
+        let prompt = prompt_message(self.prompt.as_deref(), &self.name, context);
 
-        let selections = inquire::MultiSelect::new(&prompt, self.choices)
+        let (emptyable, min_choices, max_choices) =
+            (self.emptyable, self.min_choices, self.max_choices);
+        let choices = Labeled::wrap(self.choices, &self.labels);
+        let fuzzy = choices.len() > FUZZY_FILTER_THRESHOLD;
+        let mut select = inquire::MultiSelect::new(&prompt, choices)
             .with_default(&defaults)
-            .with_validator(|a: &[ListOption<&T>]| {
-                if a.is_empty() {
-                    return Ok(Validation::Invalid("No item is selected".into()));
+            .with_validator(move |a: &[ListOption<&Labeled<T>>]| {
+                match check_selection_count(a.len(), emptyable, min_choices, max_choices) {
+                    Some(reason) => Ok(Validation::Invalid(reason.into())),
+                    None => Ok(Validation::Valid),
                 }
-
-                Ok(Validation::Valid)
-            })
-            .prompt()?;
+            });
+        if let Some(page_size) = self.page_size {
+            select = select.with_page_size(page_size);
+        }
+        if fuzzy {
+            select = select.with_filter(&fuzzy_filter);
+        }
+        let selections = select
+            .prompt()?
+            .into_iter()
+            .map(|s| s.value)
+            .collect::<Vec<T>>();
 
         context.insert(self.name, &selections);
         Ok(())
     }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
+
+    /// Takes the selections separated by commas, e.g. `running,swimming`.
+    fn answer(&self, value: &str, context: &mut Context) -> Result<()> {
+        let selections = value
+            .split(',')
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| parse_choice(&self.name, v, &self.choices))
+            .collect::<Result<Vec<T>>>()?;
+        if let Some(reason) = check_selection_count(
+            selections.len(),
+            self.emptyable,
+            self.min_choices,
+            self.max_choices,
+        ) {
+            return Err(invalid_answer(&self.name, value, reason));
+        }
+
+        context.insert(&self.name, &selections);
+        Ok(())
+    }
+
+    fn localize(&mut self, locale: &Locale) {
+        if let Some(message) = locale.message(&self.name) {
+            self.prompt = Some(message.to_string());
+        }
+        self.labels = locale.choice_labels(&self.name);
+    }
+
+    fn load_choices(&mut self, template_dir: &Path) -> Result<()> {
+        if let Some(choices_file) = &self.choices_file {
+            self.choices = read_choices_file(template_dir, choices_file)?;
+        }
+        Ok(())
+    }
+
+    fn default_value(&self, context: &Context) -> Result<Option<tera::Value>> {
+        if self.default.is_none() {
+            return Ok(None);
+        }
+
+        self.resolve_default(context).map(Some)
+    }
+
+    fn set_default(&mut self, value: &tera::Value) {
+        let Some(values) = value.as_array() else {
+            return;
+        };
+        let choices = values
+            .iter()
+            .map(|value| parse_choice(&self.name, &answer_text(value), &self.choices))
+            .collect::<Result<Vec<_>>>();
+        if let Ok(choices) = choices {
+            self.default = Some(choices.into_iter().map(Templated::Value).collect());
+        }
+    }
+}
+
+/// Returns why `count` selections of a multi select are too few or too many,
+/// if they are. No selection at all is fine for `emptyable` ones.
+fn check_selection_count(
+    count: usize,
+    emptyable: bool,
+    min_choices: Option<usize>,
+    max_choices: Option<usize>,
+) -> Option<String> {
+    match (min_choices, max_choices) {
+        _ if count == 0 && emptyable => None,
+        _ if count == 0 => Some("no item is selected".into()),
+        (Some(min), _) if count < min => Some(format!("select at least {} items", min)),
+        (_, Some(max)) if count > max => Some(format!("select at most {} items", max)),
+        _ => None,
+    }
+}
+
+/// Returns the follow-up prompt of the choice answered for `name`, if any.
+pub fn selected_follow_up(
+    mut follow_ups: BTreeMap<String, PromptType>,
+    name: &str,
+    context: &Context,
+) -> Option<PromptType> {
+    let key = choice_key(&follow_ups, context.get(name)?)?.clone();
+    follow_ups.remove(&key)
+}
+
+/// The key of `answer` in a map keyed by choices, e.g. `follow_up`. Number
+/// choices match their keys by value, e.g. `"8080"` for `8080.0`.
+fn choice_key<'a, V>(
+    by_choice: &'a BTreeMap<String, V>,
+    answer: &tera::Value,
+) -> Option<&'a String> {
+    by_choice.keys().find(|key| match answer {
+        tera::Value::String(answer) => *key == answer,
+        answer => key.trim().parse::<f64>().ok() == answer.as_f64(),
+    })
+}
+
+/// Whether a `when` predicate like `{{ cloud == 'aws' }}` holds, i.e. it
+/// renders to neither empty nor `false`.
+fn predicate_holds(predicate: &str, context: &Context) -> Result<bool> {
+    let rendered = Tera::one_off(predicate, context, false).map_err(config_render_error)?;
+    Ok(!matches!(rendered.trim(), "" | "false"))
+}
+
+/// Asks the prompt named `name` again through `ask`, e.g. to fix an answer
+/// before generating, along with the follow-up prompt of the new choice. The
+/// answers of follow-up prompts which are no longer chosen are dropped.
+pub fn edit_answer(
+    prompts: &[PromptType],
+    name: &str,
+    context: &mut Context,
+    ask: &mut dyn FnMut(&PromptType, &mut Context) -> Result<()>,
+) -> Result<()> {
+    fn ask_with_follow_up(
+        prompt: &PromptType,
+        context: &mut Context,
+        ask: &mut dyn FnMut(&PromptType, &mut Context) -> Result<()>,
+    ) -> Result<()> {
+        ask(prompt, context)?;
+        match prompt.follow_up(context) {
+            Some(follow_up) => ask_with_follow_up(follow_up, context, ask),
+            None => Ok(()),
+        }
+    }
+
+    let mut found = None;
+    for_each_prompt(prompts, &mut |prompt| {
+        if found.is_none() && prompt.name() == name {
+            found = Some(prompt);
+        }
+    });
+    let prompt = found.ok_or_else(|| Error::ArgsError(format!("unknown prompt `{}`", name)))?;
+    for_each_prompt(follow_ups(prompt), &mut |follow_up| {
+        context.remove(follow_up.name());
+    });
+    ask_with_follow_up(prompt, context, ask)
+}
+
+/// Returns the header to show before a prompt of `current` section when the
+/// previous prompt was in `previous` one, i.e. whenever a new section starts.
+pub fn section_header<'a>(previous: Option<&str>, current: Option<&'a str>) -> Option<&'a str> {
+    current.filter(|current| previous != Some(*current))
+}
+
+/// A value which can also be given as a template rendering to it, e.g.
+/// `default = ["{{ port }}"]` for a number multi select.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(untagged)]
+pub enum Templated<T> {
+    Value(T),
+    Template(String),
+}
+
+impl<T: Display + FromStr> Templated<T> {
+    /// Renders the value against the context, `None` if the result is not a
+    /// valid `T`.
+    fn render(&self, context: &Context) -> Result<Option<T>> {
+        let template = match self {
+            Templated::Value(value) => value.to_string(),
+            Templated::Template(template) => template.clone(),
+        };
+        let rendered = Tera::default()
+            .render_str(&template, context)
+            .map_err(config_render_error)?;
+        Ok(rendered.trim().parse().ok())
+    }
+}
+
+/// Selects with more choices than this filter them fuzzily while typing.
+const FUZZY_FILTER_THRESHOLD: usize = 10;
+
+/// Keeps the choices containing all characters of the filter in order,
+/// ignoring case, e.g. `uk` matches `United Kingdom`.
+fn fuzzy_filter<T>(filter: &str, _: &T, value: &str, _: usize) -> bool {
+    let mut value = value.chars().flat_map(char::to_lowercase);
+    filter
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .all(|c| value.any(|v| v == c))
+}
+
+/// A choice shown with its localized label, if any, while keeping the
+/// original value for the template context.
+struct Labeled<T> {
+    value: T,
+    label: Option<String>,
+}
+
+impl<T: Display> Labeled<T> {
+    fn wrap(choices: Vec<T>, labels: &HashMap<String, String>) -> Vec<Self> {
+        choices
+            .into_iter()
+            .map(|value| {
+                let label = labels.get(&value.to_string()).cloned();
+                Self { value, label }
+            })
+            .collect()
+    }
+}
+
+impl<T: Display> Display for Labeled<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{}", label),
+            None => write!(f, "{}", self.value),
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Confirm {
     pub name: String,
+    pub section: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
     pub prompt: Option<String>,
     #[serde(default)]
     pub default: bool,
+    /// Stored instead of `true` when confirmed, e.g. `enabled`.
+    pub true_value: Option<String>,
+    /// Stored instead of `false` when declined, e.g. `disabled`.
+    pub false_value: Option<String>,
+}
+
+impl Confirm {
+    /// The value stored for `answer`, `true_value`/`false_value` if given.
+    fn value(&self, answer: bool) -> tera::Value {
+        let mapped = if answer {
+            &self.true_value
+        } else {
+            &self.false_value
+        };
+        match mapped {
+            Some(mapped) => mapped.as_str().into(),
+            None => answer.into(),
+        }
+    }
 }
 
 impl Prompt for Confirm {
     fn prompt(self, context: &mut Context) -> Result<()> {
-        let prompt = self.prompt.unwrap_or_else(|| self.name.clone());
-        let value = inquire::Confirm::new(&prompt)
+        let prompt = prompt_message(self.prompt.as_deref(), &self.name, context);
+        let answer = inquire::Confirm::new(&prompt)
             .with_default(self.default)
             .prompt()?;
 
-        context.insert(self.name, &value);
+        context.insert(&self.name, &self.value(answer));
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::vec;
+    fn name(&self) -> &str {
+        &self.name
+    }
 
-    use super::*;
+    fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
 
-    #[test]
-    fn test_literal_number() {
-        let config = r#"
-        name="var"
-        prompt="hello"
-        type="number"
-        "#;
-        let parsed = toml::from_str::<PromptType>(config).unwrap();
-        let expected = PromptType::Number(NumberPrompt::Input(NumberInput {
-            name: "var".into(),
-            prompt: Some("hello".into()),
-            max: None,
-            min: None,
-            default: None,
-        }));
-        assert_eq!(parsed, expected);
+    fn depends_on(&self) -> &[String] {
+        &self.depends_on
     }
 
-    #[test]
-    fn test_literal_number_with_default() {
-        let config = r#"
-        name="var"
-        prompt="hello"
-        type="number"
-        default=1
-        "#;
-        let parsed = toml::from_str::<PromptType>(config).unwrap();
-        let expected = PromptType::Number(NumberPrompt::Input(NumberInput {
-            name: "var".into(),
-            prompt: Some("hello".into()),
-            max: None,
-            min: None,
-            default: Some(1_f64),
-        }));
-        assert_eq!(parsed, expected);
+    fn localize(&mut self, locale: &Locale) {
+        if let Some(message) = locale.message(&self.name) {
+            self.prompt = Some(message.to_string());
+        }
     }
 
-    #[test]
-    fn test_literal_number_with_min_and_max() {
-        let config = r#"
-        name="var"
-        prompt="hello"
-        type="number"
-        min=1
-        max=20
+    /// Takes `true`/`false`, `yes`/`no`, `y`/`n` or `1`/`0`, ignoring case.
+    fn answer(&self, value: &str, context: &mut Context) -> Result<()> {
+        let answer = match value.trim().to_lowercase().as_str() {
+            "true" | "yes" | "y" | "1" => true,
+            "false" | "no" | "n" | "0" => false,
+            _ => return Err(invalid_answer(&self.name, value, "expected a bool")),
+        };
+
+        context.insert(&self.name, &self.value(answer));
+        Ok(())
+    }
+
+    fn default_value(&self, _context: &Context) -> Result<Option<tera::Value>> {
+        Ok(Some(self.value(self.default)))
+    }
+
+    /// Takes a bool or one of `true_value`/`false_value`, e.g. a remembered
+    /// answer.
+    fn set_default(&mut self, value: &tera::Value) {
+        if let Some(value) = value.as_bool() {
+            self.default = value;
+        } else if value.as_str().is_some() && *value == self.value(true) {
+            self.default = true;
+        } else if value.as_str().is_some() && *value == self.value(false) {
+            self.default = false;
+        }
+    }
+}
+
+/// Asks for the path of an existing file, e.g. a config to transform, and
+/// takes the content of the file as the answer.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FileInput {
+    name: String,
+    section: Option<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
+    prompt: Option<String>,
+    /// The default path, support template render.
+    default: Option<String>,
+}
+
+impl Prompt for FileInput {
+    fn prompt(self, context: &mut Context) -> Result<()> {
+        let prompt = prompt_message(self.prompt.as_deref(), &self.name, context);
+        let default = self
+            .default
+            .as_ref()
+            .map(|d| Tera::one_off(d, context, false))
+            .transpose()
+            .map_err(config_render_error)?;
+        let mut text = inquire::Text::new(&prompt)
+            .with_suggester(&|input| Ok(path_suggestions(input, false)))
+            .with_validator(|path: &str| match read_file(path) {
+                Ok(_) => Ok(Validation::Valid),
+                Err(reason) => Ok(Validation::Invalid(reason.into())),
+            });
+        text.default = default.as_deref();
+        let path = text.prompt()?;
+
+        self.answer(&path, context)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
+
+    fn localize(&mut self, locale: &Locale) {
+        if let Some(message) = locale.message(&self.name) {
+            self.prompt = Some(message.to_string());
+        }
+    }
+
+    /// Takes the path of the file, relative to the current dir.
+    fn answer(&self, value: &str, context: &mut Context) -> Result<()> {
+        let content =
+            read_file(value).map_err(|reason| invalid_answer(&self.name, value, reason))?;
+        context.insert(&self.name, &content);
+        Ok(())
+    }
+
+    fn default_value(&self, context: &Context) -> Result<Option<tera::Value>> {
+        let Some(default) = &self.default else {
+            return Ok(None);
+        };
+        let path = Tera::one_off(default, context, false).map_err(config_render_error)?;
+        let content =
+            read_file(&path).map_err(|reason| invalid_answer(&self.name, &path, reason))?;
+        Ok(Some(content.into()))
+    }
+}
+
+/// Asks for a path, e.g. where to link something to, and takes the path
+/// itself as the answer, unlike `FileInput`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PathInput {
+    name: String,
+    section: Option<String>,
+    #[serde(default)]
+    depends_on: Vec<String>,
+    prompt: Option<String>,
+    /// The default path, support template render.
+    default: Option<String>,
+    /// Only takes dirs, and only suggests them.
+    #[serde(default)]
+    dir_only: bool,
+    /// Only takes paths which exist.
+    #[serde(default)]
+    must_exist: bool,
+}
+
+impl PathInput {
+    /// Checks `path` against `dir_only` and `must_exist`, the error says why
+    /// it doesn't fit.
+    fn check(&self, path: &str) -> std::result::Result<String, String> {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err("a path is required".into());
+        }
+        let exists = Path::new(path).exists();
+        if self.must_exist && !exists {
+            return Err(format!("'{}' doesn't exist", path));
+        }
+        if self.dir_only && exists && !Path::new(path).is_dir() {
+            return Err(format!("'{}' is not a dir", path));
+        }
+        Ok(path.to_string())
+    }
+}
+
+impl Prompt for PathInput {
+    fn prompt(self, context: &mut Context) -> Result<()> {
+        let prompt = prompt_message(self.prompt.as_deref(), &self.name, context);
+        let default = self
+            .default
+            .as_ref()
+            .map(|d| Tera::one_off(d, context, false))
+            .transpose()
+            .map_err(config_render_error)?;
+        let suggester = |input: &str| Ok(path_suggestions(input, self.dir_only));
+        let checked = self.clone();
+        let mut text = inquire::Text::new(&prompt)
+            .with_suggester(&suggester)
+            .with_validator(move |path: &str| match checked.check(path) {
+                Ok(_) => Ok(Validation::Valid),
+                Err(reason) => Ok(Validation::Invalid(reason.into())),
+            });
+        text.default = default.as_deref();
+        let path = text.prompt()?;
+
+        self.answer(&path, context)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
+
+    fn localize(&mut self, locale: &Locale) {
+        if let Some(message) = locale.message(&self.name) {
+            self.prompt = Some(message.to_string());
+        }
+    }
+
+    /// Takes the path as it is given, relative to the current dir.
+    fn answer(&self, value: &str, context: &mut Context) -> Result<()> {
+        let path = self
+            .check(value)
+            .map_err(|reason| invalid_answer(&self.name, value, reason))?;
+        context.insert(&self.name, &path);
+        Ok(())
+    }
+
+    fn default_value(&self, context: &Context) -> Result<Option<tera::Value>> {
+        let Some(default) = &self.default else {
+            return Ok(None);
+        };
+        let path = Tera::one_off(default, context, false).map_err(config_render_error)?;
+        let path = self
+            .check(&path)
+            .map_err(|reason| invalid_answer(&self.name, &path, reason))?;
+        Ok(Some(path.into()))
+    }
+
+    fn set_default(&mut self, value: &tera::Value) {
+        if let Some(path) = value.as_str() {
+            self.default = Some(path.to_string());
+        }
+    }
+}
+
+/// Reads the file answering a file prompt, the error says why it can't be.
+fn read_file(path: &str) -> std::result::Result<String, String> {
+    let path = Path::new(path.trim());
+    if !path.is_file() {
+        return Err(format!("'{}' is not a file", path.display()));
+    }
+    read_to_string(path).map_err(|e| format!("cannot read '{}': {}", path.display(), e))
+}
+
+/// The entries of the dir typed so far which start with the rest of `input`,
+/// e.g. `src/main.rs` and `src/` for `s`, dirs end with `/`. Only dirs are
+/// suggested with `dirs_only`.
+fn path_suggestions(input: &str, dirs_only: bool) -> Vec<String> {
+    let (dir, prefix) = match input.rfind(['/', '\\']) {
+        Some(idx) => input.split_at(idx + 1),
+        None => ("", input),
+    };
+    let Ok(entries) = std::fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return vec![];
+    };
+    let mut suggestions = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            if !name.starts_with(prefix) {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            if dirs_only && !is_dir {
+                return None;
+            }
+            let slash = if is_dir { "/" } else { "" };
+            Some(format!("{}{}{}", dir, name, slash))
+        })
+        .collect::<Vec<_>>();
+    suggestions.sort();
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_literal_number() {
+        let config = r#"
+        name="var"
+        prompt="hello"
+        type="number"
+        "#;
+        let parsed = toml::from_str::<PromptType>(config).unwrap();
+        let expected = PromptType::Number(NumberPrompt::Input(NumberInput {
+            name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            prompt: Some("hello".into()),
+            max: None,
+            min: None,
+            default: None,
+        }));
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_literal_number_with_default() {
+        let config = r#"
+        name="var"
+        prompt="hello"
+        type="number"
+        default=1
+        "#;
+        let parsed = toml::from_str::<PromptType>(config).unwrap();
+        let expected = PromptType::Number(NumberPrompt::Input(NumberInput {
+            name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            prompt: Some("hello".into()),
+            max: None,
+            min: None,
+            default: Some(1_f64),
+        }));
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_literal_number_with_min_and_max() {
+        let config = r#"
+        name="var"
+        prompt="hello"
+        type="number"
+        min=1
+        max=20
         "#;
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::Number(NumberPrompt::Input(NumberInput {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
             prompt: Some("hello".into()),
             min: Some(1_f64),
             max: Some(20_f64),
@@ -372,8 +2193,14 @@ mod tests {
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::String(StringPrompt::Input(StringInput {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
             prompt: Some("hello".into()),
             regex: None,
+            validate_command: None,
+            secret: false,
+            transform: None,
+            required_when: None,
             default: None,
         }));
         assert_eq!(parsed, expected);
@@ -390,8 +2217,14 @@ mod tests {
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::String(StringPrompt::Input(StringInput {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
             prompt: Some("hello".into()),
             regex: None,
+            validate_command: None,
+            secret: false,
+            transform: None,
+            required_when: None,
             default: Some("rust".into()),
         }));
         assert_eq!(parsed, expected);
@@ -408,13 +2241,30 @@ mod tests {
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::String(StringPrompt::Input(StringInput {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
             prompt: Some("hello".into()),
             regex: Some(".*".into()),
+            validate_command: None,
+            secret: false,
+            transform: None,
+            required_when: None,
             default: None,
         }));
         assert_eq!(parsed, expected);
     }
 
+    #[test]
+    fn test_invalid_regex() {
+        let config = r#"
+        name="var"
+        type="string"
+        regex="[a-z"
+        "#;
+        let err = toml::from_str::<PromptType>(config).unwrap_err();
+        assert!(err.to_string().contains("invalid regex '[a-z'"), "{}", err);
+    }
+
     #[test]
     fn test_confirm() {
         let config = r#"
@@ -425,8 +2275,12 @@ mod tests {
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::Bool(BoolPrompt::Confirm(Confirm {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
             prompt: Some("ok?".into()),
             default: false,
+            true_value: None,
+            false_value: None,
         }));
         assert_eq!(parsed, expected);
     }
@@ -442,12 +2296,56 @@ mod tests {
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::Bool(BoolPrompt::Confirm(Confirm {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
             prompt: Some("ok?".into()),
             default: true,
+            true_value: None,
+            false_value: None,
         }));
         assert_eq!(parsed, expected);
     }
 
+    #[test]
+    fn test_confirm_values() {
+        let config = r#"
+        name = "cache"
+        type = "bool"
+        default = true
+        true_value = "enabled"
+        false_value = "disabled"
+        "#;
+        let mut prompt = toml::from_str::<PromptType>(config).unwrap();
+        let mut context = Context::new();
+        assert_eq!(
+            prompt.default_value(&context).unwrap(),
+            Some("enabled".into())
+        );
+        prompt.answer("no", &mut context).unwrap();
+        assert_eq!(context.get("cache"), Some(&"disabled".into()));
+        prompt.answer("y", &mut context).unwrap();
+        assert_eq!(context.get("cache"), Some(&"enabled".into()));
+
+        // a remembered answer is mapped back
+        prompt.set_default(&"disabled".into());
+        assert_eq!(
+            prompt.default_value(&context).unwrap(),
+            Some("disabled".into())
+        );
+
+        // without the values the bool is stored
+        let prompt = toml::from_str::<PromptType>(
+            r#"
+            name = "cache"
+            type = "bool"
+            true_value = "enabled"
+            "#,
+        )
+        .unwrap();
+        prompt.answer("no", &mut context).unwrap();
+        assert_eq!(context.get("cache"), Some(&false.into()));
+    }
+
     #[test]
     fn test_number_select() {
         let config = r#"
@@ -459,9 +2357,16 @@ mod tests {
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::Number(NumberPrompt::Select(Select {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
             prompt: Some("age".into()),
             choices: vec![10_f64, 20_f64, 30_f64],
             default: None,
+            page_size: None,
+            labels: HashMap::new(),
+            follow_up: BTreeMap::new(),
+            when: BTreeMap::new(),
         }));
         assert_eq!(parsed, expected);
     }
@@ -478,9 +2383,16 @@ mod tests {
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::Number(NumberPrompt::Select(Select {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
             prompt: Some("age".into()),
             choices: vec![10_f64, 20_f64, 30_f64],
             default: Some(10_f64),
+            page_size: None,
+            labels: HashMap::new(),
+            follow_up: BTreeMap::new(),
+            when: BTreeMap::new(),
         }));
         assert_eq!(parsed, expected);
     }
@@ -496,9 +2408,16 @@ mod tests {
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::String(StringPrompt::Select(Select {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
             prompt: Some("name".into()),
             choices: vec!["a".into(), "b".into(), "c".into()],
             default: None,
+            page_size: None,
+            labels: HashMap::new(),
+            follow_up: BTreeMap::new(),
+            when: BTreeMap::new(),
         }));
         assert_eq!(parsed, expected);
     }
@@ -515,9 +2434,16 @@ mod tests {
         let parsed = toml::from_str::<PromptType>(config).unwrap();
         let expected = PromptType::String(StringPrompt::Select(Select {
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
             prompt: Some("name".into()),
             choices: vec!["a".into(), "b".into(), "c".into()],
             default: Some("a".into()),
+            page_size: None,
+            labels: HashMap::new(),
+            follow_up: BTreeMap::new(),
+            when: BTreeMap::new(),
         }));
         assert_eq!(parsed, expected);
     }
@@ -535,10 +2461,17 @@ mod tests {
         let expected = PromptType::Number(NumberPrompt::MultiSelect(MultiSelect {
             multi: LiteralTrue,
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
             prompt: Some("age".into()),
             choices: vec![10_f64, 20_f64, 30_f64],
             default: None,
             emptyable: false,
+            min_choices: None,
+            max_choices: None,
+            page_size: None,
+            labels: HashMap::new(),
         }));
         assert_eq!(parsed, expected);
     }
@@ -557,10 +2490,17 @@ mod tests {
         let expected = PromptType::Number(NumberPrompt::MultiSelect(MultiSelect {
             multi: LiteralTrue,
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
             prompt: Some("age".into()),
             choices: vec![10_f64, 20_f64, 30_f64],
-            default: Some(vec![10_f64]),
+            default: Some(vec![Templated::Value(10_f64)]),
             emptyable: false,
+            min_choices: None,
+            max_choices: None,
+            page_size: None,
+            labels: HashMap::new(),
         }));
         assert_eq!(parsed, expected);
     }
@@ -578,10 +2518,17 @@ mod tests {
         let expected = PromptType::String(StringPrompt::MultiSelect(MultiSelect {
             multi: LiteralTrue,
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
             prompt: Some("name".into()),
             choices: vec!["a".into(), "b".into(), "c".into()],
             default: None,
             emptyable: false,
+            min_choices: None,
+            max_choices: None,
+            page_size: None,
+            labels: HashMap::new(),
         }));
         assert_eq!(parsed, expected);
     }
@@ -600,114 +2547,825 @@ mod tests {
         let expected = PromptType::String(StringPrompt::MultiSelect(MultiSelect {
             multi: LiteralTrue,
             name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
             prompt: Some("name".into()),
             choices: vec!["a".into(), "b".into(), "c".into()],
-            default: Some(vec!["a".into()]),
+            default: Some(vec![Templated::Value("a".into())]),
             emptyable: false,
+            min_choices: None,
+            max_choices: None,
+            page_size: None,
+            labels: HashMap::new(),
         }));
         assert_eq!(parsed, expected);
     }
 
     #[test]
-    fn test_deserialize_config() {
+    fn test_multi_select_choice_count() {
         let config = r#"
-        [petridish]
-        project_prompt = "what's your project name?"
-        project_var_name = "project"
-        exclude_render_paths = ["cliff.toml"]
+        name="features"
+        choices=["docker", "ci", "docs", "bench", "fuzz"]
+        type="string"
+        multi=true
+        min_choices=2
+        max_choices=4
         "#;
-        let parsed = toml::from_str::<Config>(config).unwrap();
+        let parsed = toml::from_str::<PromptType>(config).unwrap();
+        let PromptType::String(StringPrompt::MultiSelect(select)) = &parsed else {
+            panic!("not a multi select: {:?}", parsed);
+        };
+        assert_eq!((select.min_choices, select.max_choices), (Some(2), Some(4)));
+
+        let mut context = Context::new();
+        parsed.answer("docker,ci", &mut context).unwrap();
+        let err = parsed.answer("docker", &mut context).unwrap_err();
         assert_eq!(
-            parsed,
-            Config {
-                petridish_config: PetridishConfig {
-                    project_prompt: "what's your project name?".into(),
-                    project_var_name: "project".into(),
-                    short_description: None,
-                    long_description: None,
-                    exclude_render_paths: vec!["cliff.toml".into()],
-                },
-                prompts: vec![],
-            }
+            err.to_string(),
+            "invalid value 'docker' for `features`: select at least 2 items"
         );
+
+        let config = r#"
+        name="ports"
+        choices=[80, 443]
+        type="number"
+        multi=true
+        max_choices=1
+        "#;
+        let parsed = toml::from_str::<PromptType>(config).unwrap();
+        assert!(parsed.answer("80,443", &mut context).is_err());
+        parsed.answer("443", &mut context).unwrap();
     }
 
     #[test]
-    fn test_deserialize_empty_config() {
-        let config = "";
-        let parsed = toml::from_str::<Config>(config).unwrap();
+    fn test_prompt_message() {
+        let mut context = Context::new();
+        context.insert("service_name", "billing");
         assert_eq!(
-            parsed,
-            Config {
-                petridish_config: PetridishConfig {
-                    project_prompt: "project name?".into(),
-                    project_var_name: "project_name".into(),
-                    short_description: None,
-                    long_description: None,
-                    exclude_render_paths: vec![],
-                },
-                prompts: vec![],
-            }
+            prompt_message(
+                Some("What port should {{ service_name }} listen on?"),
+                "port",
+                &context
+            ),
+            "What port should billing listen on?"
+        );
+        // shown as it is when it cannot be rendered
+        assert_eq!(
+            prompt_message(Some("Port of {{ unknown }}?"), "port", &context),
+            "Port of {{ unknown }}?"
+        );
+        assert_eq!(prompt_message(None, "port", &context), "port");
+    }
+
+    #[test]
+    fn test_transform_answer() {
+        let prompt = toml::from_str::<PromptType>(
+            r#"
+            name = "crate_name"
+            type = "string"
+            regex = "^[A-Za-z ]+$"
+            transform = "{{ value | trim | snake_case }}"
+            "#,
+        )
+        .unwrap();
+        let mut context = Context::new();
+        prompt.answer("My Crate ", &mut context).unwrap();
+        assert_eq!(context.get("crate_name"), Some(&"my_crate".into()));
+
+        // validated before the transform
+        assert!(prompt.answer("my_crate", &mut context).is_err());
+
+        let prompt = toml::from_str::<PromptType>(
+            r#"
+            name = "module"
+            type = "string"
+            transform = "{{ crate_name }}::{{ value | lower }}"
+            "#,
         )
+        .unwrap();
+        prompt
+            .prompt_with(&mut context, |_| Ok(Some("Cli".into())))
+            .unwrap();
+        assert_eq!(context.get("module"), Some(&"my_crate::cli".into()));
+
+        // a default taken without asking is transformed the same way
+        let config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "crate_name"
+            type = "string"
+            default = "My Crate"
+            transform = "{{ value | snake_case }}"
+            "#,
+        )
+        .unwrap();
+        let mut context = Context::new();
+        config.prompts[0].skip(&mut context).unwrap();
+        assert_eq!(context.get("crate_name"), Some(&"my_crate".into()));
+        let context = config.context_from(&PromptValues::default()).unwrap();
+        assert_eq!(context.get("crate_name"), Some(&"my_crate".into()));
     }
 
     #[test]
-    fn test_deserialize_config_with_prompts() {
-        let config = r#"
-        [petridish]
-        project_prompt = "what's your project name?"
-        project_var_name = "project"
+    fn test_required_when() {
+        let prompt = toml::from_str::<PromptType>(
+            r#"
+            name = "api_key"
+            type = "string"
+            required_when = "{{ use_api }}"
+            "#,
+        )
+        .unwrap();
 
-        [[prompts]]
-        name = "name"
-        prompt = "what's your name?"
-        type = "string"
+        let mut context = Context::new();
+        context.insert("use_api", &true);
+        assert!(matches!(
+            prompt.answer("", &mut context),
+            Err(Error::InvalidAnswer { .. })
+        ));
+        prompt.answer("secret", &mut context).unwrap();
+        assert_eq!(context.get("api_key"), Some(&"secret".into()));
 
-        [[prompts]]
-        name = "age"
-        prompt = "what's your age?"
-        type = "number"
-        max = 150
+        let mut context = Context::new();
+        context.insert("use_api", &false);
+        prompt
+            .prompt_with(&mut context, |_| Ok(Some("".into())))
+            .unwrap();
+        assert_eq!(context.get("api_key"), Some(&"".into()));
+    }
 
-        [[prompts]]
-        name = "love_rust"
-        prompt = "do you love rust?"
-        type = "bool"
-        default = true
+    #[test]
+    fn test_select_choices_when() {
+        let prompt = toml::from_str::<PromptType>(
+            r#"
+            name = "region"
+            type = "string"
+            choices = ["us-east-1", "eu-west-1", "europe-west1", "on-premise"]
+            default = "us-east-1"
+            depends_on = ["cloud"]
 
-        [[prompts]]
-        name = "hobbies"
-        prompt = "what's your hobbies?"
-        type = "string"
-        choices = ["swimming", "running", "reading"]
-        multi = true
+            [when]
+            "us-east-1" = "{{ cloud == 'aws' }}"
+            "eu-west-1" = "{{ cloud == 'aws' }}"
+            "europe-west1" = "{{ cloud == 'gcp' }}"
+            "#,
+        )
+        .unwrap();
+        let context = |cloud: &str| {
+            let mut context = Context::new();
+            context.insert("cloud", cloud);
+            context
+        };
 
-        [[prompts]]
-        name = "nationality"
-        prompt = "what's your nationality?"
-        type = "string"
-        choices = ["Chinese", "American", "Japanese"]
-        "#;
-        let parsed = toml::from_str::<Config>(config).unwrap();
+        let mut aws = context("aws");
         assert_eq!(
-            parsed,
-            Config {
-                petridish_config: PetridishConfig {
-                    project_prompt: "what's your project name?".into(),
-                    project_var_name: "project".into(),
-                    short_description: None,
-                    long_description: None,
-                    exclude_render_paths: vec![],
-                },
-                prompts: vec![
-                    PromptType::String(StringPrompt::Input(StringInput {
-                        name: "name".into(),
-                        prompt: Some("what's your name?".into()),
+            prompt.select_default(&aws).unwrap(),
+            Some("us-east-1".into())
+        );
+        prompt.answer("eu-west-1", &mut aws).unwrap();
+        assert_eq!(aws.get("region"), Some(&"eu-west-1".into()));
+
+        // the default is hidden, the first shown choice is taken instead
+        let mut gcp = context("gcp");
+        assert_eq!(
+            prompt.select_default(&gcp).unwrap(),
+            Some("europe-west1".into())
+        );
+        assert_eq!(
+            prompt.default_value(&gcp).unwrap(),
+            Some("europe-west1".into())
+        );
+        let err = prompt.answer("eu-west-1", &mut gcp).unwrap_err();
+        assert!(matches!(err, Error::InvalidAnswer { .. }));
+        prompt.answer("on-premise", &mut gcp).unwrap();
+        assert_eq!(gcp.get("region"), Some(&"on-premise".into()));
+    }
+
+    #[test]
+    fn test_emptyable_multi_select() {
+        let prompt = |emptyable: bool| {
+            toml::from_str::<PromptType>(&format!(
+                r#"
+                name = "features"
+                type = "string"
+                multi = true
+                choices = ["cli", "docs"]
+                emptyable = {}
+                "#,
+                emptyable
+            ))
+            .unwrap()
+        };
+
+        let mut context = Context::new();
+        prompt(true).answer("", &mut context).unwrap();
+        assert_eq!(context.get("features"), Some(&serde_json::json!([])));
+        let mut context = Context::new();
+        prompt(true)
+            .prompt_with(&mut context, |_| Ok(None))
+            .unwrap();
+        assert_eq!(context.get("features"), Some(&serde_json::json!([])));
+
+        let err = prompt(false).answer("", &mut Context::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidAnswer { .. }));
+    }
+
+    #[test]
+    fn test_check_selection_count() {
+        assert_eq!(
+            check_selection_count(1, false, Some(2), Some(4)).as_deref(),
+            Some("select at least 2 items")
+        );
+        assert_eq!(
+            check_selection_count(5, false, Some(2), Some(4)).as_deref(),
+            Some("select at most 4 items")
+        );
+        for count in 2..=4 {
+            assert_eq!(check_selection_count(count, false, Some(2), Some(4)), None);
+        }
+        assert_eq!(
+            check_selection_count(0, false, None, None).as_deref(),
+            Some("no item is selected")
+        );
+        assert_eq!(check_selection_count(0, true, Some(2), None), None);
+        assert_eq!(check_selection_count(9, false, None, None), None);
+    }
+
+    #[test]
+    fn test_inherit_prompts() {
+        let mut child = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "license"
+            default = "MIT"
+            type = "string"
+
+            [[prompts]]
+            name = "email"
+            default = "{{ author }}@example.com"
+            type = "string"
+            depends_on = ["author"]
+            "#,
+        )
+        .unwrap();
+        let base = toml::from_str::<Config>(
+            r#"
+            prompts = ["license", "ci", "author"]
+            "#,
+        )
+        .unwrap();
+
+        child.inherit_prompts(base).unwrap();
+        let names = child.prompts.iter().map(|p| p.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["ci", "author", "license", "email"]);
+        let PromptType::String(StringPrompt::Input(license)) = &child.prompts[2] else {
+            panic!("not a string input");
+        };
+        assert_eq!(license.default, Some("MIT".into()));
+    }
+
+    #[test]
+    fn test_prompt_values_precedence() {
+        let config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "author"
+            type = "string"
+            default = "nobody"
+            "#,
+        )
+        .unwrap();
+        let prompt = &config.prompts[0];
+        let mut values = PromptValues {
+            defines: HashMap::from([("author".to_string(), "define".into())]),
+            extra_context: HashMap::from([("author".to_string(), "extra".to_string())]),
+            answers: serde_json::Map::from_iter([("author".to_string(), "answers".into())]),
+            env: HashMap::from([("PETRIDISH_VAR_AUTHOR".to_string(), "env".to_string())]),
+        };
+        let resolve = |values: &PromptValues| values.resolve(prompt, &Context::new()).unwrap();
+
+        assert_eq!(
+            resolve(&values),
+            Some((ValueSource::ExtraContext, "extra".into()))
+        );
+        values.extra_context.clear();
+        assert_eq!(resolve(&values), Some((ValueSource::Env, "env".into())));
+        values.env.clear();
+        assert_eq!(
+            resolve(&values),
+            Some((ValueSource::Define, "define".into()))
+        );
+        values.defines.clear();
+        assert_eq!(
+            resolve(&values),
+            Some((ValueSource::Answers, "answers".into()))
+        );
+        values.answers.clear();
+        assert_eq!(
+            resolve(&values),
+            Some((ValueSource::Default, "nobody".into()))
+        );
+        assert_eq!(PromptValues::default().given("unknown"), None);
+    }
+
+    #[test]
+    fn test_prompt_values_answer() {
+        let mut config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "author"
+            type = "string"
+
+            [[prompts]]
+            name = "port"
+            type = "number"
+            default = 80
+            "#,
+        )
+        .unwrap();
+        let values = PromptValues {
+            defines: HashMap::from([("author".to_string(), "define".into())]),
+            extra_context: HashMap::from([("author".to_string(), "extra".to_string())]),
+            answers: serde_json::Map::from_iter([("port".to_string(), 3000.into())]),
+            env: HashMap::from([("PETRIDISH_VAR_PORT".to_string(), "8080".to_string())]),
+        };
+
+        // a define is only the default, `key=value` still answers without asking
+        config.prefill(&values);
+        assert_eq!(
+            values.answer("author"),
+            Some((ValueSource::ExtraContext, "extra".to_string()))
+        );
+        // so is a remembered answer, the environment answers
+        assert_eq!(
+            values.answer("port"),
+            Some((ValueSource::Env, "8080".to_string()))
+        );
+        let mut context = Context::new();
+        for prompt in config.prompts {
+            prompt
+                .prompt_or_timeout(&mut context, true, &values, &mut |_| {
+                    panic!("all prompts are answered")
+                })
+                .unwrap();
+        }
+        assert_eq!(context.get("author"), Some(&"extra".into()));
+        assert_eq!(context.get("port"), Some(&8080.0.into()));
+    }
+
+    #[test]
+    fn test_remember_answers() {
+        let mut config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "license"
+            type = "string"
+            choices = ["MIT", "custom"]
+
+            [prompts.follow_up.custom]
+            name = "license_text"
+            type = "string"
+            default = "All rights reserved"
+
+            [[prompts]]
+            name = "author"
+            type = "string"
+            default = "nobody"
+
+            [[prompts]]
+            name = "port"
+            type = "number"
+
+            [[prompts]]
+            name = "features"
+            type = "string"
+            multi = true
+            choices = ["cli", "docs", "ci"]
+
+            [[prompts]]
+            name = "docker"
+            type = "bool"
+
+            [[prompts]]
+            name = "api_token"
+            type = "string"
+            secret = true
+            "#,
+        )
+        .unwrap();
+        let answers = serde_json::json!({
+            "license": "custom",
+            "license_text": "{{ not a template }}",
+            "author": "me",
+            "port": 8080,
+            "features": ["docs", "ci"],
+            "docker": true,
+        });
+        config.remember(answers.as_object().unwrap());
+
+        let context = Context::new();
+        let defaults = config
+            .prompts
+            .iter()
+            .map(|p| p.default_value(&context).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            defaults,
+            [
+                Some("custom".into()),
+                Some("me".into()),
+                Some(8080.0.into()),
+                Some(serde_json::json!(["docs", "ci"])),
+                Some(true.into()),
+                None,
+            ]
+        );
+        let follow_up = config.prompts[0]
+            .follow_up(&{
+                let mut context = Context::new();
+                context.insert("license", "custom");
+                context
+            })
+            .unwrap();
+        assert_eq!(
+            follow_up.default_value(&context).unwrap(),
+            Some("{{ not a template }}".into())
+        );
+
+        // answers which don't fit are ignored
+        config.remember(
+            serde_json::json!({"license": "GPL", "port": "many"})
+                .as_object()
+                .unwrap(),
+        );
+        assert_eq!(
+            config.prompts[0].default_value(&context).unwrap(),
+            Some("custom".into())
+        );
+        assert_eq!(
+            config.unremembered_names(),
+            BTreeSet::from(["api_token".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_edit_answer() {
+        let config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "license"
+            type = "string"
+            choices = ["MIT", "custom"]
+
+            [prompts.follow_up.custom]
+            name = "license_text"
+            type = "string"
+
+            [[prompts]]
+            name = "author"
+            type = "string"
+            "#,
+        )
+        .unwrap();
+        let mut context = Context::new();
+        context.insert("license", "MIT");
+        context.insert("author", "me");
+
+        let mut answers = vec!["Copyright me", "custom"];
+        let mut asked = vec![];
+        edit_answer(
+            &config.prompts,
+            "license",
+            &mut context,
+            &mut |prompt, context| {
+                asked.push(prompt.name().to_string());
+                prompt.prompt_with(context, |_| Ok(answers.pop().map(String::from)))
+            },
+        )
+        .unwrap();
+        assert_eq!(asked, ["license", "license_text"]);
+        assert_eq!(context.get("license"), Some(&"custom".into()));
+        assert_eq!(context.get("license_text"), Some(&"Copyright me".into()));
+        assert_eq!(context.get("author"), Some(&"me".into()));
+
+        // the follow-up answer goes along with its choice
+        edit_answer(
+            &config.prompts,
+            "license",
+            &mut context,
+            &mut |prompt, context| prompt.prompt_with(context, |_| Ok(Some("MIT".into()))),
+        )
+        .unwrap();
+        assert_eq!(context.get("license"), Some(&"MIT".into()));
+        assert!(!context.contains_key("license_text"));
+
+        assert!(edit_answer(&config.prompts, "missing", &mut context, &mut |_, _| Ok(())).is_err());
+    }
+
+    #[test]
+    fn test_file_prompt() {
+        let dir = tempdir::TempDir::new("file-prompt").unwrap();
+        let path = dir.path().join("config.yml");
+        std::fs::write(&path, "key: value\n").unwrap();
+        std::fs::create_dir(dir.path().join("configs")).unwrap();
+
+        let config = r#"
+            name = "existing_config"
+            type = "file"
+            default = "{{ dir }}/config.yml"
+            "#;
+        let prompt = toml::from_str::<PromptType>(config).unwrap();
+        assert!(matches!(prompt, PromptType::File(_)));
+
+        let mut context = Context::new();
+        prompt.answer(path.to_str().unwrap(), &mut context).unwrap();
+        assert_eq!(
+            context.get("existing_config"),
+            Some(&tera::Value::from("key: value\n"))
+        );
+
+        let missing = dir.path().join("missing.yml");
+        let err = prompt
+            .answer(missing.to_str().unwrap(), &mut context)
+            .unwrap_err();
+        assert!(err.to_string().contains("is not a file"));
+        let err = prompt
+            .answer(dir.path().join("configs").to_str().unwrap(), &mut context)
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidAnswer { .. }));
+
+        context.insert("dir", &dir.path().display().to_string());
+        assert_eq!(
+            prompt.default_value(&context).unwrap(),
+            Some("key: value\n".into())
+        );
+    }
+
+    #[test]
+    fn test_path_prompt() {
+        let dir = tempdir::TempDir::new("path-prompt").unwrap();
+        let file = dir.path().join("config.yml");
+        std::fs::write(&file, "").unwrap();
+        let missing = dir.path().join("missing");
+        let prompt = |options: &str| {
+            let config = format!("name = \"target\"\ntype = \"path\"\n{}", options);
+            let prompt = toml::from_str::<PromptType>(&config).unwrap();
+            assert!(matches!(prompt, PromptType::Path(_)));
+            prompt
+        };
+        let answer = |prompt: &PromptType, path: &Path| {
+            let mut context = Context::new();
+            prompt
+                .answer(path.to_str().unwrap(), &mut context)
+                .map(|()| context.get("target").cloned().unwrap())
+        };
+
+        // the path itself is the answer, not the content
+        let any = prompt("");
+        assert_eq!(
+            answer(&any, &file).unwrap(),
+            tera::Value::from(file.to_str().unwrap())
+        );
+        assert!(answer(&any, &missing).is_ok());
+        let mut context = Context::new();
+        let err = any.answer("  ", &mut context).unwrap_err();
+        assert!(err.to_string().contains("a path is required"));
+
+        let must_exist = prompt("must_exist = true");
+        let err = answer(&must_exist, &missing).unwrap_err();
+        assert!(err.to_string().contains("doesn't exist"));
+
+        let dir_only = prompt("dir_only = true");
+        assert!(answer(&dir_only, dir.path()).is_ok());
+        assert!(answer(&dir_only, &missing).is_ok());
+        let err = answer(&dir_only, &file).unwrap_err();
+        assert!(err.to_string().contains("is not a dir"));
+
+        let existing_dir = prompt("dir_only = true\nmust_exist = true");
+        assert!(answer(&existing_dir, dir.path()).is_ok());
+        assert!(answer(&existing_dir, &missing).is_err());
+    }
+
+    #[test]
+    fn test_path_suggestions() {
+        let dir = tempdir::TempDir::new("file-prompt").unwrap();
+        std::fs::write(dir.path().join("config.yml"), "").unwrap();
+        std::fs::write(dir.path().join("README.md"), "").unwrap();
+        std::fs::create_dir(dir.path().join("configs")).unwrap();
+
+        let input = format!("{}/con", dir.path().display());
+        let base = dir.path().display();
+        assert_eq!(
+            path_suggestions(&input, false),
+            [format!("{}/config.yml", base), format!("{}/configs/", base)]
+        );
+        assert_eq!(
+            path_suggestions(&input, true),
+            [format!("{}/configs/", base)]
+        );
+        assert!(path_suggestions(&format!("{}/missing/", base), false).is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_config() {
+        let config = r#"
+        [petridish]
+        project_prompt = "what's your project name?"
+        project_var_name = "project"
+        exclude_render_paths = ["cliff.toml"]
+        "#;
+        let parsed = toml::from_str::<Config>(config).unwrap();
+        assert_eq!(
+            parsed,
+            Config {
+                petridish_config: PetridishConfig {
+                    project_prompt: "what's your project name?".into(),
+                    project_var_name: "project".into(),
+                    short_description: None,
+                    long_description: None,
+                    exclude_render_paths: vec!["cliff.toml".into()],
+                    render_suffix: None,
+                    delimiters: Delimiters::default(),
+                    trim_whitespace: false,
+                    line_endings: LineEndings::Preserve,
+                    requires: vec![],
+                    strip_gitkeep: false,
+                    dir_marker: default_dir_marker(),
+                    output_subdir: None,
+                    extends: None,
+                    exclude_render_defaults: true,
+                    symlinks: Symlinks::Copy,
+                },
+                prompts: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_empty_config() {
+        let config = "";
+        let parsed = toml::from_str::<Config>(config).unwrap();
+        assert_eq!(
+            parsed,
+            Config {
+                petridish_config: PetridishConfig {
+                    project_prompt: "project name?".into(),
+                    project_var_name: "project_name".into(),
+                    short_description: None,
+                    long_description: None,
+                    exclude_render_paths: vec![],
+                    render_suffix: None,
+                    delimiters: Delimiters::default(),
+                    trim_whitespace: false,
+                    line_endings: LineEndings::Preserve,
+                    requires: vec![],
+                    strip_gitkeep: false,
+                    dir_marker: default_dir_marker(),
+                    output_subdir: None,
+                    extends: None,
+                    exclude_render_defaults: true,
+                    symlinks: Symlinks::Copy,
+                },
+                prompts: vec![],
+            }
+        )
+    }
+
+    #[test]
+    fn test_config_to_toml() {
+        let config = r#"
+        [petridish]
+        project_var_name = "project"
+        short_description = "a rust cli"
+        line_endings = "lf"
+        requires = ["git", "node>=18"]
+
+        [petridish.delimiters]
+        variable_start = "[["
+        variable_end = "]]"
+
+        [[prompts]]
+        name = "author"
+        type = "string"
+        default = "{{ env.USER }}"
+        secret = true
+
+        [[prompts]]
+        name = "age"
+        type = "number"
+        min = 0
+
+        [[prompts]]
+        name = "license"
+        type = "string"
+        choices = ["MIT", "custom"]
+        default = "MIT"
+
+        [prompts.follow_up.custom]
+        name = "license_text"
+        type = "string"
+
+        [[prompts]]
+        name = "features"
+        type = "string"
+        choices = ["serde", "clap"]
+        multi = true
+        default = ["serde"]
+
+        [[prompts]]
+        name = "ci"
+        type = "bool"
+        true_value = "yes"
+        false_value = "no"
+
+        [[prompts]]
+        name = "readme"
+        type = "file"
+        "#;
+        let parsed = toml::from_str::<Config>(config).unwrap();
+        let printed = parsed.to_toml();
+        assert!(printed.contains("[petridish]"));
+        assert!(printed.contains("node>=18"));
+        assert_eq!(toml::from_str::<Config>(&printed).unwrap(), parsed);
+    }
+
+    #[test]
+    fn test_deserialize_config_with_prompts() {
+        let config = r#"
+        [petridish]
+        project_prompt = "what's your project name?"
+        project_var_name = "project"
+
+        [[prompts]]
+        name = "name"
+        prompt = "what's your name?"
+        type = "string"
+
+        [[prompts]]
+        name = "age"
+        prompt = "what's your age?"
+        type = "number"
+        max = 150
+
+        [[prompts]]
+        name = "love_rust"
+        prompt = "do you love rust?"
+        type = "bool"
+        default = true
+
+        [[prompts]]
+        name = "hobbies"
+        prompt = "what's your hobbies?"
+        type = "string"
+        choices = ["swimming", "running", "reading"]
+        multi = true
+
+        [[prompts]]
+        name = "nationality"
+        prompt = "what's your nationality?"
+        type = "string"
+        choices = ["Chinese", "American", "Japanese"]
+        "#;
+        let parsed = toml::from_str::<Config>(config).unwrap();
+        assert_eq!(
+            parsed,
+            Config {
+                petridish_config: PetridishConfig {
+                    project_prompt: "what's your project name?".into(),
+                    project_var_name: "project".into(),
+                    short_description: None,
+                    long_description: None,
+                    exclude_render_paths: vec![],
+                    render_suffix: None,
+                    delimiters: Delimiters::default(),
+                    trim_whitespace: false,
+                    line_endings: LineEndings::Preserve,
+                    requires: vec![],
+                    strip_gitkeep: false,
+                    dir_marker: default_dir_marker(),
+                    output_subdir: None,
+                    extends: None,
+                    exclude_render_defaults: true,
+                    symlinks: Symlinks::Copy,
+                },
+                prompts: vec![
+                    PromptType::String(StringPrompt::Input(StringInput {
+                        name: "name".into(),
+                        section: None,
+                        depends_on: vec![],
+                        prompt: Some("what's your name?".into()),
                         default: None,
                         regex: None,
+                        validate_command: None,
+                        secret: false,
+                        transform: None,
+                        required_when: None,
                     })),
                     PromptType::Number(NumberPrompt::Input(NumberInput {
                         name: "age".into(),
+                        section: None,
+                        depends_on: vec![],
                         prompt: Some("what's your age?".into()),
                         default: None,
                         max: Some(150_f64),
@@ -715,25 +3373,1076 @@ mod tests {
                     })),
                     PromptType::Bool(BoolPrompt::Confirm(Confirm {
                         name: "love_rust".into(),
+                        section: None,
+                        depends_on: vec![],
                         prompt: Some("do you love rust?".into()),
                         default: true,
+                        true_value: None,
+                        false_value: None,
                     })),
                     PromptType::String(StringPrompt::MultiSelect(MultiSelect {
                         name: "hobbies".into(),
+                        section: None,
+                        depends_on: vec![],
+                        choices_file: None,
                         prompt: Some("what's your hobbies?".into()),
                         choices: vec!["swimming".into(), "running".into(), "reading".into()],
                         default: None,
                         multi: LiteralTrue,
                         emptyable: false,
+                        min_choices: None,
+                        max_choices: None,
+                        page_size: None,
+                        labels: HashMap::new(),
                     })),
                     PromptType::String(StringPrompt::Select(Select {
                         name: "nationality".into(),
+                        section: None,
+                        depends_on: vec![],
+                        choices_file: None,
                         prompt: Some("what's your nationality?".into()),
                         choices: vec!["Chinese".into(), "American".into(), "Japanese".into()],
                         default: None,
+                        page_size: None,
+                        labels: HashMap::new(),
+                        follow_up: BTreeMap::new(),
+                        when: BTreeMap::new(),
                     })),
                 ]
             }
         )
     }
+
+    #[test]
+    fn test_localize_config() {
+        let config = r#"
+        [[prompts]]
+        name = "name"
+        prompt = "what's your name?"
+        type = "string"
+
+        [[prompts]]
+        name = "hobby"
+        prompt = "what's your hobby?"
+        type = "string"
+        choices = ["running", "swimming"]
+        "#;
+        let locale = r#"
+        [messages]
+        project_name = "项目名称?"
+        name = "你的名字?"
+
+        [choices.hobby]
+        running = "跑步"
+        "#;
+        let mut parsed = toml::from_str::<Config>(config).unwrap();
+        parsed.localize(&toml::from_str::<Locale>(locale).unwrap());
+
+        assert_eq!(parsed.petridish_config.project_prompt, "项目名称?");
+        assert_eq!(
+            parsed.prompts[0],
+            PromptType::String(StringPrompt::Input(StringInput {
+                name: "name".into(),
+                section: None,
+                depends_on: vec![],
+                prompt: Some("你的名字?".into()),
+                default: None,
+                regex: None,
+                validate_command: None,
+                secret: false,
+                transform: None,
+                required_when: None,
+            }))
+        );
+        // no translation for the message, so the built-in one is kept
+        let mut labels = HashMap::new();
+        labels.insert("running".to_string(), "跑步".to_string());
+        assert_eq!(
+            parsed.prompts[1],
+            PromptType::String(StringPrompt::Select(Select {
+                name: "hobby".into(),
+                section: None,
+                depends_on: vec![],
+                choices_file: None,
+                prompt: Some("what's your hobby?".into()),
+                choices: vec!["running".into(), "swimming".into()],
+                default: None,
+                page_size: None,
+                labels,
+                follow_up: BTreeMap::new(),
+                when: BTreeMap::new(),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = serde_json::to_value(Config::json_schema()).unwrap();
+        assert_eq!(schema["title"], "Config");
+        assert!(schema["properties"]["petridish"].is_object());
+        assert!(schema["properties"]["prompts"].is_object());
+        assert!(schema["definitions"]["StringInput"]["properties"]["regex"].is_object());
+        assert!(schema["definitions"]["MultiSelect_for_String"]["properties"]["multi"].is_object());
+    }
+
+    #[test]
+    fn test_select_with_page_size() {
+        let config = r#"
+        name="var"
+        choices=["a", "b", "c"]
+        type="string"
+        page_size=2
+        "#;
+        let parsed = toml::from_str::<PromptType>(config).unwrap();
+        let expected = PromptType::String(StringPrompt::Select(Select {
+            name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
+            prompt: None,
+            choices: vec!["a".into(), "b".into(), "c".into()],
+            default: None,
+            page_size: Some(2),
+            labels: HashMap::new(),
+            follow_up: BTreeMap::new(),
+            when: BTreeMap::new(),
+        }));
+        assert_eq!(parsed, expected);
+
+        let config = r#"
+        name="var"
+        choices=[1, 2, 3]
+        type="number"
+        multi=true
+        page_size=2
+        "#;
+        let parsed = toml::from_str::<PromptType>(config).unwrap();
+        let expected = PromptType::Number(NumberPrompt::MultiSelect(MultiSelect {
+            multi: LiteralTrue,
+            name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
+            prompt: None,
+            choices: vec![1_f64, 2_f64, 3_f64],
+            default: None,
+            emptyable: false,
+            min_choices: None,
+            max_choices: None,
+            page_size: Some(2),
+            labels: HashMap::new(),
+        }));
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_fuzzy_filter() {
+        assert!(fuzzy_filter("uk", &(), "United Kingdom", 0));
+        assert!(fuzzy_filter("UNI", &(), "United States", 0));
+        assert!(fuzzy_filter("", &(), "Japan", 0));
+        assert!(!fuzzy_filter("ku", &(), "United Kingdom", 0));
+        assert!(!fuzzy_filter("china", &(), "Chile", 0));
+    }
+
+    #[test]
+    fn test_number_select_with_range() {
+        let config = r#"
+        name="var"
+        choices="1..3"
+        type="number"
+        "#;
+        let parsed = toml::from_str::<PromptType>(config).unwrap();
+        let expected = PromptType::Number(NumberPrompt::Select(Select {
+            name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
+            prompt: None,
+            choices: vec![1_f64, 2_f64, 3_f64],
+            default: None,
+            page_size: None,
+            labels: HashMap::new(),
+            follow_up: BTreeMap::new(),
+            when: BTreeMap::new(),
+        }));
+        assert_eq!(parsed, expected);
+
+        let config = r#"
+        name="var"
+        range={ start = 10, end = 30, step = 10 }
+        type="number"
+        multi=true
+        "#;
+        let parsed = toml::from_str::<PromptType>(config).unwrap();
+        let expected = PromptType::Number(NumberPrompt::MultiSelect(MultiSelect {
+            multi: LiteralTrue,
+            name: "var".into(),
+            section: None,
+            depends_on: vec![],
+            choices_file: None,
+            prompt: None,
+            choices: vec![10_f64, 20_f64, 30_f64],
+            default: None,
+            emptyable: false,
+            min_choices: None,
+            max_choices: None,
+            page_size: None,
+            labels: HashMap::new(),
+        }));
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let config = r#"
+        name="var"
+        mesage="hello"
+        type="string"
+        "#;
+        let err = toml::from_str::<PromptType>(config).unwrap_err();
+        assert!(err.to_string().contains("unknown field `mesage`"));
+
+        let config = r#"
+        [[prompts]]
+        name="var"
+        choices=[1, 2]
+        defualt=1
+        type="number"
+        "#;
+        let err = toml::from_str::<Config>(config).unwrap_err();
+        assert!(err.to_string().contains("unknown field `defualt`"));
+
+        let config = r#"
+        name="var"
+        default=true
+        emptyable=true
+        type="bool"
+        "#;
+        let err = toml::from_str::<PromptType>(config).unwrap_err();
+        assert!(err.to_string().contains("unknown field `emptyable`"));
+    }
+
+    #[test]
+    fn test_prompt_from_env() {
+        let config = r#"
+        [[prompts]]
+        name = "env_test_age"
+        type = "number"
+        max = 200
+
+        [[prompts]]
+        name = "env_test_language"
+        type = "string"
+        choices = ["rust", "go"]
+
+        [[prompts]]
+        name = "env_test_hobbies"
+        type = "string"
+        multi = true
+        choices = ["running", "swimming", "reading"]
+
+        [[prompts]]
+        name = "env_test_confirm"
+        type = "bool"
+        "#;
+        std::env::set_var("PETRIDISH_VAR_ENV_TEST_AGE", "18");
+        std::env::set_var("PETRIDISH_VAR_ENV_TEST_LANGUAGE", "go");
+        std::env::set_var("PETRIDISH_VAR_ENV_TEST_HOBBIES", "running,reading");
+        std::env::set_var("PETRIDISH_VAR_ENV_TEST_CONFIRM", "yes");
+
+        let mut context = Context::new();
+        for prompt in toml::from_str::<Config>(config).unwrap().prompts {
+            prompt.prompt_or_env(&mut context, true).unwrap();
+        }
+        assert_eq!(
+            context.into_json(),
+            serde_json::json!({
+                "env_test_age": 18.0,
+                "env_test_language": "go",
+                "env_test_hobbies": ["running", "reading"],
+                "env_test_confirm": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_prompt_from_env_strict() {
+        let config = r#"
+        name = "env_test_strict"
+        type = "number"
+        max = 10
+        "#;
+        std::env::set_var("PETRIDISH_VAR_ENV_TEST_STRICT", "20");
+
+        let prompt = toml::from_str::<PromptType>(config).unwrap();
+        assert_eq!(prompt.env_var(), "PETRIDISH_VAR_ENV_TEST_STRICT");
+        let err = prompt.prompt_or_env(&mut Context::new(), true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value '20' for `env_test_strict`: out of range"
+        );
+    }
+
+    #[test]
+    fn test_section_header() {
+        let config = r#"
+        [[prompts]]
+        name = "host"
+        type = "string"
+        section = "Database"
+
+        [[prompts]]
+        name = "port"
+        type = "number"
+        section = "Database"
+
+        [[prompts]]
+        name = "debug"
+        type = "bool"
+
+        [[prompts]]
+        name = "level"
+        type = "string"
+        choices = ["info", "debug"]
+        section = "Logging"
+        "#;
+        let prompts = toml::from_str::<Config>(config).unwrap().prompts;
+
+        let mut previous = None;
+        let mut headers = vec![];
+        for prompt in &prompts {
+            headers.push(section_header(previous, prompt.section()));
+            previous = prompt.section();
+        }
+        assert_eq!(headers, vec![Some("Database"), None, None, Some("Logging")]);
+    }
+
+    #[test]
+    fn test_suggest_project_var_name() {
+        let template = tempdir::TempDir::new("template").unwrap();
+        assert_eq!(suggest_project_var_name(template.path()), None);
+
+        std::fs::create_dir(template.path().join("{{ project }}")).unwrap();
+        std::fs::create_dir(template.path().join("docs")).unwrap();
+        std::fs::write(template.path().join("{{ notes }}"), "").unwrap();
+        assert_eq!(
+            suggest_project_var_name(template.path()).as_deref(),
+            Some("project")
+        );
+
+        // ambiguous with another templated dir
+        std::fs::create_dir(template.path().join("{{app_name}}")).unwrap();
+        assert_eq!(suggest_project_var_name(template.path()), None);
+    }
+
+    #[test]
+    fn test_overlay_prompts() {
+        let dir = tempdir::TempDir::new("overlay").unwrap();
+        let path = dir.path().join("org-prompts.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [[prompts]]
+            name = "cost_center"
+            type = "string"
+            choices_file = "cost-centers.txt"
+            default = "cc-200"
+
+            [[prompts]]
+            name = "owner"
+            type = "string"
+            default = "{{ author }}-team"
+            "#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("cost-centers.txt"), "cc-100\ncc-200\n").unwrap();
+        let mut config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "author"
+            type = "string"
+            default = "me"
+
+            [[prompts]]
+            name = "license"
+            type = "string"
+            choices = ["MIT", "Apache-2.0"]
+            "#,
+        )
+        .unwrap();
+
+        config.overlay_prompts(&path).unwrap();
+        let names = config.prompts.iter().map(|p| p.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["author", "license", "cost_center", "owner"]);
+        let context = config.context_from(&PromptValues::default()).unwrap();
+        assert_eq!(context.get("cost_center"), Some(&"cc-200".into()));
+        assert_eq!(context.get("owner"), Some(&"me-team".into()));
+
+        let err = config.overlay_prompts(&path).unwrap_err();
+        assert!(matches!(err, Error::DuplicatePrompt(name) if name == "cost_center"));
+    }
+
+    #[test]
+    fn test_load_invalid_glob() {
+        let template = tempdir::TempDir::new("template").unwrap();
+        let write = |globs: &str| {
+            std::fs::write(
+                template.path().join("petridish.toml"),
+                format!("[petridish]\nexclude_render_paths = [{}]\n", globs),
+            )
+            .unwrap();
+        };
+
+        write(r#""assets/{a,b""#);
+        let err = Config::load(template.path()).unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidGlob { pattern, .. } if pattern == "assets/{a,b"),
+            "{:?}",
+            err
+        );
+        assert_eq!(err.exit_code(), 2);
+
+        // templated globs are only checked once rendered
+        write(r#""{{ assets }}/**", "**/*.png""#);
+        Config::load(template.path()).unwrap();
+    }
+
+    #[test]
+    fn test_load_with_context() {
+        let template = tempdir::TempDir::new("template").unwrap();
+        std::fs::write(
+            template.path().join("petridish.toml"),
+            r#"
+            [[prompts]]
+            name = "author"
+            type = "string"
+            default = "{{ user }}"
+            <% if ci | default(value=false) %>
+            [[prompts]]
+            name = "ci_provider"
+            type = "string"
+            choices = [<% for provider in providers %>"<< provider >>", <% endfor %>]
+            <% endif %>
+            "#,
+        )
+        .unwrap();
+
+        let config = Config::load(template.path()).unwrap();
+        let names = config.prompts.iter().map(|p| p.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["author"]);
+
+        let mut context = Context::new();
+        context.insert("ci", &true);
+        context.insert("providers", &["github", "gitlab"]);
+        context.insert("user", "me");
+        let config = Config::load_with_context(template.path(), &context).unwrap();
+        let names = config.prompts.iter().map(|p| p.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["author", "ci_provider"]);
+        let mut context = Context::new();
+        config.prompts[1].answer("gitlab", &mut context).unwrap();
+        assert!(config.prompts[1].answer("bitbucket", &mut context).is_err());
+        // the prompt templates are rendered when prompting only
+        match &config.prompts[0] {
+            PromptType::String(StringPrompt::Input(input)) => {
+                assert_eq!(input.default.as_deref(), Some("{{ user }}"))
+            }
+            prompt => panic!("unexpected prompt {:?}", prompt),
+        }
+    }
+
+    #[test]
+    fn test_load_choices_file() {
+        let template = tempdir::TempDir::new("template").unwrap();
+        std::fs::write(
+            template.path().join("petridish.toml"),
+            r#"
+            [[prompts]]
+            name = "engine"
+            type = "string"
+            choices_file = "engines.txt"
+
+            [[prompts]]
+            name = "ports"
+            type = "number"
+            multi = true
+            choices_file = "ports.txt"
+            "#,
+        )
+        .unwrap();
+        std::fs::write(
+            template.path().join("engines.txt"),
+            "postgres\nmysql\n\nsqlite\n",
+        )
+        .unwrap();
+        std::fs::write(template.path().join("ports.txt"), "80\n443\n").unwrap();
+
+        let config = Config::load(template.path()).unwrap();
+        match &config.prompts[0] {
+            PromptType::String(StringPrompt::Select(select)) => {
+                assert_eq!(select.choices, vec!["postgres", "mysql", "sqlite"])
+            }
+            prompt => panic!("unexpected prompt {:?}", prompt),
+        }
+        match &config.prompts[1] {
+            PromptType::Number(NumberPrompt::MultiSelect(select)) => {
+                assert_eq!(select.choices, vec![80_f64, 443_f64])
+            }
+            prompt => panic!("unexpected prompt {:?}", prompt),
+        }
+
+        std::fs::write(template.path().join("engines.txt"), "\n").unwrap();
+        let err = Config::load(template.path()).unwrap_err();
+        assert!(matches!(err, Error::InvalidChoicesFile { .. }));
+
+        std::fs::remove_file(template.path().join("engines.txt")).unwrap();
+        let err = Config::load(template.path()).unwrap_err();
+        assert!(matches!(err, Error::PathNotFound { .. }));
+    }
+
+    #[test]
+    fn test_choices_file_outside_template() {
+        let dir = tempdir::TempDir::new("choices").unwrap();
+        let template = dir.path().join("template");
+        std::fs::create_dir(&template).unwrap();
+        std::fs::write(dir.path().join("secrets.txt"), "hunter2\n").unwrap();
+        let write = |choices_file: &str| {
+            std::fs::write(
+                template.join("petridish.toml"),
+                format!(
+                    "[[prompts]]\nname = \"engine\"\ntype = \"string\"\nchoices_file = \"{}\"\n",
+                    choices_file
+                ),
+            )
+            .unwrap();
+        };
+
+        write("../secrets.txt");
+        let err = Config::load(&template).unwrap_err();
+        assert!(matches!(err, Error::InvalidChoicesFile { .. }), "{:?}", err);
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path().join("secrets.txt"), template.join("link.txt"))
+                .unwrap();
+            write("link.txt");
+            let err = Config::load(&template).unwrap_err();
+            assert!(matches!(err, Error::InvalidChoicesFile { .. }), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn test_multi_select_default_from_context() {
+        let config = r#"
+        name = "features"
+        type = "string"
+        multi = true
+        choices = ["web", "grpc", "cli"]
+        default = ["{% if framework == 'axum' %}web{% endif %}", "cli"]
+        "#;
+        let mut context = Context::new();
+        context.insert("framework", "axum");
+        match toml::from_str::<PromptType>(config).unwrap() {
+            PromptType::String(StringPrompt::MultiSelect(select)) => {
+                assert_eq!(select.default_indices(&context).unwrap(), vec![0, 2]);
+                context.insert("framework", "tonic");
+                assert_eq!(select.default_indices(&context).unwrap(), vec![2]);
+            }
+            prompt => panic!("unexpected prompt {:?}", prompt),
+        }
+
+        let config = r#"
+        name = "ports"
+        type = "number"
+        multi = true
+        choices = [80, 443, 8080]
+        default = [443, "{{ port }}"]
+        "#;
+        let mut context = Context::new();
+        context.insert("port", &8080);
+        match toml::from_str::<PromptType>(config).unwrap() {
+            PromptType::Number(NumberPrompt::MultiSelect(select)) => {
+                assert_eq!(select.default_indices(&context).unwrap(), vec![1, 2]);
+            }
+            prompt => panic!("unexpected prompt {:?}", prompt),
+        }
+    }
+
+    #[test]
+    fn test_skip_prompt() {
+        let config = r#"
+        [[prompts]]
+        name = "name"
+        type = "string"
+        default = "{{ project_name }}-cli"
+
+        [[prompts]]
+        name = "features"
+        type = "string"
+        multi = true
+        choices = ["web", "cli"]
+        default = ["cli"]
+
+        [[prompts]]
+        name = "docker"
+        type = "bool"
+
+        [[prompts]]
+        name = "age"
+        type = "number"
+        "#;
+        let prompts = toml::from_str::<Config>(config).unwrap().prompts;
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+        for prompt in &prompts[..3] {
+            prompt.skip(&mut context).unwrap();
+        }
+        assert_eq!(
+            context.into_json(),
+            serde_json::json!({
+                "project_name": "awesome",
+                "name": "awesome-cli",
+                "features": ["cli"],
+                "docker": false,
+            })
+        );
+
+        let err = prompts[3].skip(&mut Context::new()).unwrap_err();
+        assert!(matches!(err, Error::MissingDefault(name) if name == "age"));
+    }
+
+    #[test]
+    fn test_select_default() {
+        let config = r#"
+        [[prompts]]
+        name = "license"
+        type = "string"
+        choices = ["MIT", "Apache-2.0"]
+        default = "Apache-2.0"
+
+        [[prompts]]
+        name = "port"
+        type = "number"
+        choices = [80, 443]
+
+        [[prompts]]
+        name = "features"
+        type = "string"
+        multi = true
+        choices = ["web", "cli"]
+        default = ["cli"]
+
+        [[prompts]]
+        name = "targets"
+        type = "string"
+        multi = true
+        choices = ["linux", "macos"]
+
+        [[prompts]]
+        name = "name"
+        type = "string"
+        default = "awesome"
+        "#;
+        let prompts = toml::from_str::<Config>(config).unwrap().prompts;
+        let context = Context::new();
+        let defaults = prompts
+            .iter()
+            .map(|prompt| prompt.select_default(&context).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            defaults,
+            vec![
+                Some(serde_json::json!("Apache-2.0")),
+                Some(serde_json::json!(80.0)),
+                Some(serde_json::json!(["cli"])),
+                Some(serde_json::json!([])),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prompt_name_shorthand() {
+        let input = |name: &str| {
+            PromptType::String(StringPrompt::Input(StringInput {
+                name: name.into(),
+                section: None,
+                depends_on: vec![],
+                prompt: None,
+                default: None,
+                regex: None,
+                validate_command: None,
+                secret: false,
+                transform: None,
+                required_when: None,
+            }))
+        };
+
+        let config = toml::from_str::<Config>(r#"prompts = ["author", "email"]"#).unwrap();
+        assert_eq!(config.prompts, vec![input("author"), input("email")]);
+
+        let config = toml::from_str::<Config>(
+            r#"prompts = ["author", { name = "docker", type = "bool", default = true }]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.prompts,
+            vec![
+                input("author"),
+                PromptType::Bool(BoolPrompt::Confirm(Confirm {
+                    name: "docker".into(),
+                    section: None,
+                    depends_on: vec![],
+                    prompt: None,
+                    default: true,
+                    true_value: None,
+                    false_value: None,
+                })),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_validate_command() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir::TempDir::new("validate").unwrap();
+        let script = dir.path().join("check-name");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\n[ \"$1\" = free ] || { echo \"'$1' is taken\" >&2; exit 1; }\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let command = format!("{} {{{{ value }}}}", script.display());
+
+        assert_eq!(run_validate_command(&command, "free"), Ok(()));
+        assert_eq!(
+            run_validate_command(&command, "taken"),
+            Err("'taken' is taken".to_string())
+        );
+        // the value is passed as a single argument, not through a shell
+        assert_eq!(
+            run_validate_command(&command, "free; exit 0"),
+            Err("'free; exit 0' is taken".to_string())
+        );
+        assert!(run_validate_command("petridish-missing-command", "free")
+            .unwrap_err()
+            .starts_with("cannot run `petridish-missing-command`"));
+
+        let prompt = toml::from_str::<PromptType>(&format!(
+            "name = \"name\"\ntype = \"string\"\nvalidate_command = \"{}\"",
+            command
+        ))
+        .unwrap();
+        let mut context = Context::new();
+        prompt.answer("free", &mut context).unwrap();
+        assert!(matches!(
+            prompt.answer("taken", &mut context),
+            Err(Error::InvalidAnswer { reason, .. }) if reason == "'taken' is taken"
+        ));
+    }
+
+    #[test]
+    fn test_sort_prompts() {
+        let config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "email"
+            type = "string"
+            default = "{{ user }}@{{ domain }}"
+            depends_on = ["user", "domain"]
+
+            [[prompts]]
+            name = "domain"
+            type = "string"
+            depends_on = ["project_name"]
+
+            [[prompts]]
+            name = "user"
+            type = "string"
+
+            [[prompts]]
+            name = "license"
+            type = "string"
+            "#,
+        )
+        .unwrap();
+        let sorted = sort_prompts(config.prompts).unwrap();
+        assert_eq!(
+            sorted.iter().map(|p| p.name()).collect::<Vec<_>>(),
+            ["domain", "user", "email", "license"]
+        );
+    }
+
+    #[test]
+    fn test_sort_prompts_cycle() {
+        let config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "a"
+            type = "string"
+            depends_on = ["b"]
+
+            [[prompts]]
+            name = "b"
+            type = "string"
+            depends_on = ["a"]
+
+            [[prompts]]
+            name = "c"
+            type = "string"
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            sort_prompts(config.prompts),
+            Err(Error::PromptDependencyCycle(names)) if names == ["a", "b"]
+        ));
+    }
+
+    fn scripted(answers: &[Option<&str>]) -> impl FnMut(&str) -> Result<Option<String>> {
+        let mut answers = answers
+            .iter()
+            .map(|answer| answer.map(String::from))
+            .collect::<std::collections::VecDeque<_>>();
+        move |name| {
+            answers
+                .pop_front()
+                .ok_or_else(|| Error::ArgsError(format!("no answer left for `{}`", name)))
+        }
+    }
+
+    #[test]
+    fn test_prompt_with_scripted_input() {
+        let config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "name"
+            type = "string"
+            regex = "^[a-z]+$"
+
+            [[prompts]]
+            name = "greeting"
+            type = "string"
+            default = "hello {{ name }}"
+
+            [[prompts]]
+            name = "age"
+            type = "number"
+            min = 18
+            max = 99
+
+            [[prompts]]
+            name = "level"
+            type = "number"
+            min = 1
+
+            [[prompts]]
+            name = "license"
+            type = "string"
+            choices = ["MIT", "Apache-2.0"]
+
+            [[prompts]]
+            name = "ports"
+            type = "number"
+            choices = [80, 443, 8080]
+            multi = true
+
+            [[prompts]]
+            name = "ci"
+            type = "bool"
+            default = true
+
+            [[prompts]]
+            name = "docker"
+            type = "bool"
+            "#,
+        )
+        .unwrap();
+
+        let answers = [
+            // name: rejected by the regex first
+            vec![Some("Jo Jo"), Some("jojo")],
+            // greeting: the rendered default
+            vec![None],
+            // age: out of range, not a number, then fine
+            vec![Some("12"), Some("old"), Some("42")],
+            // level: falls back to min
+            vec![None],
+            // license: not a choice, then the first choice by default
+            vec![Some("GPL"), None],
+            vec![Some("80, 8080")],
+            vec![None],
+            vec![Some("yes")],
+        ];
+        let mut context = Context::new();
+        for (prompt, answers) in config.prompts.iter().zip(answers) {
+            prompt
+                .prompt_with(&mut context, scripted(&answers))
+                .unwrap();
+        }
+
+        assert_eq!(
+            context.into_json(),
+            serde_json::json!({
+                "name": "jojo",
+                "greeting": "hello jojo",
+                "age": 42.0,
+                "level": 1.0,
+                "license": "MIT",
+                "ports": [80.0, 8080.0],
+                "ci": true,
+                "docker": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_prompt_with_exhausted_input() {
+        let prompt = toml::from_str::<PromptType>(
+            r#"
+            name = "age"
+            type = "number"
+            max = 10
+            "#,
+        )
+        .unwrap();
+        let mut context = Context::new();
+        assert!(matches!(
+            prompt.prompt_with(&mut context, scripted(&[Some("11")])),
+            Err(Error::ArgsError(_))
+        ));
+        assert!(context.get("age").is_none());
+    }
+
+    #[test]
+    fn test_prompt_timeout_takes_default() {
+        let config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "timeout_license"
+            type = "string"
+            choices = ["MIT", "Apache-2.0"]
+            default = "Apache-2.0"
+
+            [[prompts]]
+            name = "timeout_greeting"
+            type = "string"
+            default = "hello {{ timeout_license }}"
+
+            [[prompts]]
+            name = "timeout_name"
+            type = "string"
+            "#,
+        )
+        .unwrap();
+        let mut prompts = config.prompts.into_iter();
+        let mut context = Context::new();
+        for prompt in prompts.by_ref().take(2) {
+            prompt
+                .prompt_or_timeout(&mut context, true, &PromptValues::default(), &mut |_| {
+                    Ok(false)
+                })
+                .unwrap();
+        }
+        assert_eq!(
+            context.into_json(),
+            serde_json::json!({
+                "timeout_license": "Apache-2.0",
+                "timeout_greeting": "hello Apache-2.0",
+            })
+        );
+
+        let mut context = Context::new();
+        let err = prompts
+            .next()
+            .unwrap()
+            .prompt_or_timeout(&mut context, true, &PromptValues::default(), &mut |_| {
+                Ok(false)
+            })
+            .unwrap_err();
+        assert!(matches!(err, Error::MissingDefault(name) if name == "timeout_name"));
+        assert!(context.get("timeout_name").is_none());
+    }
+
+    #[test]
+    fn test_follow_up_prompts() {
+        let config = |default: &str| {
+            toml::from_str::<Config>(&format!(
+                r#"
+                [[prompts]]
+                name = "license"
+                type = "string"
+                choices = ["MIT", "custom"]
+                default = "{}"
+
+                [prompts.follow_up.custom]
+                name = "license_text"
+                type = "string"
+                default = "All rights reserved"
+
+                [[prompts]]
+                name = "port"
+                type = "number"
+                choices = [80, 8080]
+
+                [prompts.follow_up.8080]
+                name = "proxy"
+                type = "bool"
+                default = true
+                "#,
+                default
+            ))
+            .unwrap()
+        };
+
+        // the branch is taken
+        let mut context = Context::new();
+        config("custom")
+            .prompts
+            .into_iter()
+            .next()
+            .unwrap()
+            .prompt_or_timeout(&mut context, true, &PromptValues::default(), &mut |_| {
+                Ok(false)
+            })
+            .unwrap();
+        assert_eq!(
+            context.get("license_text"),
+            Some(&tera::Value::from("All rights reserved"))
+        );
+
+        // and skipped
+        let mut context = Context::new();
+        config("MIT")
+            .prompts
+            .into_iter()
+            .next()
+            .unwrap()
+            .prompt_or_timeout(&mut context, true, &PromptValues::default(), &mut |_| {
+                Ok(false)
+            })
+            .unwrap();
+        assert_eq!(context.get("license"), Some(&tera::Value::from("MIT")));
+        assert!(context.get("license_text").is_none());
+
+        // numbers match their follow-ups by value
+        let context = config("MIT")
+            .context_from(&PromptValues {
+                extra_context: HashMap::from([("port".to_string(), "8080".to_string())]),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(context.get("proxy"), Some(&tera::Value::from(true)));
+        assert!(context.get("license_text").is_none());
+        let context = config("custom")
+            .context_from(&PromptValues {
+                extra_context: HashMap::from([("port".to_string(), "80".to_string())]),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(context.get("proxy").is_none());
+        assert!(context.get("license_text").is_some());
+    }
 }
@@ -1,51 +1,384 @@
 use std::path::PathBuf;
 
+use inquire::error::InquireError;
+use miette::Diagnostic;
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, Diagnostic)]
 pub enum Error {
     #[error("not found '{path}'")]
+    #[diagnostic(code(petridish::path_not_found))]
     PathNotFound {
         #[source]
         source: std::io::Error,
         path: PathBuf,
     },
 
-    #[error("{0}")]
-    RenderError(#[from] tera::Error),
+    #[error("project dir '{path}' not found in the template")]
+    #[diagnostic(code(petridish::entry_dir_not_found))]
+    EntryDirNotFound {
+        path: PathBuf,
+        #[help]
+        suggestion: Option<String>,
+    },
+
+    #[error("cannot render '{path}'")]
+    #[diagnostic(code(petridish::render))]
+    RenderError {
+        #[source]
+        source: tera::Error,
+        path: PathBuf,
+    },
+
+    #[error("cannot render {} files", .0.len())]
+    #[diagnostic(
+        code(petridish::render),
+        help("every file which failed to render is listed, fix them and generate again")
+    )]
+    RenderErrors(#[related] Vec<Error>),
 
     #[error("invalid {kind} repo: {uri}")]
+    #[diagnostic(
+        code(petridish::invalid_repo),
+        help("use a git uri like `https://github.com/owner/repo.git`, `gh:owner/repo` or a local path")
+    )]
     InvalidRepo { kind: String, uri: String },
 
     #[error("invalid alias `{provider}` repo: {alias}")]
+    #[diagnostic(
+        code(petridish::invalid_alias_repo),
+        help("an alias repo should look like `{provider}:owner/repo`")
+    )]
     InvalidGitAliasRepo { alias: String, provider: String },
 
     #[error("{0} `password` is not provided")]
+    #[diagnostic(
+        code(petridish::auth),
+        help("pass it like `--auth <username>:<password>`")
+    )]
     AuthMissingPassword(String),
 
     #[error("{0} `username` is not provided")]
+    #[diagnostic(
+        code(petridish::auth),
+        help("pass it like `--auth <username>:<password>`")
+    )]
     AuthMissingUsername(String),
 
     #[error("{0}")]
-    PromptError(#[from] inquire::error::InquireError),
+    #[diagnostic(code(petridish::prompt))]
+    PromptError(#[from] InquireError),
 
     #[error("{0}")]
+    #[diagnostic(code(petridish::args))]
     ArgsError(String),
 
     #[error("ParseError")]
+    #[diagnostic(
+        code(petridish::parse),
+        help("check the syntax of petridish.toml, `petridish schema` prints what it accepts")
+    )]
     ParseError(#[from] toml::de::Error),
 
-    #[error("exists '{0}' in output dir, cannot overwrite it, or you can add flag `-f` or `-s`")]
+    #[error("exists '{0}' in output dir, cannot overwrite it")]
+    #[diagnostic(
+        code(petridish::cannot_overwrite),
+        help("add flag `-f` to overwrite the existing files or `-s` to skip them")
+    )]
     CannotOverwriteContent(PathBuf),
 
+    #[error("output dir '{0}' is a file")]
+    #[diagnostic(
+        code(petridish::output_dir_is_file),
+        help("pass a directory to `--output-dir`")
+    )]
+    OutputDirIsFile(PathBuf),
+
+    #[error("the parent of output dir '{0}' doesn't exist")]
+    #[diagnostic(
+        code(petridish::output_dir_parent_not_found),
+        help("add flag `--create-output-dir` to create it")
+    )]
+    OutputDirParentNotFound(PathBuf),
+
+    #[error("the project dir '{output}' overlaps with the template '{template}'")]
+    #[diagnostic(
+        code(petridish::output_overlaps_template),
+        help("pass an `--output-dir` outside of the template")
+    )]
+    OutputOverlapsTemplate { output: PathBuf, template: PathBuf },
+
+    #[error("output subdir '{0}' is not inside of the output dir")]
+    #[diagnostic(
+        code(petridish::invalid_output_subdir),
+        help("`output_subdir` should render to a relative path without `..`")
+    )]
+    InvalidOutputSubdir(String),
+
+    #[error("invalid glob `{pattern}` in `exclude_render_paths`")]
+    #[diagnostic(
+        code(petridish::invalid_glob),
+        help("globs look like `**/*.png`, a literal `[`, `]`, `{{` or `}}` is written like `[[]`")
+    )]
+    InvalidGlob {
+        pattern: String,
+        #[source]
+        source: globset::Error,
+    },
+
+    #[error("'{path}' references undefined variable `{name}`")]
+    #[diagnostic(
+        code(petridish::undefined_variable),
+        help("add a prompt for it, or guard it like `{{{{ {name} | default(value=\"\") }}}}`")
+    )]
+    UndefinedVariable { path: PathBuf, name: String },
+
+    #[error("templates extend each other: {}", .0.join(" -> "))]
+    #[diagnostic(
+        code(petridish::extends_cycle),
+        help("remove `extends` from one of the templates")
+    )]
+    ExtendsCycle(Vec<String>),
+
+    #[error("cannot create dir '{path}'")]
+    #[diagnostic(code(petridish::io))]
+    CannotCreateDir {
+        #[source]
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[error("cannot write file '{path}'")]
+    #[diagnostic(code(petridish::io))]
+    CannotWriteFile {
+        #[source]
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[error("'{0}' is not empty")]
+    #[diagnostic(
+        code(petridish::dir_not_empty),
+        help("add flag `-f` to write into it anyway")
+    )]
+    DirNotEmpty(PathBuf),
+
     #[error("git error")]
+    #[diagnostic(code(petridish::git))]
     GitError(#[from] git2::Error),
 
+    #[error("git operation timed out after {0}s")]
+    #[diagnostic(
+        code(petridish::git_timeout),
+        help("raise the limit with `--git-timeout <seconds>`")
+    )]
+    GitTimeout(u64),
+
+    #[error("hg error: {0}")]
+    #[diagnostic(
+        code(petridish::hg),
+        help("hg templates are cloned by the `hg` command, which should be installed")
+    )]
+    HgError(String),
+
     #[error("unknown git ref '{0}'")]
+    #[diagnostic(
+        code(petridish::invalid_git_ref),
+        help("`--branch` takes a branch, tag or commit of the repo")
+    )]
     InvalidGitRef(String),
 
-    #[error("not found repo '{0}' in cache")]
-    RepoNotFoundInCache(String),
+    #[error("invalid value '{value}' for `{name}`: {reason}")]
+    #[diagnostic(code(petridish::invalid_answer))]
+    InvalidAnswer {
+        name: String,
+        value: String,
+        reason: String,
+    },
+
+    #[error("variables used but not declared: {}", .0.join(", "))]
+    #[diagnostic(
+        code(petridish::undeclared_variables),
+        help("add prompts for them, or guard them like `{{{{ name | default(value=\"\") }}}}`")
+    )]
+    UndeclaredVariables(Vec<String>),
+
+    #[error("prompt `{0}` is declared more than once")]
+    #[diagnostic(
+        code(petridish::duplicate_prompt),
+        help("rename one of the prompts, prompt names must be unique")
+    )]
+    DuplicatePrompt(String),
+
+    #[error("prompt `{0}` is skipped but has no default")]
+    #[diagnostic(
+        code(petridish::missing_default),
+        help("answer it with `PETRIDISH_VAR_<NAME>` instead of skipping it")
+    )]
+    MissingDefault(String),
+
+    #[error("prompts depend on each other: {}", .0.join(", "))]
+    #[diagnostic(
+        code(petridish::prompt_dependency_cycle),
+        help("remove one of the names from the `depends_on` of these prompts")
+    )]
+    PromptDependencyCycle(Vec<String>),
+
+    #[error("invalid choices file '{path}': {reason}")]
+    #[diagnostic(
+        code(petridish::invalid_choices_file),
+        help("the file should contain one choice per line")
+    )]
+    InvalidChoicesFile { path: PathBuf, reason: String },
+
+    #[error("invalid registry '{location}': {reason}")]
+    #[diagnostic(
+        code(petridish::invalid_registry),
+        help("a registry is a JSON file like `{{\"templates\": {{\"<name>\": \"<git uri>\"}}}}`")
+    )]
+    InvalidRegistry { location: String, reason: String },
+
+    #[error("the template requires {}", .0.join(", "))]
+    #[diagnostic(
+        code(petridish::missing_requirements),
+        help("install the missing tools and make sure they are on PATH")
+    )]
+    MissingRequirements(Vec<String>),
+
+    #[error(
+        "not found repo '{name}' in cache{}",
+        .suggestion.as_ref().map(|s| format!(", did you mean '{}'?", s)).unwrap_or_default()
+    )]
+    #[diagnostic(
+        code(petridish::not_found_in_cache),
+        help("`petridish list` shows the cached templates")
+    )]
+    RepoNotFoundInCache {
+        name: String,
+        suggestion: Option<String>,
+    },
+}
+
+impl Error {
+    /// The exit code of the cli for this error, so scripts can tell the
+    /// kinds of failures apart:
+    ///
+    /// | code | meaning                          |
+    /// | ---- | -------------------------------- |
+    /// | 1    | other errors, e.g. io            |
+    /// | 2    | invalid config, args or answers  |
+    /// | 3    | git or network errors            |
+    /// | 4    | conflicts with the output dir    |
+    /// | 130  | canceled by the user             |
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::PromptError(
+                InquireError::OperationCanceled | InquireError::OperationInterrupted,
+            ) => 130,
+            Error::RenderError { .. }
+            | Error::RenderErrors(_)
+            | Error::InvalidRepo { .. }
+            | Error::InvalidGitAliasRepo { .. }
+            | Error::AuthMissingPassword(_)
+            | Error::AuthMissingUsername(_)
+            | Error::ArgsError(_)
+            | Error::ParseError(_)
+            | Error::InvalidOutputSubdir(_)
+            | Error::InvalidGlob { .. }
+            | Error::UndefinedVariable { .. }
+            | Error::InvalidAnswer { .. }
+            | Error::MissingDefault(_)
+            | Error::DuplicatePrompt(_)
+            | Error::UndeclaredVariables(_)
+            | Error::PromptDependencyCycle(_)
+            | Error::ExtendsCycle(_)
+            | Error::InvalidChoicesFile { .. }
+            | Error::MissingRequirements(_)
+            | Error::RepoNotFoundInCache { .. } => 2,
+            Error::GitError(_)
+            | Error::GitTimeout(_)
+            | Error::HgError(_)
+            | Error::InvalidGitRef(_)
+            | Error::InvalidRegistry { .. } => 3,
+            Error::CannotOverwriteContent(_)
+            | Error::OutputDirIsFile(_)
+            | Error::OutputDirParentNotFound(_)
+            | Error::OutputOverlapsTemplate { .. }
+            | Error::DirNotEmpty(_) => 4,
+            Error::PathNotFound { .. }
+            | Error::EntryDirNotFound { .. }
+            | Error::PromptError(_)
+            | Error::CannotCreateDir { .. }
+            | Error::CannotWriteFile { .. } => 1,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic_help() {
+        let err = Error::CannotOverwriteContent(PathBuf::from("awesome/README.md"));
+        assert_eq!(
+            err.code().unwrap().to_string(),
+            "petridish::cannot_overwrite"
+        );
+        assert_eq!(
+            err.help().unwrap().to_string(),
+            "add flag `-f` to overwrite the existing files or `-s` to skip them"
+        );
+
+        let err = Error::InvalidGitAliasRepo {
+            alias: "gh:repo".into(),
+            provider: "gh".into(),
+        };
+        assert_eq!(
+            err.help().unwrap().to_string(),
+            "an alias repo should look like `gh:owner/repo`"
+        );
+
+        let err = Error::EntryDirNotFound {
+            path: PathBuf::from("template/{{ project_name }}"),
+            suggestion: Some("did you mean to set `project_var_name = \"project\"`?".into()),
+        };
+        assert_eq!(
+            err.help().unwrap().to_string(),
+            "did you mean to set `project_var_name = \"project\"`?"
+        );
+
+        assert!(Error::ArgsError("bad".into()).help().is_none());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let cases = [
+            (Error::PromptError(InquireError::OperationCanceled), 130),
+            (Error::PromptError(InquireError::OperationInterrupted), 130),
+            (Error::ArgsError("bad".into()), 2),
+            (Error::MissingDefault("age".into()), 2),
+            (Error::MissingRequirements(vec![]), 2),
+            (Error::RenderErrors(vec![]), 2),
+            (Error::GitTimeout(30), 3),
+            (Error::InvalidGitRef("v2".into()), 3),
+            (Error::HgError("abort: no repository found".into()), 3),
+            (Error::GitError(git2::Error::from_str("clone failed")), 3),
+            (Error::CannotOverwriteContent(PathBuf::from("README.md")), 4),
+            (Error::DirNotEmpty(PathBuf::from("template")), 4),
+            (
+                Error::CannotWriteFile {
+                    source: std::io::ErrorKind::PermissionDenied.into(),
+                    path: PathBuf::from("README.md"),
+                },
+                1,
+            ),
+            (Error::PromptError(InquireError::NotTTY), 1),
+        ];
+        for (err, code) in cases {
+            assert_eq!(err.exit_code(), code, "{:?}", err);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+};
+
+/// A template which another one `extends`, its files are rendered under the
+/// files of the extending template.
+#[derive(Debug)]
+pub struct Base {
+    pub dir: PathBuf,
+    pub config: Config,
+}
+
+/// Follows the `extends` chain of `config`, the config of the template at
+/// `uri`. `fetch` is given a base uri and the dir of the template extending
+/// it (for relative paths) and returns the dir of the downloaded base.
+/// The bases are returned root-most first. Cycles are told by the fetched
+/// dirs, as relative uris like `../base` mean another template at each level.
+pub fn resolve_bases(
+    uri: &str,
+    dir: &Path,
+    config: &Config,
+    mut fetch: impl FnMut(&str, &Path) -> Result<PathBuf>,
+) -> Result<Vec<Base>> {
+    let mut chain = vec![uri.to_string()];
+    let mut visited = vec![canonical_dir(dir)];
+    let mut bases = vec![];
+    let mut extends = config.petridish_config.extends.clone();
+    let mut extending_dir = dir.to_path_buf();
+    while let Some(base_uri) = extends {
+        chain.push(base_uri.clone());
+        let dir = fetch(&base_uri, &extending_dir)?;
+        let canonical = canonical_dir(&dir);
+        if visited.contains(&canonical) {
+            return Err(Error::ExtendsCycle(chain));
+        }
+        visited.push(canonical);
+
+        let config = Config::load(&dir)?;
+        extends = config.petridish_config.extends.clone();
+        extending_dir = dir.clone();
+        bases.push(Base { dir, config });
+    }
+
+    bases.reverse();
+    Ok(bases)
+}
+
+/// The dir with symlinks and `..` resolved, so templates reached by several
+/// paths compare equal.
+fn canonical_dir(dir: &Path) -> PathBuf {
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn fetch_local(uri: &str, extending_dir: &Path) -> Result<PathBuf> {
+        Ok(extending_dir.join(uri))
+    }
+
+    #[test]
+    fn test_resolve_bases() {
+        let dir = tempdir::TempDir::new("extends").unwrap();
+        let child = dir.path().join("child");
+        for (name, extends) in [("child", "../base"), ("base", "../root"), ("root", "")] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+            let config = if extends.is_empty() {
+                String::new()
+            } else {
+                format!("[petridish]\nextends = \"{}\"", extends)
+            };
+            fs::write(dir.path().join(name).join("petridish.toml"), config).unwrap();
+        }
+
+        let config = Config::load(&child).unwrap();
+        let bases = resolve_bases("child", &child, &config, fetch_local).unwrap();
+        let names = bases
+            .iter()
+            .map(|base| base.dir.file_name().unwrap().to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["root", "base"]);
+
+        fs::write(
+            dir.path().join("root").join("petridish.toml"),
+            "[petridish]\nextends = \"../base\"",
+        )
+        .unwrap();
+        let err = resolve_bases("child", &child, &config, fetch_local).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "templates extend each other: child -> ../base -> ../root -> ../base"
+        );
+    }
+
+    #[test]
+    fn test_resolve_bases_by_dir() {
+        let dir = tempdir::TempDir::new("extends").unwrap();
+        // every level extends `base`, which is another template each time
+        let child = dir.path().join("child");
+        let base = child.join("base");
+        let root = base.join("base");
+        fs::create_dir_all(&root).unwrap();
+        for template in [&child, &base] {
+            fs::write(
+                template.join("petridish.toml"),
+                "[petridish]\nextends = \"base\"",
+            )
+            .unwrap();
+        }
+        fs::write(root.join("petridish.toml"), "").unwrap();
+
+        let config = Config::load(&child).unwrap();
+        let bases = resolve_bases("child", &child, &config, fetch_local).unwrap();
+        assert_eq!(bases.len(), 2);
+
+        // the same template written another way is still a cycle
+        fs::write(
+            root.join("petridish.toml"),
+            "[petridish]\nextends = \"../../base/../\"",
+        )
+        .unwrap();
+        let err = resolve_bases("child", &child, &config, fetch_local).unwrap_err();
+        assert!(matches!(err, Error::ExtendsCycle(_)), "{:?}", err);
+    }
+}
@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+
+use heck::{ToKebabCase, ToLowerCamelCase, ToSnakeCase, ToUpperCamelCase};
+use tera::{Tera, Value};
+
+/// Registers the case conversion filters Tera lacks, e.g.
+/// `{{ project_name | snake_case }}` renders `My Project` as `my_project`.
+pub fn register_filters(tera: &mut Tera) {
+    tera.register_filter("snake_case", case_filter(|s| s.to_snake_case()));
+    tera.register_filter("kebab_case", case_filter(|s| s.to_kebab_case()));
+    tera.register_filter("camel_case", case_filter(|s| s.to_lower_camel_case()));
+    tera.register_filter("pascal_case", case_filter(|s| s.to_upper_camel_case()));
+}
+
+fn case_filter(convert: fn(&str) -> String) -> impl tera::Filter {
+    move |value: &Value, _: &HashMap<String, Value>| -> tera::Result<Value> {
+        match value.as_str() {
+            Some(value) => Ok(convert(value).into()),
+            None => Err(format!("cannot change the case of {}, it's not a string", value).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_filters() {
+        let mut tera = Tera::default();
+        register_filters(&mut tera);
+        let mut context = tera::Context::new();
+        context.insert("name", "My awesome-Project");
+        context.insert("age", &3);
+
+        assert_eq!(
+            tera.render_str(
+                "{{ name | snake_case }} {{ name | kebab_case }} {{ name | camel_case }} {{ name | pascal_case }}",
+                &context
+            )
+            .unwrap(),
+            "my_awesome_project my-awesome-project myAwesomeProject MyAwesomeProject"
+        );
+        assert!(tera.render_str("{{ age | snake_case }}", &context).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use tera::Context;
+
+use crate::error::{Error, Result};
+
+/// The environment variable overriding where the globals are loaded from.
+pub const GLOBALS_ENV: &str = "PETRIDISH_GLOBALS";
+
+/// The context variable the globals are available under.
+const GLOBALS_VAR: &str = "globals";
+
+/// Organization-wide values shared by every template, e.g. the company name,
+/// loaded from `globals.toml` in the petridish config dir and rendered like
+/// `{{ globals.company }}`.
+///
+/// ```toml
+/// company = "ACME"
+/// support_email = "support@acme.com"
+/// ```
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Globals(toml::value::Table);
+
+impl Globals {
+    /// Returns `$PETRIDISH_GLOBALS`, or `globals.toml` in the user's config
+    /// dir, e.g. `~/.config/petridish/globals.toml` on Linux.
+    pub fn path() -> Option<PathBuf> {
+        match std::env::var_os(GLOBALS_ENV) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => dirs::config_dir().map(|dir| dir.join("petridish").join("globals.toml")),
+        }
+    }
+
+    /// Returns `None` when there is no globals file.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = read_to_string(path).map_err(|e| Error::PathNotFound {
+            source: e,
+            path: path.to_path_buf(),
+        })?;
+        Ok(Some(toml::from_str(&content)?))
+    }
+
+    /// Inserts the globals into `context` under `globals`, a prompt with the
+    /// same name takes precedence.
+    pub fn merge_into(&self, context: &mut Context) {
+        if !context.contains_key(GLOBALS_VAR) {
+            context.insert(GLOBALS_VAR, &self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_globals() {
+        let dir = tempdir::TempDir::new("globals").unwrap();
+        let path = dir.path().join("globals.toml");
+        assert_eq!(Globals::load(&path).unwrap(), None);
+
+        std::fs::write(
+            &path,
+            "company = \"ACME\"\n[support]\nemail = \"help@acme.com\"",
+        )
+        .unwrap();
+        let globals = Globals::load(&path).unwrap().unwrap();
+        assert_eq!(globals.0["company"].as_str(), Some("ACME"));
+
+        std::fs::write(&path, "company = ").unwrap();
+        assert!(Globals::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_merge_globals() {
+        let globals: Globals =
+            toml::from_str("company = \"ACME\"\n[support]\nemail = \"help@acme.com\"").unwrap();
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+        globals.merge_into(&mut context);
+        assert_eq!(
+            tera::Tera::one_off(
+                "{{ project_name }} by {{ globals.company }} <{{ globals.support.email }}>",
+                &context,
+                false
+            )
+            .unwrap(),
+            "awesome by ACME <help@acme.com>"
+        );
+
+        let mut context = Context::new();
+        context.insert("globals", "from a prompt");
+        globals.merge_into(&mut context);
+        assert_eq!(
+            context.get("globals"),
+            Some(&tera::Value::from("from a prompt"))
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::{fs, path::Path};
+
+use crate::error::{Error, Result};
+
+const PETRIDISH_TOML: &str = r#"[petridish]
+short_description = "A new petridish template"
+long_description = """
+# A new petridish template
+Edit `petridish.toml` to change the prompts, and the files under
+`{{ project_name }}/` to change what gets generated.
+"""
+
+[[prompts]]
+name = "author"
+prompt = "who is the author?"
+type = "string"
+
+[[prompts]]
+name = "license"
+prompt = "which license?"
+choices = ["MIT", "Apache-2.0"]
+type = "string"
+
+[[prompts]]
+name = "with_ci"
+prompt = "add CI config?"
+default = true
+type = "bool"
+"#;
+
+const PROJECT_README: &str = r#"# {{ project_name }}
+
+Created by {{ author }}, licensed under {{ license }}.
+{% if with_ci %}
+CI is enabled.
+{% endif %}"#;
+
+const TEMPLATE_README: &str = r#"# petridish template
+
+Generate a project from this template with:
+
+```shell
+petridish new <path to this dir>
+```
+
+- `petridish.toml` holds the prompts, the answers are the template vars.
+- `{{ project_name }}/` is the project dir, every file in it is rendered with [Tera](https://tera.netlify.app).
+"#;
+
+/// Creates a starter template in `dir`, refusing to write into a non-empty
+/// dir unless `force` is set.
+pub fn init_template(dir: &Path, force: bool) -> Result<()> {
+    let not_empty = fs::read_dir(dir)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if not_empty && !force {
+        return Err(Error::DirNotEmpty(dir.to_path_buf()));
+    }
+
+    let entry_dir = dir.join("{{ project_name }}");
+    fs::create_dir_all(&entry_dir).map_err(|e| Error::CannotCreateDir {
+        source: e,
+        path: entry_dir.clone(),
+    })?;
+    for (path, content) in [
+        (dir.join("petridish.toml"), PETRIDISH_TOML),
+        (dir.join("README.md"), TEMPLATE_README),
+        (entry_dir.join("README.md"), PROJECT_README),
+    ] {
+        fs::write(&path, content).map_err(|e| Error::CannotWriteFile { source: e, path })?;
+    }
+
+    Ok(())
+}
@@ -1,8 +1,23 @@
+pub mod answers;
+pub mod auth;
 pub mod cache;
+mod choice_range;
 pub mod config;
 pub mod error;
+pub mod extends;
+pub mod filters;
+pub mod globals;
+pub mod init;
 mod literal_value;
+pub mod locale;
+pub mod manifest;
+pub mod registry;
 pub mod render;
 mod repository;
+pub mod requirement;
+pub mod update;
+pub mod variables;
+pub mod watch;
 
-pub use repository::{try_new_repo, Repository};
+pub use cache::{list_templates, TemplateInfo};
+pub use repository::{head_revision, try_new_repo, Repository, Revision};
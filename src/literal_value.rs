@@ -1,8 +1,14 @@
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject},
+    JsonSchema,
+};
 use serde::{Deserialize, Serialize, Serializer};
 
 macro_rules! literal_bool {
     ($src:literal, $dst:ident) => {
-        #[derive(PartialEq, Eq)]
+        #[allow(dead_code)]
+        #[derive(Clone, PartialEq, Eq)]
         pub struct $dst;
 
         impl std::fmt::Debug for $dst {
@@ -11,6 +17,21 @@ macro_rules! literal_bool {
             }
         }
 
+        impl JsonSchema for $dst {
+            fn schema_name() -> String {
+                stringify!($dst).into()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                SchemaObject {
+                    instance_type: Some(InstanceType::Boolean.into()),
+                    const_value: Some($src.into()),
+                    ..Default::default()
+                }
+                .into()
+            }
+        }
+
         impl Serialize for $dst {
             fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             where
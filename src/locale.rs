@@ -0,0 +1,84 @@
+use std::{collections::HashMap, fs::read_to_string, path::Path};
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// Translated prompt messages and choice labels loaded from a
+/// `messages.<lang>.toml` file next to `petridish.toml`.
+///
+/// ```toml
+/// [messages]
+/// project_name = "项目名称?"
+/// name = "你的名字?"
+///
+/// [choices.hobby]
+/// running = "跑步"
+/// swimming = "游泳"
+/// ```
+#[derive(Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Locale {
+    #[serde(default)]
+    pub messages: HashMap<String, String>,
+    #[serde(default)]
+    pub choices: HashMap<String, HashMap<String, String>>,
+}
+
+impl Locale {
+    /// Returns the language to use, preferring the explicit `--locale` value
+    /// and falling back to `$LANG` (e.g. `zh_CN.UTF-8` gives `zh_CN`).
+    pub fn detect(locale: Option<String>) -> Option<String> {
+        locale
+            .or_else(|| std::env::var("LANG").ok())
+            .map(|lang| lang.split('.').next().unwrap_or_default().to_string())
+            .filter(|lang| !lang.is_empty() && lang != "C" && lang != "POSIX")
+    }
+
+    /// Loads `messages.<lang>.toml` from the template dir, falling back to the
+    /// primary language (`messages.zh.toml` for `zh_CN`). Returns `None` when
+    /// the template ships no translation for the language.
+    pub fn load(template_dir: &Path, lang: &str) -> Result<Option<Self>> {
+        let primary = lang.split(['_', '-']).next().unwrap_or(lang);
+        for candidate in [lang, primary] {
+            let path = template_dir.join(format!("messages.{}.toml", candidate));
+            if path.exists() {
+                let content =
+                    read_to_string(&path).map_err(|e| Error::PathNotFound { source: e, path })?;
+                return Ok(Some(toml::from_str(&content)?));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn message(&self, name: &str) -> Option<&str> {
+        self.messages.get(name).map(|m| m.as_str())
+    }
+
+    pub fn choice_labels(&self, name: &str) -> HashMap<String, String> {
+        self.choices.get(name).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_falls_back_to_primary_language() {
+        let dir = tempdir::TempDir::new("locale").unwrap();
+        std::fs::write(
+            dir.path().join("messages.zh.toml"),
+            r#"
+            [messages]
+            name = "你的名字?"
+            "#,
+        )
+        .unwrap();
+
+        let locale = Locale::load(dir.path(), "zh_CN").unwrap().unwrap();
+        assert_eq!(locale.message("name"), Some("你的名字?"));
+        assert_eq!(locale.message("age"), None);
+        assert!(Locale::load(dir.path(), "fr_FR").unwrap().is_none());
+    }
+}
@@ -1,25 +1,49 @@
 use std::{
-    collections::HashMap,
-    fs::read_to_string,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::IsTerminal,
     path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
 };
 
-use ::crossterm::style::{Color, Stylize};
-use clap::{Parser, Subcommand};
+use ::crossterm::{
+    event,
+    style::Stylize,
+    terminal::{self, Clear, ClearType},
+};
+use clap::{Parser, Subcommand, ValueEnum};
 use inquire::error::InquireError;
+use notify::Watcher;
 use petridish::{
+    answers::Answers,
+    auth::{Credentials, HttpAuth},
     cache::Cache,
-    config::{Config, Prompt},
+    config::{
+        edit_answer, section_header, selected_follow_up, suggest_project_var_name, Config,
+        PetridishConfig, Prompt, PromptType, PromptValues,
+    },
     error::Error,
-    render::Render,
+    extends::resolve_bases,
+    globals::Globals,
+    head_revision,
+    init::init_template,
+    list_templates,
+    locale::Locale,
+    registry::{Registry, REGISTRY_ENV},
+    render::{prepare_output_dir, Render, RenderReport},
+    requirement::check_requirements,
     try_new_repo,
+    update::update_hint,
+    variables::template_variables,
+    watch::{debounce, rerender, Rerender},
+    Revision,
 };
 use tabled::{
     object::{Columns, FirstRow, Segment},
     Alignment, Format, Modify, Style, Table, Tabled,
 };
 use tera::Context;
-use termimad::*;
+use termimad::{crossterm::style::Color, *};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -27,9 +51,33 @@ use termimad::*;
 struct Args {
     #[clap(subcommand)]
     command: Commands,
+
+    #[clap(
+        long,
+        global = true,
+        action,
+        help = "Disable colored output, which is also disabled by NO_COLOR or when stdout is not a terminal"
+    )]
+    no_color: bool,
+
+    #[clap(
+        long,
+        global = true,
+        action,
+        help = "Check whether a newer petridish is published, at most once a day, and print how to upgrade"
+    )]
+    check_update: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
 }
 
 #[derive(Subcommand, Debug)]
+// parsed once, boxing the args of `new` buys nothing
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     #[clap(about = "Generate new project")]
     New {
@@ -60,31 +108,377 @@ enum Commands {
         )]
         output_dir: Option<PathBuf>,
 
-        /*
         #[clap(
-        value_parser,
-        action = ArgAction::Set,
-        default_value = "",
-        hide_default_value = true,
-        help = "Add default prompt values, format should be like <key>=<value>"
+            value_parser,
+            help = "Prompt values, format should be like <key>=<value>, e.g. project_name=awesome"
         )]
-        extra_context: Vec<String>, */
+        extra_context: Vec<String>,
+
         #[clap(
             value_parser,
             long,
-            help = "The username and password used for authorization, format should be like <username>:<password>"
+            help = "The username and password used for authorization, format should be like <username>:<password>, PETRIDISH_GIT_USERNAME and PETRIDISH_GIT_PASSWORD take precedence"
         )]
         auth: Option<String>,
 
+        #[clap(
+            value_parser,
+            long,
+            help = "The bearer token sent with http(s) downloads like the registry index, PETRIDISH_TOKEN takes precedence"
+        )]
+        token: Option<String>,
+
+        #[clap(
+            long,
+            value_parser,
+            help = "Cache the git template under this name instead of the repo name, e.g. to use it later by this name"
+        )]
+        name: Option<String>,
+
         #[clap(
             value_parser,
             long,
             help = "Check into the branch, tag or commit after git clone"
         )]
         branch: Option<String>,
+
+        #[clap(
+            value_parser,
+            long,
+            help = "Give up git operations after the given seconds"
+        )]
+        git_timeout: Option<u64>,
+
+        #[clap(
+            value_parser,
+            long,
+            help = "The user of ssh git urls, defaults to the one of the url or else `git`"
+        )]
+        ssh_user: Option<String>,
+
+        #[clap(
+            value_parser,
+            long,
+            help = "The language of prompt messages, defaults to $LANG, e.g. zh_CN"
+        )]
+        locale: Option<String>,
+
+        #[clap(
+            long,
+            action,
+            help = "Print the template context as JSON to stderr after each prompt"
+        )]
+        trace_context: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Print the config with defines, base templates, --prompts-from and answers applied, then exit without prompting"
+        )]
+        print_config: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Create the directories of the template even if they are empty"
+        )]
+        keep_empty_dirs: bool,
+
+        #[clap(
+            long,
+            action,
+            requires = "keep-empty-dirs",
+            help = "With --keep-empty-dirs, don't create the dirs left empty because all of their files were skipped"
+        )]
+        prune_empty_dirs: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Download the git template again even if it is already cached"
+        )]
+        refresh: bool,
+
+        #[clap(
+            long,
+            action,
+            conflicts_with = "refresh",
+            help = "Only use the cached git template, fail if it has not been downloaded"
+        )]
+        use_cache: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Render the project files directly into the output dir without the project dir"
+        )]
+        in_place: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Fail when a PETRIDISH_VAR_<NAME> value doesn't fit its prompt instead of asking for it"
+        )]
+        strict: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Create the missing parent directories of the output dir"
+        )]
+        create_output_dir: bool,
+
+        #[clap(
+            long,
+            value_parser,
+            help = "Skip the prompt and take its default, can be repeated"
+        )]
+        exclude: Vec<String>,
+
+        #[clap(
+            long,
+            action,
+            help = "Take the defaults of select prompts without asking, other prompts are still asked"
+        )]
+        auto_select_defaults: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Render into a temporary directory first and only move the files into place when everything succeeded"
+        )]
+        atomic: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Print what would change in the existing output as unified diffs instead of writing it"
+        )]
+        diff: bool,
+
+        #[clap(
+            long,
+            value_parser,
+            help = "A JSON index mapping template names to git uris, defaults to $PETRIDISH_REGISTRY"
+        )]
+        registry: Option<String>,
+
+        #[clap(
+            long,
+            value_parser,
+            value_name = "SECONDS",
+            help = "Take the default of a prompt when nothing is typed for this long, prompts without a default fail"
+        )]
+        prompt_timeout: Option<u64>,
+
+        #[clap(
+            long,
+            action,
+            help = "Fail when a template references a variable which has no value, naming the file"
+        )]
+        strict_vars: bool,
+
+        #[clap(
+            long,
+            action,
+            conflicts_with = "strict-vars",
+            help = "Render variables which have no value as <<MISSING:name>> instead of failing"
+        )]
+        undefined_placeholder: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Go on rendering the other files when one fails to render and report all of the failures at the end"
+        )]
+        keep_going: bool,
+
+        #[clap(
+            long,
+            action,
+            requires = "keep-going",
+            help = "With --keep-going, still write the files which rendered"
+        )]
+        write_partial: bool,
+
+        #[clap(
+            short,
+            long,
+            action,
+            help = "Don't print the description of the template, only prompts and errors are shown"
+        )]
+        quiet: bool,
+
+        #[clap(
+            long,
+            value_enum,
+            default_value_t = OutputFormat::Text,
+            conflicts_with = "diff",
+            help = "Print a JSON summary of the generated project on stdout, implies --quiet"
+        )]
+        format: OutputFormat,
+
+        #[clap(
+            long,
+            alias = "use-last",
+            action,
+            help = "Offer the answers of the last run of the template as defaults and remember this run's answers, secrets are never stored"
+        )]
+        remember: bool,
+
+        #[clap(
+            long,
+            action,
+            help = "Show the answers before generating, with the option to edit one of them"
+        )]
+        review: bool,
+
+        #[clap(
+            short = 'D',
+            long,
+            value_parser,
+            value_name = "KEY=VALUE",
+            help = "Add a string value to the template context without a prompt, can be repeated"
+        )]
+        define: Vec<String>,
+
+        #[clap(
+            long,
+            value_parser,
+            value_name = "KEY=JSON",
+            help = "Like --define but the value is JSON, e.g. jobs=4, debug=true or targets='[\"linux\"]'"
+        )]
+        define_json: Vec<String>,
+
+        #[clap(
+            long,
+            value_parser = glob_pattern,
+            value_name = "GLOB",
+            help = "Only generate the files matching the glob relative to the project dir, e.g. \"src/**\", can be repeated"
+        )]
+        only: Vec<String>,
+
+        #[clap(
+            long,
+            value_parser,
+            value_name = "PATH",
+            help = "Write the answers to this JSON file after every prompt, so an interrupted run can be resumed with --answers"
+        )]
+        answers_out: Option<PathBuf>,
+
+        #[clap(
+            long,
+            value_parser,
+            value_name = "PATH",
+            help = "Offer the answers of a file written by --answers-out as defaults"
+        )]
+        answers: Option<PathBuf>,
+
+        #[clap(
+            long,
+            value_parser,
+            value_name = "PATH",
+            help = "Also ask the prompts of this TOML file after the template's own, can be repeated"
+        )]
+        prompts_from: Vec<PathBuf>,
+
+        #[clap(
+            short,
+            long,
+            action,
+            help = "Print whether each file was rendered, copied as it is or skipped"
+        )]
+        verbose: bool,
+
+        #[clap(
+            long,
+            action,
+            conflicts_with_all = &["force", "skip", "diff"],
+            help = "Update an existing project, keeping its changes since the last merge and writing <file>.new on conflicts"
+        )]
+        merge: bool,
+    },
+    #[clap(about = "Create a starter petridish template")]
+    Init {
+        #[clap(
+            value_parser,
+            default_value = ".",
+            help = "Where to create the template"
+        )]
+        dir: PathBuf,
+
+        #[clap(
+            short,
+            long,
+            action,
+            help = "Create the template even if the directory is not empty"
+        )]
+        force: bool,
     },
     #[clap(about = "List all cached templates")]
     List,
+    #[clap(about = "Print the JSON schema of petridish.toml")]
+    Schema,
+    #[clap(about = "List the variables the files of a template use, failing on undeclared ones")]
+    Vars {
+        #[clap(value_parser, help = "The cached template name, git uri or local path")]
+        template_uri: String,
+    },
+    #[clap(about = "Print the directory of a cached template")]
+    Where {
+        #[clap(value_parser, help = "The cached template name, git uri or local path")]
+        template_uri: String,
+    },
+    #[clap(about = "Render a single file of a template and print it")]
+    RenderFile {
+        #[clap(value_parser, help = "The cached template name, git uri or local path")]
+        template_uri: String,
+
+        #[clap(
+            value_parser,
+            help = "The file path relative to the project dir of the template, e.g. README.md"
+        )]
+        relative_path: String,
+
+        #[clap(
+            value_parser,
+            help = "Prompt values, format should be like <key>=<value>, other prompts take their defaults"
+        )]
+        extra_context: Vec<String>,
+
+        #[clap(
+            long,
+            value_parser,
+            help = "Write the rendered file here instead of stdout"
+        )]
+        dump_rendered: Option<PathBuf>,
+    },
+    #[clap(about = "Generate a template again whenever its files change, e.g. while writing it")]
+    Watch {
+        #[clap(value_parser, help = "The cached template name or local path")]
+        template_uri: String,
+
+        #[clap(
+            short,
+            long,
+            value_parser,
+            help = "Where to output the generated project dir into"
+        )]
+        output_dir: Option<PathBuf>,
+
+        #[clap(
+            value_parser,
+            help = "Prompt values, format should be like <key>=<value>, other prompts take their defaults"
+        )]
+        extra_context: Vec<String>,
+
+        #[clap(
+            long,
+            value_parser,
+            help = "Answer the prompts from a JSON file like the one written by --answers-out"
+        )]
+        answers: Option<PathBuf>,
+    },
     #[clap(about = "Remove cached template")]
     Remove {
         #[clap(value_parser, help = "cached template name")]
@@ -94,6 +488,15 @@ enum Commands {
 
 fn entry() -> petridish::error::Result<()> {
     let args = Args::parse();
+    let colors = colors_enabled(args.no_color);
+    if !colors {
+        inquire::set_global_render_config(inquire::ui::RenderConfig::empty());
+        let _ = miette::set_hook(Box::new(|_| {
+            Box::new(miette::MietteHandlerOpts::new().color(false).build())
+        }));
+    }
+    Cache::migrate()?;
+    let check_update = args.check_update;
 
     match args.command {
         Commands::New {
@@ -101,191 +504,1279 @@ fn entry() -> petridish::error::Result<()> {
             force,
             skip,
             output_dir,
-            // extra_context: _,
+            extra_context,
             auth,
+            token,
+            name,
             branch,
+            git_timeout,
+            ssh_user,
+            locale,
+            trace_context,
+            print_config,
+            keep_empty_dirs,
+            prune_empty_dirs,
+            refresh,
+            use_cache,
+            in_place,
+            strict,
+            create_output_dir,
+            exclude,
+            auto_select_defaults,
+            atomic,
+            diff,
+            registry,
+            prompt_timeout,
+            strict_vars,
+            undefined_placeholder,
+            keep_going,
+            write_partial,
+            quiet,
+            format,
+            remember,
+            review,
+            define,
+            define_json,
+            only,
+            answers_out,
+            answers,
+            prompts_from,
+            verbose,
+            merge,
         } => {
-            let mut context = HashMap::new();
-            if let Some(auth) = auth.as_ref() {
-                let splitted_auth = auth.split(':').collect::<Vec<&str>>();
-                if splitted_auth.len() != 2 {
-                    Err(Error::ArgsError(format!(
-                        "auth '{}' is invalid, should be like <username>:<password>",
-                        auth
-                    )))?;
+            let json = format == OutputFormat::Json;
+            let credentials = Credentials::resolve(auth.as_deref(), |key| std::env::var(key).ok())?;
+            let template_uri = match registry.or_else(|| std::env::var(REGISTRY_ENV).ok()) {
+                Some(registry) => {
+                    let http_auth =
+                        HttpAuth::resolve(credentials.clone(), token.as_deref(), |key| {
+                            std::env::var(key).ok()
+                        });
+                    Registry::load(&registry, refresh, http_auth.as_ref())?
+                        .resolve(&template_uri)
+                        .map(String::from)
+                        .unwrap_or(template_uri)
                 }
-                context.insert("username".to_string(), splitted_auth[0].to_string());
-                context.insert("password".to_string(), splitted_auth[1].to_string());
+                None => template_uri,
+            };
+            let extra_context = parse_extra_context(extra_context)?;
+            let defines = parse_defines(define, define_json)?;
+            let output_path = output_dir.unwrap_or_default();
+            prepare_output_dir(&output_path, create_output_dir)?;
+            let mut context = HashMap::new();
+            if let Some(credentials) = credentials {
+                context.insert("username".to_string(), credentials.username);
+                context.insert("password".to_string(), credentials.password);
             }
 
             if let Some(branch) = branch.as_ref() {
                 context.insert("branch".to_string(), branch.to_string());
             }
 
-            let repo = if regex::Regex::new(r"^[\w-]+$")
-                .unwrap()
-                .is_match(&template_uri)
-                && !Path::new(&template_uri).exists()
-            {
-                let path = Cache::get(&template_uri)
-                    .ok_or_else(|| Error::RepoNotFoundInCache(template_uri.to_string()))?;
+            if let Some(timeout) = git_timeout {
+                context.insert("timeout".to_string(), timeout.to_string());
+            }
+            if let Some(ssh_user) = ssh_user {
+                context.insert("ssh_user".to_string(), ssh_user);
+            }
+
+            // bases are downloaded like the template, but from their default branch
+            let base_context = context.clone();
+            if let Some(name) = name {
+                context.insert("name".to_string(), name);
+            }
+
+            let repo = if is_cached_name(&template_uri) {
+                let path = Cache::find(&template_uri)?;
                 try_new_repo(path.display().to_string(), context.clone())?
             } else {
                 let repo = try_new_repo(template_uri.clone(), context.clone())?;
-                if repo.need_cache()
-                    && Cache::get(repo.name()).is_some()
-                    && !inquire::Confirm::new(&format!(
-                        "You've downloaded '{}' before. Is it okay to re-download it?",
-                        repo.name()
-                    ))
-                    .with_default(true)
-                    .prompt()?
-                {
-                    return Ok(());
-                }
-
-                match repo.download() {
-                    Err(Error::GitError(e)) => {
-                        if e.code() == git2::ErrorCode::Auth {
-                            let username = inquire::Text::new("git username").prompt()?;
-                            let password = inquire::Password::new("git password").prompt()?;
-                            context.insert("username".to_string(), username);
-                            context.insert("password".to_string(), password);
-                            let repo = try_new_repo(template_uri, context)?;
-                            repo.download()?;
-                            repo
-                        } else {
-                            return Err(Error::GitError(e));
+                let cached = repo.is_cached();
+                if cached && !refresh {
+                    // reuse the cached template instead of cloning it again
+                    repo
+                } else if use_cache && repo.need_cache() {
+                    return Err(Error::RepoNotFoundInCache {
+                        name: template_uri,
+                        suggestion: None,
+                    });
+                } else {
+                    match repo.download() {
+                        Err(Error::GitError(e)) => {
+                            if e.code() == git2::ErrorCode::Auth {
+                                let username = inquire::Text::new("git username").prompt()?;
+                                let password = inquire::Password::new("git password").prompt()?;
+                                context.insert("username".to_string(), username);
+                                context.insert("password".to_string(), password);
+                                let repo = try_new_repo(template_uri.clone(), context)?;
+                                repo.download()?;
+                                repo
+                            } else {
+                                return Err(Error::GitError(e));
+                            }
                         }
+                        Err(e) => return Err(e),
+                        _ => repo,
                     }
-                    Err(e) => return Err(e),
-                    _ => repo,
                 }
             };
 
-            let petridish_config = repo.repo_dir().join("petridish.toml");
-            let petridish_config =
-                toml::from_str::<Config>(&read_to_string(&petridish_config).map_err(|e| {
-                    Error::PathNotFound {
-                        source: e,
-                        path: petridish_config,
-                    }
-                })?)?;
+            let mut define_context = Context::new();
+            for (key, value) in &defines {
+                define_context.insert(key, value);
+            }
+            let mut petridish_config =
+                Config::load_with_context(&repo.repo_dir(), &define_context)?;
+            let bases = resolve_bases(
+                &template_uri,
+                &repo.repo_dir(),
+                &petridish_config,
+                |uri, extending_dir| {
+                    fetch_base(uri, extending_dir, base_context.clone(), refresh, use_cache)
+                },
+            )?;
+            let mut base_dirs = vec![];
+            for base in bases.into_iter().rev() {
+                base_dirs.push((
+                    base.dir,
+                    format!(
+                        "{{{{ {} }}}}",
+                        base.config.petridish_config.project_var_name
+                    ),
+                ));
+                petridish_config.inherit_prompts(base.config)?;
+            }
+            base_dirs.reverse();
+            for path in &prompts_from {
+                petridish_config.overlay_prompts(path)?;
+            }
+            check_excludes(&petridish_config, &exclude)?;
+            check_requirements(&petridish_config.petridish_config.requires)?;
+            if let Some(lang) = Locale::detect(locale) {
+                if let Some(locale) = Locale::load(&repo.repo_dir(), &lang)? {
+                    petridish_config.localize(&locale);
+                }
+            }
+
             let entry_dir_name = format!(
                 "{{{{ {} }}}}",
                 petridish_config.petridish_config.project_var_name
             );
             let entry_dir = repo.repo_dir().join(&entry_dir_name);
             if !entry_dir.exists() {
-                return Err(Error::PathNotFound {
-                    source: std::io::Error::new(
-                        std::io::ErrorKind::NotFound,
-                        "No such file or directory (os error 2)",
-                    ),
+                return Err(Error::EntryDirNotFound {
                     path: entry_dir,
+                    suggestion: suggest_project_var_name(&repo.repo_dir()).map(|name| {
+                        format!(
+                            "did you mean to set `project_var_name = \"{}\"` in petridish.toml?",
+                            name
+                        )
+                    }),
                 });
             }
 
-            // show description
-            let description = petridish_config
-                .petridish_config
-                .long_description
-                .or(petridish_config.petridish_config.short_description);
-            if let Some(description) = description {
-                let mut skin = MadSkin::default();
-                skin.set_headers_fg(rgb(255, 187, 0));
-                skin.bold.set_fg(Color::Yellow);
-                skin.italic.set_fgbg(Color::Magenta, rgb(30, 30, 40));
-                println!("{}", skin.term_text(&description));
+            if let Some(banner) = description_banner(
+                &petridish_config.petridish_config,
+                colors,
+                quiet || json || print_config,
+            ) {
+                println!("{}", banner);
             }
 
             // start prompting
+            let secrets = petridish_config.secret_names();
+            let answers_path = remember.then(|| Answers::path(repo.name()));
+            let mut remembered = match &answers_path {
+                Some(answers_path) => Answers::load(answers_path).unwrap_or_default(),
+                None => Answers::default(),
+            };
+            if let Some(path) = &answers {
+                let answers = Answers::load(path).ok_or_else(|| {
+                    Error::ArgsError(format!("cannot read answers from '{}'", path.display()))
+                })?;
+                remembered.0.extend(answers.0);
+            }
+            let values = PromptValues {
+                defines: defines.iter().cloned().collect(),
+                extra_context: extra_context.clone(),
+                answers: remembered.0.clone(),
+                ..Default::default()
+            }
+            .with_env();
+            petridish_config.prefill(&values);
+            if print_config {
+                print!("{}", petridish_config.to_toml());
+                return Ok(());
+            }
+            let mut unremembered = petridish_config.unremembered_names();
+            unremembered.insert("globals".to_string());
+            unremembered.extend(defines.iter().map(|(key, _)| key.clone()));
             let mut prompt_context = Context::new();
+            if let Some(globals) = load_globals()? {
+                globals.merge_into(&mut prompt_context);
+            }
+            // defines are only context, prompts of the same name are still asked
+            prompt_context.extend(define_context);
 
-            let project_name =
-                inquire::Text::new(&petridish_config.petridish_config.project_prompt).prompt()?;
+            let project_var_name = &petridish_config.petridish_config.project_var_name;
+            let project_name = match given_project_name(project_var_name, &values) {
+                (Some(project_name), _) => project_name,
+                (None, default) if !std::io::stdin().is_terminal() => default.ok_or_else(|| {
+                    Error::ArgsError(format!(
+                        "`{}` is not provided, pass it like {}=<value>",
+                        project_var_name, project_var_name
+                    ))
+                })?,
+                (None, default) => {
+                    let mut prompt =
+                        inquire::Text::new(&petridish_config.petridish_config.project_prompt);
+                    prompt.default = default.as_deref();
+                    prompt.prompt()?
+                }
+            };
 
             prompt_context.insert(
-                petridish_config.petridish_config.project_var_name,
+                &petridish_config.petridish_config.project_var_name,
                 &project_name,
             );
+            if trace_context {
+                print_context(&prompt_context, &secrets);
+            }
+            record_answers(answers_out.as_deref(), &prompt_context, &unremembered)?;
+
+            let mut section = None;
+
+            let mut wait = |prompt: &PromptType| match prompt_timeout {
+                Some(timeout) => wait_for_input(prompt, Duration::from_secs(timeout)),
+                None => Ok(true),
+            };
+            // the prompts are used up by asking them
+            let review_prompts = if review {
+                petridish_config.prompts.clone()
+            } else {
+                vec![]
+            };
+            for mut prompt_type in petridish_config.prompts {
+                let follow_ups = prompt_type.take_follow_ups();
+                let name = prompt_type.name().to_string();
+                let auto_selected = if auto_select_defaults && values.answer(&name).is_none() {
+                    prompt_type.select_default(&prompt_context)?
+                } else {
+                    None
+                };
+                if exclude.contains(&name) {
+                    prompt_type.skip(&mut prompt_context)?;
+                } else if let Some(default) = auto_selected {
+                    prompt_context.insert(&name, &default);
+                } else {
+                    if let Some(header) = section_header(section.as_deref(), prompt_type.section())
+                    {
+                        let header = format!("=== {} ===", header);
+                        let header = if colors {
+                            header.yellow().bold().to_string()
+                        } else {
+                            header
+                        };
+                        // stdout is left to the summary
+                        if json {
+                            eprintln!("{}", header);
+                        } else {
+                            println!("{}", header);
+                        }
+                    }
+                    section = prompt_type.section().map(String::from);
+                    prompt_type.prompt_or_timeout(
+                        &mut prompt_context,
+                        strict,
+                        &values,
+                        &mut wait,
+                    )?;
+                }
+                // e.g. a free text license after picking `custom`
+                if let Some(follow_up) = selected_follow_up(follow_ups, &name, &prompt_context) {
+                    follow_up.prompt_or_timeout(&mut prompt_context, strict, &values, &mut wait)?;
+                }
+                if trace_context {
+                    print_context(&prompt_context, &secrets);
+                }
+                record_answers(answers_out.as_deref(), &prompt_context, &unremembered)?;
+            }
 
-            for prompt_type in petridish_config.prompts {
-                prompt_type.prompt(&mut prompt_context)?;
+            if review {
+                review_answers(
+                    &review_prompts,
+                    &petridish_config.petridish_config.project_var_name,
+                    &mut prompt_context,
+                    &secrets,
+                )?;
+                record_answers(answers_out.as_deref(), &prompt_context, &unremembered)?;
             }
 
-            let output_path = output_dir.unwrap_or_default();
             let render = Render::new(
                 repo.repo_dir(),
                 &entry_dir_name,
-                output_path,
-                prompt_context,
+                output_path.clone(),
+                prompt_context.clone(),
                 force,
                 skip,
+                petridish_config.petridish_config.render_excludes(),
+            )
+            .with_keep_empty_dirs(keep_empty_dirs)
+            .with_prune_empty_dirs(prune_empty_dirs)
+            .with_in_place(in_place)
+            .with_render_suffix(petridish_config.petridish_config.render_suffix.clone())
+            .with_delimiters(petridish_config.petridish_config.delimiters.clone())
+            .with_trim_whitespace(petridish_config.petridish_config.trim_whitespace)
+            .with_line_endings(petridish_config.petridish_config.line_endings)
+            .with_atomic(atomic)
+            .with_strip_dir_marker(
                 petridish_config
                     .petridish_config
-                    .exclude_render_paths
-                    .clone(),
-            );
-            render.render()?;
-        }
-        Commands::List => {
-            let mut templates = vec![];
-
-            for path in Cache::list() {
-                let name = path.file_name().unwrap().to_str().unwrap();
-                let config = path.join("petridish.toml");
-                if !config.exists() {
-                    continue;
+                    .strip_gitkeep
+                    .then(|| petridish_config.petridish_config.dir_marker.clone()),
+            )
+            .with_output_subdir(petridish_config.petridish_config.output_subdir.clone())
+            .with_strict_vars(strict_vars)
+            .with_undefined_placeholder(undefined_placeholder)
+            .with_keep_going(keep_going)
+            .with_write_partial(write_partial)
+            .with_only(only)
+            .with_merge(merge)
+            .with_symlinks(petridish_config.petridish_config.symlinks)
+            .with_bases(base_dirs);
+            if diff {
+                print!("{}", render.diff()?);
+            } else {
+                let report = render.render()?;
+                if !quiet {
+                    warn_escaping_symlinks(&report);
+                }
+                if verbose {
+                    // stdout is left to the summary
+                    if json {
+                        eprint!("{}", report_lines(&report));
+                    } else {
+                        print!("{}", report_lines(&report));
+                    }
+                }
+                if let Some(answers_path) = &answers_path {
+                    Answers::from_context(&prompt_context, &unremembered).save(answers_path)?;
+                }
+                if json {
+                    let summary = run_summary(
+                        repo.name(),
+                        head_revision(&repo.repo_dir()),
+                        &output_path,
+                        &report,
+                        &prompt_context,
+                        &secrets,
+                    );
+                    println!("{}", serde_json::to_string_pretty(&summary).unwrap());
                 }
-                let config = toml::from_str::<Config>(&read_to_string(&config).unwrap()).unwrap();
-                let description = config
-                    .petridish_config
-                    .short_description
-                    .unwrap_or_default();
-                templates.push(CachedTemplate {
-                    name: name.to_string(),
-                    description,
-                });
             }
+        }
+        Commands::Init { dir, force } => {
+            init_template(&dir, force)?;
+        }
+        Commands::List => {
+            let templates = list_templates()
+                .into_iter()
+                .map(|template| CachedTemplate {
+                    name: template.name,
+                    description: template.description.unwrap_or_default(),
+                    branch: template.branches.join(", "),
+                })
+                .collect::<Vec<_>>();
+            println!("{}", templates_table(templates, colors));
+        }
+        Commands::Schema => {
             println!(
                 "{}",
-                Table::new(templates)
-                    .with(Style::blank())
-                    .with(Modify::new(Segment::all()).with(Alignment::left()))
-                    .with(Modify::new(FirstRow).with(Format::new(|s| s.yellow().to_string())))
-                    .with(
-                        Modify::new(Columns::single(1)).with(Format::new(|s| s.blue().to_string()))
-                    )
+                serde_json::to_string_pretty(&Config::json_schema()).unwrap()
             );
         }
+        Commands::Vars { template_uri } => {
+            let template_dir = cached_template_dir(template_uri)?;
+            let config = Config::load(&template_dir)?;
+            let entry_dir_name = format!("{{{{ {} }}}}", config.petridish_config.project_var_name);
+            let variables = template_variables(
+                &template_dir,
+                &entry_dir_name,
+                &config.petridish_config.render_excludes(),
+                &config.petridish_config.delimiters,
+            )?;
+            let mut declared = config.prompt_names();
+            declared.insert("globals".to_string());
+            print!("{}", variables_lines(&variables, &declared));
+            let undeclared = variables
+                .into_keys()
+                .filter(|name| !declared.contains(name))
+                .collect::<Vec<_>>();
+            if !undeclared.is_empty() {
+                return Err(Error::UndeclaredVariables(undeclared));
+            }
+        }
+        Commands::Where { template_uri } => {
+            let path = cached_template_dir(template_uri)?;
+            println!("{}", path.canonicalize().unwrap_or(path).display());
+        }
+        Commands::RenderFile {
+            template_uri,
+            relative_path,
+            extra_context,
+            dump_rendered,
+        } => {
+            let template_dir = cached_template_dir(template_uri)?;
+            let config = Config::load(&template_dir)?;
+            let values = PromptValues {
+                extra_context: parse_extra_context(extra_context)?,
+                ..Default::default()
+            }
+            .with_env();
+            let mut context = config.context_from(&values)?;
+            if let Some(globals) = load_globals()? {
+                globals.merge_into(&mut context);
+            }
+            let entry_dir_name = format!("{{{{ {} }}}}", config.petridish_config.project_var_name);
+            let rendered = Render::new(
+                &template_dir,
+                &entry_dir_name,
+                PathBuf::new(),
+                context,
+                false,
+                false,
+                vec![],
+            )
+            .with_delimiters(config.petridish_config.delimiters)
+            .with_trim_whitespace(config.petridish_config.trim_whitespace)
+            .with_line_endings(config.petridish_config.line_endings)
+            .render_file(&relative_path)?;
+            match dump_rendered {
+                Some(path) => std::fs::write(&path, rendered)
+                    .map_err(|e| Error::CannotWriteFile { source: e, path })?,
+                None => print!("{}", rendered),
+            }
+        }
+        Commands::Watch {
+            template_uri,
+            output_dir,
+            extra_context,
+            answers,
+        } => {
+            let template_dir = cached_template_dir(template_uri)?;
+            // the paths of changes are absolute
+            let template_dir = template_dir
+                .canonicalize()
+                .map_err(|e| Error::PathNotFound {
+                    source: e,
+                    path: template_dir,
+                })?;
+            let values = parse_extra_context(extra_context)?;
+            let answers = match answers {
+                Some(path) => Answers::load(&path).ok_or_else(|| {
+                    Error::ArgsError(format!("cannot read answers from '{}'", path.display()))
+                })?,
+                None => Answers::default(),
+            };
+            let output_path = output_dir.unwrap_or_default();
+            prepare_output_dir(&output_path, false)?;
+            let render = |changed| match watch_render(
+                &template_dir,
+                &output_path,
+                &values,
+                &answers,
+                changed,
+            ) {
+                Ok(report) => print!("{}", report_lines(&report)),
+                Err(e) => eprintln!("{:?}", miette::Report::new(e)),
+            };
+
+            render(None);
+            let (tx, rx) = std::sync::mpsc::channel();
+            // events stop once the watcher is dropped
+            let _watcher =
+                notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                    if let Ok(event) = event {
+                        if !event.kind.is_access() {
+                            for path in event.paths {
+                                let _ = tx.send(path);
+                            }
+                        }
+                    }
+                })
+                .and_then(|mut watcher| {
+                    watcher.watch(&template_dir, notify::RecursiveMode::Recursive)?;
+                    Ok(watcher)
+                })
+                .map_err(|e| {
+                    Error::ArgsError(format!("cannot watch '{}': {}", template_dir.display(), e))
+                })?;
+            eprintln!(
+                "watching '{}' for changes, press Ctrl-C to stop",
+                template_dir.display()
+            );
+            while let Some(changed) = debounce(&rx, WATCH_DEBOUNCE) {
+                let project_var_name = match Config::load(&template_dir) {
+                    Ok(config) => config.petridish_config.project_var_name,
+                    Err(e) => {
+                        eprintln!("{:?}", miette::Report::new(e));
+                        continue;
+                    }
+                };
+                let entry_dir_name = format!("{{{{ {} }}}}", project_var_name);
+                match rerender(&template_dir, &entry_dir_name, changed) {
+                    Some(Rerender::All) => render(None),
+                    Some(Rerender::Files(files)) => render(Some(files)),
+                    None => {}
+                }
+            }
+        }
         Commands::Remove { name } => {
-            let path = Cache::get(&name).ok_or(Error::RepoNotFoundInCache(name))?;
+            let path = Cache::find(&name)?;
             std::fs::remove_dir_all(path).unwrap();
         }
     }
 
+    if check_update {
+        if let Some(hint) = update_hint() {
+            eprintln!("{}", hint);
+        }
+    }
     Ok(())
 }
 
+/// Returns the directory of a template which is already available, i.e. a
+/// cached one or a local path, without downloading anything.
+fn cached_template_dir(template_uri: String) -> petridish::error::Result<PathBuf> {
+    if is_cached_name(&template_uri) {
+        return Cache::find(&template_uri);
+    }
+
+    let repo = try_new_repo(template_uri.clone(), HashMap::new())?;
+    if repo.need_cache() && !repo.is_cached() {
+        return Err(Error::RepoNotFoundInCache {
+            name: template_uri,
+            suggestion: None,
+        });
+    }
+    Ok(repo.repo_dir())
+}
+
+/// How long `watch` waits for more changes before generating, e.g. while an
+/// editor saves all of its files.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Generates the template for `watch` without prompting, all of it or just
+/// the `changed` files. The config is loaded again every time, it may have
+/// changed as well.
+fn watch_render(
+    template_dir: &Path,
+    output_path: &Path,
+    values: &HashMap<String, String>,
+    answers: &Answers,
+    changed: Option<BTreeSet<PathBuf>>,
+) -> petridish::error::Result<RenderReport> {
+    let config = Config::load(template_dir)?;
+    let values = PromptValues {
+        extra_context: values.clone(),
+        answers: answers.0.clone(),
+        ..Default::default()
+    }
+    .with_env();
+    let mut context = config.context_from(&values)?;
+    let project_var_name = &config.petridish_config.project_var_name;
+    if !context.contains_key(project_var_name) {
+        return Err(Error::ArgsError(format!(
+            "`{}` is not provided, pass it like {}=<value>",
+            project_var_name, project_var_name
+        )));
+    }
+    if let Some(globals) = load_globals()? {
+        globals.merge_into(&mut context);
+    }
+
+    let entry_dir_name = format!("{{{{ {} }}}}", project_var_name);
+    let petridish_config = config.petridish_config;
+    let mut render = Render::new(
+        template_dir,
+        &entry_dir_name,
+        output_path,
+        context,
+        true,
+        false,
+        petridish_config.render_excludes(),
+    )
+    .with_render_suffix(petridish_config.render_suffix.clone())
+    .with_delimiters(petridish_config.delimiters.clone())
+    .with_trim_whitespace(petridish_config.trim_whitespace)
+    .with_line_endings(petridish_config.line_endings)
+    .with_strip_dir_marker(
+        petridish_config
+            .strip_gitkeep
+            .then(|| petridish_config.dir_marker.clone()),
+    )
+    .with_output_subdir(petridish_config.output_subdir.clone())
+    .with_symlinks(petridish_config.symlinks);
+    if let Some(changed) = changed {
+        render = render.with_changed(changed);
+    }
+    render.render()
+}
+
+/// Downloads the base template at `uri` unless it's cached, a relative path
+/// is relative to the template extending it.
+fn fetch_base(
+    uri: &str,
+    extending_dir: &Path,
+    context: HashMap<String, String>,
+    refresh: bool,
+    use_cache: bool,
+) -> petridish::error::Result<PathBuf> {
+    let relative_dir = extending_dir.join(uri);
+    if Path::new(uri).is_relative() && relative_dir.join("petridish.toml").exists() {
+        return Ok(relative_dir);
+    }
+
+    let repo = if is_cached_name(uri) {
+        try_new_repo(Cache::find(uri)?.display().to_string(), context)?
+    } else {
+        try_new_repo(uri.to_string(), context)?
+    };
+    if repo.need_cache() && (refresh || !repo.is_cached()) {
+        if use_cache && !repo.is_cached() {
+            return Err(Error::RepoNotFoundInCache {
+                name: uri.to_string(),
+                suggestion: None,
+            });
+        }
+        repo.download()?;
+    }
+    Ok(repo.repo_dir())
+}
+
+/// Whether the template uri refers to a template in the cache by its name
+/// rather than a git uri or a local path.
+fn is_cached_name(template_uri: &str) -> bool {
+    regex::Regex::new(r"^[\w-]+$")
+        .unwrap()
+        .is_match(template_uri)
+        && !Path::new(template_uri).exists()
+}
+
+fn parse_extra_context(
+    extra_context: Vec<String>,
+) -> petridish::error::Result<HashMap<String, String>> {
+    extra_context
+        .into_iter()
+        .map(|item| match item.split_once('=') {
+            Some((key, value)) => Ok((key.to_string(), value.to_string())),
+            None => Err(Error::ArgsError(format!(
+                "extra context '{}' is invalid, should be like <key>=<value>",
+                item
+            ))),
+        })
+        .collect()
+}
+
+fn glob_pattern(pattern: &str) -> Result<String, String> {
+    globset::Glob::new(pattern)
+        .map(|_| pattern.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Saves the answers given so far for `--answers-out`, so they survive a
+/// canceled or failed run.
+fn record_answers(
+    path: Option<&Path>,
+    context: &Context,
+    excluded: &BTreeSet<String>,
+) -> petridish::error::Result<()> {
+    match path {
+        Some(path) => Answers::from_context(context, excluded).save(path),
+        None => Ok(()),
+    }
+}
+
+/// Parses `--define key=value` as strings and `--define-json key=value` as
+/// JSON values, the JSON ones are inserted last.
+fn parse_defines(
+    defines: Vec<String>,
+    json_defines: Vec<String>,
+) -> petridish::error::Result<Vec<(String, tera::Value)>> {
+    let invalid = |item: &str, format: &str| {
+        Error::ArgsError(format!(
+            "define '{}' is invalid, should be like <key>={}",
+            item, format
+        ))
+    };
+    let defines = defines.into_iter().map(|item| match item.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.into())),
+        _ => Err(invalid(&item, "<value>")),
+    });
+    let json_defines = json_defines.into_iter().map(|item| {
+        item.split_once('=')
+            .filter(|(key, _)| !key.is_empty())
+            .and_then(|(key, value)| Some((key.to_string(), serde_json::from_str(value).ok()?)))
+            .ok_or_else(|| invalid(&item, "<json>"))
+    });
+    defines.chain(json_defines).collect()
+}
+
+/// Shows which prompt is waiting and returns whether a key is pressed within
+/// `timeout`. The key is left for the prompt to read.
+fn wait_for_input(prompt: &PromptType, timeout: Duration) -> petridish::error::Result<bool> {
+    eprint!(
+        "? {} (the default is taken in {}s)",
+        prompt.name(),
+        timeout.as_secs()
+    );
+    let io_error = |e| Error::PromptError(InquireError::IO(e));
+    terminal::enable_raw_mode().map_err(io_error)?;
+    let pressed = event::poll(timeout);
+    terminal::disable_raw_mode().map_err(io_error)?;
+    let pressed = pressed.map_err(io_error)?;
+    // the prompt draws itself over the hint
+    eprint!("\r{}", Clear(ClearType::CurrentLine));
+    Ok(pressed)
+}
+
+/// Fails on `--exclude` names which are not prompts of the config, before
+/// anything is asked.
+/// The project name answered by `values` and the one offered as its default,
+/// resolved like the prompts, see [`PromptValues`].
+fn given_project_name(
+    project_var_name: &str,
+    values: &PromptValues,
+) -> (Option<String>, Option<String>) {
+    let answer = values.answer(project_var_name).map(|(_, name)| name);
+    let default = values
+        .given_default(project_var_name)
+        .map(|(_, name)| match name {
+            tera::Value::String(name) => name,
+            name => name.to_string(),
+        });
+    (answer, default)
+}
+
+fn check_excludes(config: &Config, exclude: &[String]) -> petridish::error::Result<()> {
+    match exclude
+        .iter()
+        .find(|name| !config.prompts.iter().any(|p| p.name() == *name))
+    {
+        Some(name) => Err(Error::ArgsError(format!(
+            "cannot exclude unknown prompt `{}`",
+            name
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn print_context(context: &Context, secrets: &BTreeSet<String>) {
+    eprintln!("{}", context_trace(context, secrets));
+}
+
+/// The context as pretty JSON for `--trace-context`, with the answers of
+/// `secrets` masked.
+fn context_trace(context: &Context, secrets: &BTreeSet<String>) -> String {
+    let mut context = context.clone().into_json();
+    if let Some(values) = context.as_object_mut() {
+        for (name, value) in values.iter_mut() {
+            if secrets.contains(name) {
+                *value = "***".into();
+            }
+        }
+    }
+    serde_json::to_string_pretty(&context).unwrap()
+}
+
+/// Shows the answers until they are confirmed, `edit` asks one of the
+/// prompts again.
+fn review_answers(
+    prompts: &[PromptType],
+    project_var_name: &str,
+    context: &mut Context,
+    secrets: &BTreeSet<String>,
+) -> petridish::error::Result<()> {
+    loop {
+        // the prompts along with the follow-ups of their answers
+        let names = prompts
+            .iter()
+            .flat_map(|prompt| std::iter::successors(Some(prompt), |p| p.follow_up(context)))
+            .map(|prompt| prompt.name().to_string())
+            .filter(|name| context.contains_key(name))
+            .collect::<Vec<_>>();
+        let mut shown = vec![project_var_name.to_string()];
+        shown.extend(names.iter().cloned());
+        eprint!("{}", answers_summary(&shown, context, secrets));
+
+        match inquire::Select::new("Generate the project?", vec!["yes", "no", "edit"]).prompt()? {
+            "yes" => return Ok(()),
+            "no" => return Err(Error::PromptError(InquireError::OperationCanceled)),
+            _ => {}
+        }
+        if names.is_empty() {
+            continue;
+        }
+        let name = inquire::Select::new("Which answer?", names).prompt()?;
+        edit_answer(prompts, &name, context, &mut |prompt, context| {
+            // the current answer is the default
+            let mut prompt = prompt.clone();
+            if let Some(answer) = context.get(prompt.name()) {
+                prompt.set_default(answer);
+            }
+            prompt.prompt(context)
+        })?;
+    }
+}
+
+/// The answers of `names`, one per line, with the ones of `secrets` masked.
+fn answers_summary(names: &[String], context: &Context, secrets: &BTreeSet<String>) -> String {
+    names
+        .iter()
+        .map(|name| {
+            let answer = match context.get(name) {
+                _ if secrets.contains(name) => "***".to_string(),
+                Some(tera::Value::String(answer)) => answer.clone(),
+                Some(answer) => answer.to_string(),
+                None => String::new(),
+            };
+            format!("  {}: {}\n", name, answer)
+        })
+        .collect()
+}
+
+/// A line per file of `--verbose`, e.g. `copied  demo/logo.png`.
+fn report_lines(report: &RenderReport) -> String {
+    let files = [
+        ("rendered", &report.rendered),
+        ("copied", &report.copied),
+        ("skipped", &report.skipped),
+        ("conflict", &report.conflicts),
+    ];
+    files
+        .into_iter()
+        .flat_map(|(kind, paths)| paths.iter().map(move |path| (kind, path)))
+        .map(|(kind, path)| format!("{:<8}  {}\n", kind, path.display()))
+        .collect()
+}
+
+/// Symlinks pointing outside of the project are dangling or leak files of the
+/// machine generating it, e.g. `/etc/passwd`.
+fn warn_escaping_symlinks(report: &RenderReport) {
+    for path in &report.escaping_symlinks {
+        eprintln!(
+            "warning: symlink '{}' points outside of the template, see `symlinks` in petridish.toml",
+            path.display()
+        );
+    }
+}
+
+/// A line per variable of `vars`, the undeclared ones name the files which
+/// read them.
+fn variables_lines(
+    variables: &BTreeMap<String, BTreeSet<PathBuf>>,
+    declared: &BTreeSet<String>,
+) -> String {
+    variables
+        .iter()
+        .map(|(name, paths)| {
+            if declared.contains(name) {
+                format!("{}\n", name)
+            } else {
+                let paths = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>();
+                format!("{}  (not declared, read by {})\n", name, paths.join(", "))
+            }
+        })
+        .collect()
+}
+
+/// The summary printed by `--format json`, the answers of `secrets` are
+/// masked and the globals are left out.
+fn run_summary(
+    template: &str,
+    revision: Option<Revision>,
+    output_dir: &Path,
+    report: &RenderReport,
+    context: &Context,
+    secrets: &BTreeSet<String>,
+) -> serde_json::Value {
+    let mut values = match context.clone().into_json() {
+        serde_json::Value::Object(values) => values,
+        _ => serde_json::Map::new(),
+    };
+    values.remove("globals");
+    for (name, value) in values.iter_mut() {
+        if secrets.contains(name) {
+            *value = "***".into();
+        }
+    }
+    let (reference, sha) = match revision {
+        Some(revision) => (revision.reference, Some(revision.sha)),
+        None => (None, None),
+    };
+
+    serde_json::json!({
+        "template": template,
+        "ref": reference,
+        "sha": sha,
+        "output_dir": output_dir,
+        "files": {
+            "created": report.rendered.len() + report.copied.len(),
+            "skipped": report.skipped.len(),
+            "conflicts": report.conflicts.len(),
+        },
+        "values": values,
+    })
+}
+
 #[derive(Tabled)]
 struct CachedTemplate {
     name: String,
     description: String,
+    branch: String,
+}
+
+/// Whether output is styled, i.e. colors are not disabled by `--no-color` or
+/// a non-empty `NO_COLOR`, and stdout is a terminal.
+fn colors_enabled(no_color: bool) -> bool {
+    !no_color
+        && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        && std::io::stdout().is_terminal()
+}
+
+fn description_skin(colors: bool) -> MadSkin {
+    if !colors {
+        return MadSkin::no_style();
+    }
+
+    let mut skin = MadSkin::default();
+    skin.set_headers_fg(rgb(255, 187, 0));
+    skin.bold.set_fg(Color::Yellow);
+    skin.italic.set_fgbg(Color::Magenta, rgb(30, 30, 40));
+    skin
 }
 
-fn main() -> anyhow::Result<()> {
-    if let Err(e) = entry() {
-        if matches!(
-            e,
-            Error::PromptError(InquireError::OperationCanceled)
-                | Error::PromptError(InquireError::OperationInterrupted)
-        ) {
-            return Ok(());
+/// The description shown before the first prompt, if any and not `quiet`.
+fn description_banner(config: &PetridishConfig, colors: bool, quiet: bool) -> Option<String> {
+    if quiet {
+        return None;
+    }
+    let description = config
+        .long_description
+        .as_ref()
+        .or(config.short_description.as_ref())?;
+    Some(description_skin(colors).term_text(description).to_string())
+}
+
+fn templates_table(templates: Vec<CachedTemplate>, colors: bool) -> String {
+    let mut table = Table::new(templates)
+        .with(Style::blank())
+        .with(Modify::new(Segment::all()).with(Alignment::left()));
+    if colors {
+        table = table
+            .with(Modify::new(FirstRow).with(Format::new(|s| s.yellow().to_string())))
+            .with(Modify::new(Columns::single(1)).with(Format::new(|s| s.blue().to_string())));
+    }
+    table.to_string()
+}
+
+fn load_globals() -> petridish::error::Result<Option<Globals>> {
+    match Globals::path() {
+        Some(path) => Globals::load(&path),
+        None => Ok(None),
+    }
+}
+
+fn main() -> ExitCode {
+    match entry() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let code = e.exit_code();
+            // canceling a prompt is not worth a report
+            if code != 130 {
+                eprintln!("{:?}", miette::Report::new(e));
+            }
+            ExitCode::from(code)
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Err(e)?
+    #[test]
+    fn test_args() {
+        use clap::CommandFactory;
+        Args::command().debug_assert();
     }
 
-    Ok(())
+    #[test]
+    fn test_record_answers() {
+        let dir = tempdir::TempDir::new("answers").unwrap();
+        let path = dir.path().join("partial.json");
+        let excluded = BTreeSet::from(["api_token".to_string(), "globals".to_string()]);
+        let mut context = Context::new();
+        context.insert("globals", &HashMap::from([("author", "me")]));
+        context.insert("project_name", "demo");
+
+        record_answers(None, &context, &excluded).unwrap();
+        assert!(!path.exists());
+
+        record_answers(Some(&path), &context, &excluded).unwrap();
+        let answers = Answers::load(&path).unwrap();
+        assert_eq!(answers.0.keys().collect::<Vec<_>>(), ["project_name"]);
+
+        // every prompt adds to the file, the run could stop after any of them
+        context.insert("api_token", "hunter2");
+        context.insert("features", &["cli"]);
+        record_answers(Some(&path), &context, &excluded).unwrap();
+        let answers = Answers::load(&path).unwrap();
+        assert_eq!(
+            answers.0.keys().collect::<Vec<_>>(),
+            ["features", "project_name"]
+        );
+        assert_eq!(answers.0["features"], serde_json::json!(["cli"]));
+    }
+
+    #[test]
+    fn test_parse_defines() {
+        let defines = parse_defines(
+            vec!["target=x86_64".into(), "empty=".into(), "jobs=4".into()],
+            vec![
+                "jobs=4".into(),
+                "debug=true".into(),
+                "targets=[\"linux\", \"macos\"]".into(),
+                "name=\"a=b\"".into(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            defines,
+            vec![
+                ("target".to_string(), serde_json::json!("x86_64")),
+                ("empty".to_string(), serde_json::json!("")),
+                ("jobs".to_string(), serde_json::json!("4")),
+                ("jobs".to_string(), serde_json::json!(4)),
+                ("debug".to_string(), serde_json::json!(true)),
+                ("targets".to_string(), serde_json::json!(["linux", "macos"])),
+                ("name".to_string(), serde_json::json!("a=b")),
+            ]
+        );
+
+        assert!(matches!(
+            parse_defines(vec!["target".into()], vec![]),
+            Err(Error::ArgsError(_))
+        ));
+        assert!(matches!(
+            parse_defines(vec!["=x86_64".into()], vec![]),
+            Err(Error::ArgsError(_))
+        ));
+        assert!(matches!(
+            parse_defines(vec![], vec!["debug=yes".into()]),
+            Err(Error::ArgsError(_))
+        ));
+    }
+
+    #[test]
+    fn test_templates_table_colors() {
+        let templates = || {
+            vec![CachedTemplate {
+                name: "rust-cli".into(),
+                description: "A rust cli".into(),
+                branch: "main".into(),
+            }]
+        };
+
+        let plain = templates_table(templates(), false);
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.contains("rust-cli"));
+        assert!(plain.contains("A rust cli"));
+        assert!(templates_table(templates(), true).contains('\x1b'));
+    }
+
+    #[test]
+    fn test_run_summary() {
+        let output_dir = tempdir::TempDir::new("summary").unwrap();
+        std::fs::create_dir_all(output_dir.path().join("demo")).unwrap();
+        std::fs::write(output_dir.path().join("demo").join("kept.txt"), "kept").unwrap();
+        let mut context = Context::new();
+        context.insert("project_name", "demo");
+        context.insert("token", "hunter2");
+        context.insert("globals", &HashMap::from([("author", "me")]));
+        let files = HashMap::from([
+            (
+                "{{ project_name }}/README.md".into(),
+                b"# {{ project_name }}".to_vec(),
+            ),
+            ("{{ project_name }}/kept.txt".into(), b"new".to_vec()),
+        ]);
+        let report = Render::from_files(
+            files,
+            "{{ project_name }}",
+            output_dir.path(),
+            context.clone(),
+            false,
+            true,
+            vec![],
+        )
+        .render()
+        .unwrap();
+
+        let summary = run_summary(
+            "demo-template",
+            Some(Revision {
+                reference: Some("main".into()),
+                sha: "abc123".into(),
+            }),
+            output_dir.path(),
+            &report,
+            &context,
+            &BTreeSet::from(["token".to_string()]),
+        );
+        assert_eq!(
+            summary,
+            serde_json::json!({
+                "template": "demo-template",
+                "ref": "main",
+                "sha": "abc123",
+                "output_dir": output_dir.path(),
+                "files": {"created": 1, "skipped": 1, "conflicts": 0},
+                "values": {"project_name": "demo", "token": "***"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_report_lines() {
+        let report = RenderReport {
+            rendered: vec!["demo/README.md".into()],
+            copied: vec!["demo/logo.png".into()],
+            skipped: vec!["demo/kept.txt".into()],
+            conflicts: vec!["demo/Cargo.toml".into()],
+            escaping_symlinks: vec![],
+        };
+        assert_eq!(
+            report_lines(&report),
+            "rendered  demo/README.md\ncopied    demo/logo.png\nskipped   demo/kept.txt\nconflict  demo/Cargo.toml\n"
+        );
+    }
+
+    #[test]
+    fn test_variables_lines() {
+        let variables = BTreeMap::from([
+            (
+                "author".to_string(),
+                BTreeSet::from([PathBuf::from("{{ project_name }}/README.md")]),
+            ),
+            (
+                "port".to_string(),
+                BTreeSet::from([
+                    PathBuf::from("{{ project_name }}/Dockerfile"),
+                    PathBuf::from("{{ project_name }}/config.toml"),
+                ]),
+            ),
+        ]);
+        assert_eq!(
+            variables_lines(&variables, &BTreeSet::from(["author".to_string()])),
+            "author\nport  (not declared, read by {{ project_name }}/Dockerfile, {{ project_name }}/config.toml)\n"
+        );
+    }
+
+    #[test]
+    fn test_answers_summary() {
+        let mut context = Context::new();
+        context.insert("project_name", "demo");
+        context.insert("features", &["cli", "docs"]);
+        context.insert("api_token", "hunter2");
+        let names = ["project_name", "features", "api_token"].map(String::from);
+        assert_eq!(
+            answers_summary(&names, &context, &BTreeSet::from(["api_token".to_string()])),
+            "  project_name: demo\n  features: [\"cli\",\"docs\"]\n  api_token: ***\n"
+        );
+    }
+
+    #[test]
+    fn test_check_excludes() {
+        let config = toml::from_str::<Config>(
+            r#"
+            [[prompts]]
+            name = "license"
+            type = "string"
+            default = "MIT"
+            "#,
+        )
+        .unwrap();
+        check_excludes(&config, &["license".to_string()]).unwrap();
+        let err = check_excludes(&config, &["licence".to_string()]).unwrap_err();
+        assert_eq!(err.to_string(), "cannot exclude unknown prompt `licence`");
+    }
+
+    #[test]
+    fn test_context_trace() {
+        let mut context = Context::new();
+        context.insert("project_name", "demo");
+        context.insert("api_token", "hunter2");
+        let trace = context_trace(&context, &BTreeSet::from(["api_token".to_string()]));
+        assert!(trace.contains("\"project_name\": \"demo\""));
+        assert!(trace.contains("\"api_token\": \"***\""));
+        assert!(!trace.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_given_project_name() {
+        let mut values = PromptValues {
+            defines: HashMap::from([("project_name".to_string(), "defined".into())]),
+            answers: serde_json::Map::from_iter([(
+                "project_name".to_string(),
+                "remembered".into(),
+            )]),
+            env: HashMap::from([(
+                "PETRIDISH_VAR_PROJECT_NAME".to_string(),
+                "from-env".to_string(),
+            )]),
+            ..Default::default()
+        };
+        assert_eq!(
+            given_project_name("project_name", &values),
+            (Some("from-env".to_string()), Some("defined".to_string()))
+        );
+        values
+            .extra_context
+            .insert("project_name".to_string(), "given".to_string());
+        assert_eq!(
+            given_project_name("project_name", &values).0.as_deref(),
+            Some("given")
+        );
+        values.extra_context.clear();
+        values.env.clear();
+        values.defines.clear();
+        assert_eq!(
+            given_project_name("project_name", &values),
+            (None, Some("remembered".to_string()))
+        );
+        assert_eq!(
+            given_project_name("project_name", &PromptValues::default()),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn test_description_banner_quiet() {
+        let config = PetridishConfig {
+            short_description: Some("A rust cli".into()),
+            ..Default::default()
+        };
+        assert!(description_banner(&config, false, false)
+            .unwrap()
+            .contains("A rust cli"));
+        assert_eq!(description_banner(&config, false, true), None);
+        assert_eq!(
+            description_banner(&PetridishConfig::default(), false, false),
+            None
+        );
+    }
 }
@@ -0,0 +1,66 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Where `--merge` records what it generated, in the project dir.
+pub const MANIFEST_FILE: &str = ".petridish-manifest.json";
+
+/// The files of the last generation with `--merge`, relative to the project
+/// dir, with the hashes of their contents. It's the baseline telling which
+/// files were changed in the project since, and which in the template.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub files: BTreeMap<PathBuf, String>,
+}
+
+impl Manifest {
+    /// Returns `None` when nothing was recorded, a manifest which cannot be
+    /// read is taken as nothing recorded too.
+    pub fn load(project_path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(project_path.join(MANIFEST_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn to_json(&self) -> Vec<u8> {
+        serde_json::to_vec_pretty(self).unwrap()
+    }
+}
+
+/// The git blob id of `content`, the same as `git hash-object` prints.
+pub fn content_hash(content: &[u8]) -> String {
+    git2::Oid::hash_object(git2::ObjectType::Blob, content)
+        .unwrap()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(
+            content_hash(b"hello\n"),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+    }
+
+    #[test]
+    fn test_load_manifest() {
+        let dir = tempdir::TempDir::new("manifest").unwrap();
+        assert_eq!(Manifest::load(dir.path()), None);
+
+        let manifest = Manifest {
+            files: BTreeMap::from([("src/main.rs".into(), content_hash(b"fn main() {}"))]),
+        };
+        fs::write(dir.path().join(MANIFEST_FILE), manifest.to_json()).unwrap();
+        assert_eq!(Manifest::load(dir.path()), Some(manifest));
+
+        fs::write(dir.path().join(MANIFEST_FILE), "{").unwrap();
+        assert_eq!(Manifest::load(dir.path()), None);
+    }
+}
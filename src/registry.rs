@@ -0,0 +1,190 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+    auth::HttpAuth,
+    cache::Cache,
+    error::{Error, Result},
+};
+
+/// The registry used when `--registry` is not given.
+pub const REGISTRY_ENV: &str = "PETRIDISH_REGISTRY";
+
+/// A JSON index mapping friendly template names to their git uris.
+///
+/// ```json
+/// {
+///   "templates": {
+///     "my-org/service": "https://github.com/my-org/service-template.git",
+///     "my-org/lib": {
+///       "uri": "gh:my-org/lib-template",
+///       "description": "A rust library"
+///     }
+///   }
+/// }
+/// ```
+#[derive(Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Registry {
+    #[serde(default)]
+    pub templates: BTreeMap<String, RegistryEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "RegistryEntryDef")]
+pub struct RegistryEntry {
+    pub uri: String,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RegistryEntryDef {
+    Uri(String),
+    Entry {
+        uri: String,
+        description: Option<String>,
+    },
+}
+
+impl From<RegistryEntryDef> for RegistryEntry {
+    fn from(def: RegistryEntryDef) -> Self {
+        match def {
+            RegistryEntryDef::Uri(uri) => Self {
+                uri,
+                description: None,
+            },
+            RegistryEntryDef::Entry { uri, description } => Self { uri, description },
+        }
+    }
+}
+
+impl Registry {
+    pub fn parse(location: &str, content: &str) -> Result<Self> {
+        serde_json::from_str(content).map_err(|e| Error::InvalidRegistry {
+            location: location.to_string(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// Loads the index from an http(s) url or a local path. Remote indexes
+    /// are cached and only downloaded again with `refresh`, `auth` is sent
+    /// along with the download.
+    pub fn load(location: &str, refresh: bool, auth: Option<&HttpAuth>) -> Result<Self> {
+        if !is_remote(location) {
+            let content = fs::read_to_string(location).map_err(|e| Error::InvalidRegistry {
+                location: location.to_string(),
+                reason: e.to_string(),
+            })?;
+            return Self::parse(location, &content);
+        }
+
+        let cached_path = cached_index_path(location);
+        if !refresh {
+            if let Ok(content) = fs::read_to_string(&cached_path) {
+                return Self::parse(location, &content);
+            }
+        }
+
+        let mut request = ureq::get(location);
+        if let Some(auth) = auth {
+            request = request.set("Authorization", &auth.header());
+        }
+        let content = request
+            .call()
+            .map_err(|e| e.to_string())
+            .and_then(|response| response.into_string().map_err(|e| e.to_string()))
+            .map_err(|reason| Error::InvalidRegistry {
+                location: location.to_string(),
+                reason,
+            })?;
+        let registry = Self::parse(location, &content)?;
+        fs::create_dir_all(Cache::cache_dir())
+            .and_then(|_| fs::write(&cached_path, &content))
+            .map_err(|e| Error::CannotWriteFile {
+                source: e,
+                path: cached_path,
+            })?;
+        Ok(registry)
+    }
+
+    /// Returns the uri registered for `name`, if any.
+    pub fn resolve(&self, name: &str) -> Option<&str> {
+        self.templates.get(name).map(|entry| entry.uri.as_str())
+    }
+}
+
+fn is_remote(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Where the index downloaded from `url` is cached, next to the cached
+/// templates.
+fn cached_index_path(url: &str) -> PathBuf {
+    let name = url
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    Cache::cache_dir().join(format!("registry-{}.json", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INDEX: &str = r#"{
+        "templates": {
+            "my-org/service": "https://github.com/my-org/service-template.git",
+            "my-org/lib": {
+                "uri": "gh:my-org/lib-template",
+                "description": "A rust library"
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_registry() {
+        let registry = Registry::parse("index.json", INDEX).unwrap();
+        assert_eq!(
+            registry.templates["my-org/service"],
+            RegistryEntry {
+                uri: "https://github.com/my-org/service-template.git".into(),
+                description: None,
+            }
+        );
+        assert_eq!(
+            registry.templates["my-org/lib"].description.as_deref(),
+            Some("A rust library")
+        );
+
+        let err = Registry::parse("index.json", r#"{"templates": {"a": 1}}"#).unwrap_err();
+        assert!(matches!(err, Error::InvalidRegistry { location, .. } if location == "index.json"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let registry = Registry::parse("index.json", INDEX).unwrap();
+        assert_eq!(
+            registry.resolve("my-org/lib"),
+            Some("gh:my-org/lib-template")
+        );
+        assert_eq!(registry.resolve("my-org/missing"), None);
+    }
+
+    #[test]
+    fn test_load_local_registry() {
+        let dir = tempdir::TempDir::new("registry").unwrap();
+        let path = dir.path().join("index.json");
+        fs::write(&path, INDEX).unwrap();
+
+        let registry = Registry::load(path.to_str().unwrap(), false, None).unwrap();
+        assert_eq!(registry.templates.len(), 2);
+        assert!(Registry::load("missing/index.json", false, None).is_err());
+    }
+
+    #[test]
+    fn test_cached_index_path() {
+        assert!(cached_index_path("https://example.com/index.json")
+            .ends_with("registry-https___example_com_index_json.json"));
+    }
+}
@@ -1,23 +1,310 @@
 use std::{
-    collections::HashMap,
-    fs,
-    path::{Path, PathBuf},
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Component, Path, PathBuf},
 };
 
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use tera::Context;
 use tera::Tera;
 use walkdir::WalkDir;
 
-use crate::error::{Error, Result};
+use crate::{
+    error::{Error, Result},
+    filters::register_filters,
+    manifest::{content_hash, Manifest, MANIFEST_FILE},
+    variables::referenced_variables,
+};
+
+/// Tool configs copied without rendering by default, in any dir of the
+/// project. Their braces, e.g. `{{` in nested JSON, would be taken as Tera
+/// tags.
+pub const DEFAULT_EXCLUDE_RENDER_PATHS: &[&str] = &[
+    "**/.editorconfig",
+    "**/.prettierrc",
+    "**/.prettierrc.json",
+    "**/.eslintrc",
+    "**/.eslintrc.json",
+    "**/.babelrc",
+    "**/.stylelintrc",
+    "**/.stylelintrc.json",
+    "**/.markdownlint.json",
+    "**/.vscode/*.json",
+];
+
+/// The delimiters of variables and blocks in file contents, for files which
+/// contain `{{ }}` for other tooling, e.g. GitHub workflows.
+///
+/// ```toml
+/// [petridish.delimiters]
+/// variable_start = "[["
+/// variable_end = "]]"
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Delimiters {
+    pub variable_start: String,
+    pub variable_end: String,
+    pub block_start: String,
+    pub block_end: String,
+}
+
+impl Default for Delimiters {
+    fn default() -> Self {
+        Self {
+            variable_start: "{{".into(),
+            variable_end: "}}".into(),
+            block_start: "{%".into(),
+            block_end: "%}".into(),
+        }
+    }
+}
+
+impl Delimiters {
+    /// Translates a template using these delimiters into a Tera template,
+    /// keeping everything outside of them literal.
+    pub fn translate(&self, content: &str) -> String {
+        if self == &Self::default() {
+            return content.to_string();
+        }
+
+        let mut translated = String::with_capacity(content.len());
+        let mut rest = content;
+        loop {
+            let next = [
+                (&self.variable_start, &self.variable_end, "{{", "}}"),
+                (&self.block_start, &self.block_end, "{%", "%}"),
+            ]
+            .into_iter()
+            .filter_map(|d| rest.find(d.0.as_str()).map(|idx| (idx, d)))
+            .min_by_key(|(idx, _)| *idx);
+            let Some((idx, (start, end, tera_start, tera_end))) = next else {
+                push_literal(&mut translated, rest);
+                break;
+            };
+
+            let inner = &rest[idx + start.len()..];
+            let Some(end_idx) = inner.find(end.as_str()) else {
+                push_literal(&mut translated, rest);
+                break;
+            };
+            push_literal(&mut translated, &rest[..idx]);
+            translated.push_str(tera_start);
+            translated.push_str(&inner[..end_idx]);
+            translated.push_str(tera_end);
+            rest = &inner[end_idx + end.len()..];
+        }
+
+        translated
+    }
+}
+
+/// Pushes text which Tera should not render, wrapping it in a raw block if it
+/// looks like Tera syntax.
+fn push_literal(translated: &mut String, literal: &str) {
+    if ["{{", "{%", "{#"].iter().any(|d| literal.contains(d)) {
+        translated.push_str("{% raw %}");
+        translated.push_str(literal);
+        translated.push_str("{% endraw %}");
+    } else {
+        translated.push_str(literal);
+    }
+}
+
+/// The line endings of rendered files, files copied without rendering keep
+/// theirs.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndings {
+    #[default]
+    Preserve,
+    Lf,
+    Crlf,
+}
+
+impl LineEndings {
+    pub fn apply(self, content: String) -> String {
+        match self {
+            LineEndings::Preserve => content,
+            LineEndings::Lf => content.replace("\r\n", "\n"),
+            LineEndings::Crlf => content.replace("\r\n", "\n").replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// Where the template files are read from.
+#[derive(Clone)]
+enum Source {
+    Dir(PathBuf),
+    /// Template files keyed by their path relative to the template root.
+    Files(HashMap<PathBuf, Vec<u8>>),
+}
+
+enum TemplateEntry {
+    Dir,
+    File(FileContent),
+    /// A symlink to `target`, located at `path` in the template.
+    Symlink {
+        target: PathBuf,
+        path: PathBuf,
+    },
+}
+
+/// What to generate for the symlinks of a template.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Symlinks {
+    /// Recreates the links as they are.
+    #[default]
+    Copy,
+    /// Copies the files the links point to, links to dirs or missing files
+    /// are recreated.
+    Follow,
+    /// Leaves the links out.
+    Skip,
+}
+
+/// The content of a file, files on disk are only read when they are
+/// rendered, the others are streamed into place.
+enum FileContent {
+    Bytes(Vec<u8>),
+    Path(PathBuf),
+}
+
+impl FileContent {
+    fn read(&self) -> Result<Cow<'_, [u8]>> {
+        match self {
+            FileContent::Bytes(content) => Ok(Cow::Borrowed(content)),
+            FileContent::Path(path) => {
+                fs::read(path)
+                    .map(Cow::Owned)
+                    .map_err(|e| Error::PathNotFound {
+                        source: e,
+                        path: path.clone(),
+                    })
+            }
+        }
+    }
+
+    fn write_to(&self, dest_path: &Path) -> Result<()> {
+        let FileContent::Path(path) = self else {
+            return write_file(dest_path, &self.read()?);
+        };
+        create_parent_dir(dest_path)?;
+        let mut source = fs::File::open(path).map_err(|e| Error::PathNotFound {
+            source: e,
+            path: path.clone(),
+        })?;
+        fs::File::create(dest_path)
+            .and_then(|mut dest| io::copy(&mut source, &mut dest))
+            .map(|_| ())
+            .map_err(|e| Error::CannotWriteFile {
+                source: e,
+                path: dest_path.to_path_buf(),
+            })
+    }
+}
+
+impl Source {
+    /// Lists the entries under the entry dir with their paths relative to the
+    /// template root.
+    fn entries(&self, entry_dir_name: &str, keep_empty_dirs: bool) -> Vec<(String, TemplateEntry)> {
+        match self {
+            Source::Dir(template_path) => WalkDir::new(template_path.join(entry_dir_name))
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|p| {
+                    p.file_type().is_file()
+                        || p.file_type().is_symlink()
+                        || (keep_empty_dirs && p.file_type().is_dir())
+                })
+                .map(|entry| {
+                    let relative_path = entry
+                        .path()
+                        .display()
+                        .to_string()
+                        .trim_start_matches(&template_path.display().to_string())
+                        .trim_start_matches('/') // for unix
+                        .trim_start_matches('\\') // for windows
+                        .to_string();
+                    let template_entry = if entry.path_is_symlink() {
+                        TemplateEntry::Symlink {
+                            target: fs::read_link(entry.path()).unwrap(),
+                            path: entry.path().to_path_buf(),
+                        }
+                    } else if entry.file_type().is_dir() {
+                        TemplateEntry::Dir
+                    } else {
+                        TemplateEntry::File(FileContent::Path(entry.path().to_path_buf()))
+                    };
+                    (relative_path, template_entry)
+                })
+                .collect(),
+            Source::Files(files) => files
+                .iter()
+                .filter(|(path, _)| path.starts_with(entry_dir_name))
+                .map(|(path, content)| {
+                    (
+                        path.display().to_string(),
+                        TemplateEntry::File(FileContent::Bytes(content.clone())),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Reads a file relative to the template root, refusing paths which
+    /// escape it.
+    fn read(&self, path: &str) -> std::result::Result<Vec<u8>, String> {
+        match self {
+            Source::Dir(template_path) => {
+                let file = template_file(template_path, path)
+                    .map_err(|e| format!("cannot include '{}': {}", path, e))?
+                    .ok_or_else(|| format!("cannot include '{}' outside of the template", path))?;
+                fs::read(&file).map_err(|e| format!("cannot include '{}': {}", path, e))
+            }
+            Source::Files(files) => files
+                .get(Path::new(path))
+                .cloned()
+                .ok_or_else(|| format!("cannot include '{}': not found", path)),
+        }
+    }
+}
+
+/// Registers custom filters or functions, see `Render::with_tera_setup`.
+type TeraSetup = dyn Fn(&mut Tera);
 
 pub struct Render {
-    template_path: PathBuf,
+    source: Source,
     entry_dir_name: String,
     output_path: PathBuf,
     context: Context,
     overwrite_if_exists: bool,
     skip_if_exists: bool,
     exclude_render_paths: Vec<String>,
+    keep_empty_dirs: bool,
+    in_place: bool,
+    render_suffix: Option<String>,
+    delimiters: Delimiters,
+    trim_whitespace: bool,
+    line_endings: LineEndings,
+    atomic: bool,
+    strip_dir_marker: Option<String>,
+    output_subdir: Option<String>,
+    strict_vars: bool,
+    bases: Vec<(Source, String)>,
+    prune_empty_dirs: bool,
+    tera_setup: Option<Box<TeraSetup>>,
+    only: Option<GlobSet>,
+    changed: Option<HashSet<PathBuf>>,
+    merge: bool,
+    symlinks: Symlinks,
+    keep_going: bool,
+    write_partial: bool,
+    undefined_placeholder: bool,
 }
 
 impl Render {
@@ -30,74 +317,644 @@ impl Render {
         skip_if_exists: bool,
         exclude_render_paths: Vec<String>,
     ) -> Self {
-        let mut tera = Tera::default();
-        let exclude_render_paths = exclude_render_paths
-            .into_iter()
-            .map(|p| {
-                tera.render_str(&format!("{}/{}", entry_dir_name, p), &context)
-                    .unwrap()
-            })
-            .collect();
+        Self::with_source(
+            Source::Dir(template_path.into()),
+            entry_dir_name,
+            output_path.into(),
+            context,
+            overwrite_if_exists,
+            skip_if_exists,
+            exclude_render_paths,
+        )
+    }
+
+    /// Renders the template from in-memory files keyed by their path relative
+    /// to the template root (e.g. `{{ project_name }}/README.md`), e.g. for
+    /// templates embedded into a binary.
+    pub fn from_files(
+        files: HashMap<PathBuf, Vec<u8>>,
+        entry_dir_name: &str,
+        output_path: impl Into<PathBuf>,
+        context: Context,
+        overwrite_if_exists: bool,
+        skip_if_exists: bool,
+        exclude_render_paths: Vec<String>,
+    ) -> Self {
+        Self::with_source(
+            Source::Files(files),
+            entry_dir_name,
+            output_path.into(),
+            context,
+            overwrite_if_exists,
+            skip_if_exists,
+            exclude_render_paths,
+        )
+    }
 
+    fn with_source(
+        source: Source,
+        entry_dir_name: &str,
+        output_path: PathBuf,
+        context: Context,
+        overwrite_if_exists: bool,
+        skip_if_exists: bool,
+        exclude_render_paths: Vec<String>,
+    ) -> Self {
         Self {
-            template_path: template_path.into(),
+            source,
             entry_dir_name: entry_dir_name.into(),
-            output_path: output_path.into(),
+            output_path,
             context,
             overwrite_if_exists,
             skip_if_exists,
             exclude_render_paths,
+            keep_empty_dirs: false,
+            in_place: false,
+            render_suffix: None,
+            delimiters: Delimiters::default(),
+            trim_whitespace: false,
+            line_endings: LineEndings::default(),
+            atomic: false,
+            strip_dir_marker: None,
+            output_subdir: None,
+            strict_vars: false,
+            bases: vec![],
+            prune_empty_dirs: false,
+            tera_setup: None,
+            only: None,
+            changed: None,
+            merge: false,
+            symlinks: Symlinks::default(),
+            keep_going: false,
+            write_partial: false,
+            undefined_placeholder: false,
+        }
+    }
+
+    /// Also creates directories of the template which contain no files.
+    pub fn with_keep_empty_dirs(mut self, keep_empty_dirs: bool) -> Self {
+        self.keep_empty_dirs = keep_empty_dirs;
+        self
+    }
+
+    /// Renders the content of the entry dir directly into the output dir,
+    /// instead of into a new project dir.
+    pub fn with_in_place(mut self, in_place: bool) -> Self {
+        self.in_place = in_place;
+        self
+    }
+
+    /// Strips `render_suffix` (e.g. `.tmpl`) from the names of rendered files.
+    pub fn with_render_suffix(mut self, render_suffix: Option<String>) -> Self {
+        self.render_suffix = render_suffix;
+        self
+    }
+
+    /// Uses custom delimiters in file contents, file names keep using the
+    /// default ones.
+    pub fn with_delimiters(mut self, delimiters: Delimiters) -> Self {
+        self.delimiters = delimiters;
+        self
+    }
+
+    /// Removes the lines of file contents which hold nothing but block tags
+    /// like `{% if %}`, like `trim_blocks` and `lstrip_blocks` of Jinja.
+    pub fn with_trim_whitespace(mut self, trim_whitespace: bool) -> Self {
+        self.trim_whitespace = trim_whitespace;
+        self
+    }
+
+    pub fn with_line_endings(mut self, line_endings: LineEndings) -> Self {
+        self.line_endings = line_endings;
+        self
+    }
+
+    /// Renders everything into a staging dir first, so nothing is written to
+    /// the output dir when rendering fails partway through.
+    pub fn with_atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Creates the dirs containing a marker file like `.gitkeep`, which only
+    /// exists because git can't track empty dirs, without the marker itself.
+    pub fn with_strip_dir_marker(mut self, marker: Option<String>) -> Self {
+        self.strip_dir_marker = marker;
+        self
+    }
+
+    /// Generates the project into a templated subdir of the output dir, e.g.
+    /// `{{ org }}/{{ project_name }}`, instead of the entry dir name. It's
+    /// ignored when rendering in place.
+    pub fn with_output_subdir(mut self, output_subdir: Option<String>) -> Self {
+        self.output_subdir = output_subdir;
+        self
+    }
+
+    /// Fails on variables missing from the context, including the ones Tera
+    /// would take as falsy like `{% if use_docker %}`, naming the file which
+    /// references them.
+    pub fn with_strict_vars(mut self, strict_vars: bool) -> Self {
+        self.strict_vars = strict_vars;
+        self
+    }
+
+    /// Renders variables missing from the context as `<<MISSING:name>>`
+    /// instead of failing, so they stand out in the generated files. It is
+    /// meant as an alternative to `strict_vars`, which wins when both are on.
+    pub fn with_undefined_placeholder(mut self, undefined_placeholder: bool) -> Self {
+        self.undefined_placeholder = undefined_placeholder;
+        self
+    }
+
+    /// Goes on rendering the other files when one fails to render, failing
+    /// with the errors of all of them at the end.
+    pub fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// Along with `keep_going`, still writes the files which rendered before
+    /// failing, otherwise nothing is written.
+    pub fn with_write_partial(mut self, write_partial: bool) -> Self {
+        self.write_partial = write_partial;
+        self
+    }
+
+    /// Takes the error of rendering a single file into `errors` with
+    /// `keep_going`, returning `None` to skip the file.
+    fn keep_going<T>(&self, result: Result<T>, errors: &mut Vec<Error>) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if self.keep_going => {
+                errors.push(e);
+                Ok(None)
+            }
+            Err(e) => Err(e),
         }
     }
+
+    /// Only creates the dirs which are empty in the template itself along
+    /// with `keep_empty_dirs`, not the ones left empty because all of their
+    /// files were skipped, e.g. by `{% if use_docker %}Dockerfile{% endif %}`.
+    pub fn with_prune_empty_dirs(mut self, prune_empty_dirs: bool) -> Self {
+        self.prune_empty_dirs = prune_empty_dirs;
+        self
+    }
+
+    /// Renders the files of base templates (their dir and entry dir name,
+    /// root-most first) under the files of this one, which win on conflicts.
+    pub fn with_bases(mut self, bases: Vec<(PathBuf, String)>) -> Self {
+        self.bases = bases
+            .into_iter()
+            .map(|(template_path, entry_dir_name)| (Source::Dir(template_path), entry_dir_name))
+            .collect();
+        self
+    }
+
+    /// Only generates the files (and dirs) whose rendered path relative to
+    /// the project dir matches one of the globs, e.g. `src/**`. Everything is
+    /// generated when there are none.
+    pub fn with_only(mut self, patterns: Vec<String>) -> Self {
+        self.only = (!patterns.is_empty()).then(|| {
+            let mut only = GlobSetBuilder::new();
+            for pattern in patterns {
+                only.add(
+                    GlobBuilder::new(&pattern)
+                        .literal_separator(true)
+                        .build()
+                        .unwrap(),
+                );
+            }
+            only.build().unwrap()
+        });
+        self
+    }
+
+    /// Only generates the files at these paths relative to the template dir,
+    /// e.g. `{{ project_name }}/README.md` after it changed, leaving the rest
+    /// of the project and the files of base templates alone.
+    pub fn with_changed(mut self, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.changed = Some(paths.into_iter().collect());
+        self
+    }
+
+    /// Updates an existing project instead of failing on existing files,
+    /// keeping the changes made to it since the last merge. What is generated
+    /// is recorded in `.petridish-manifest.json` of the project dir, files
+    /// changed since on both sides are reported as conflicts.
+    pub fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Recreates, follows or leaves out the symlinks of the template, see
+    /// `Symlinks`.
+    pub fn with_symlinks(mut self, symlinks: Symlinks) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Extends the `Tera` instance with custom filters or functions, e.g. an
+    /// `employee_id()` function. The built-in ones are registered before, so
+    /// they can be overridden.
+    pub fn with_tera_setup(mut self, setup: impl Fn(&mut Tera) + 'static) -> Self {
+        self.tera_setup = Some(Box::new(setup));
+        self
+    }
 }
 
 impl Render {
-    pub fn render(&self) -> Result<()> {
+    /// Builds the `Tera` instance used for rendering, with the petridish
+    /// functions registered.
+    fn build_tera(&self) -> Tera {
         let mut tera = Tera::default();
-        let mut file_contents = HashMap::new();
+        register_filters(&mut tera);
+        tera.register_function(
+            "include_file",
+            include_file(self.source.clone(), self.context.clone()),
+        );
+        if let Some(setup) = &self.tera_setup {
+            setup(&mut tera);
+        }
+        tera
+    }
+
+    /// Renders the content of a file with the delimiters and whitespace
+    /// options applied.
+    fn render_content(&self, tera: &mut Tera, path: &str, content: &str) -> Result<String> {
+        let mut content = self.delimiters.translate(content);
+        if self.trim_whitespace {
+            content = trim_block_lines(&content);
+        }
+        let rendered = self.render_str(tera, path, &content)?;
+        Ok(self.line_endings.apply(rendered))
+    }
+
+    /// Renders a path or the content of a file against the context, checking
+    /// its variables first.
+    fn render_str(&self, tera: &mut Tera, path: &str, template: &str) -> Result<String> {
+        self.check_vars(path, template)?;
+        let context = self.placeholder_context(path, template)?;
+        tera.render_str(template, &context)
+            .map_err(render_error(path))
+    }
 
-        // first render templates into file_contents
-        let template_entry_path = self.template_path.join(&self.entry_dir_name);
-        for entry in WalkDir::new(template_entry_path)
+    /// The context with a placeholder for every variable `template` reads
+    /// but the context lacks, when `undefined_placeholder` is on.
+    fn placeholder_context(&self, path: &str, template: &str) -> Result<Cow<'_, Context>> {
+        if !self.undefined_placeholder {
+            return Ok(Cow::Borrowed(&self.context));
+        }
+        let missing = referenced_variables(template)
+            .map_err(render_error(path))?
             .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|p| p.file_type().is_file() || p.file_type().is_symlink())
+            .filter(|name| !self.context.contains_key(name))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(Cow::Borrowed(&self.context));
+        }
+        let mut context = self.context.clone();
+        for name in missing {
+            context.insert(&name, &format!("<<MISSING:{}>>", name));
+        }
+        Ok(Cow::Owned(context))
+    }
+
+    /// Renders a single file of the entry dir, e.g. `README.md`, without
+    /// generating the project.
+    pub fn render_file(&self, relative_path: &str) -> Result<String> {
+        let path = format!("{}/{}", self.entry_dir_name, relative_path);
+        let content = self
+            .source
+            .read(&path)
+            .and_then(|content| String::from_utf8(content).map_err(|e| e.to_string()))
+            .map_err(Error::ArgsError)?;
+        self.render_content(&mut self.build_tera(), &path, &content)
+    }
+
+    /// The globs of `exclude_render_paths` rendered against the context,
+    /// matching paths relative to the project dir so the project name never
+    /// becomes part of a glob.
+    fn exclude_globs(&self, tera: &mut Tera) -> Result<GlobSet> {
+        let mut globs = GlobSetBuilder::new();
+        for path in &self.exclude_render_paths {
+            let pattern = tera
+                .render_str(path, &self.context)
+                .map_err(render_error("petridish.toml"))?;
+            globs.add(glob(&pattern)?);
+        }
+        globs.build().map_err(|source| Error::InvalidGlob {
+            pattern: self.exclude_render_paths.join(", "),
+            source,
+        })
+    }
+
+    /// Checks that every variable `template` reads is in the context when
+    /// `strict_vars` is on.
+    fn check_vars(&self, path: &str, template: &str) -> Result<()> {
+        if !self.strict_vars {
+            return Ok(());
+        }
+        match referenced_variables(template)
+            .map_err(render_error(path))?
+            .into_iter()
+            .find(|name| !self.context.contains_key(name))
         {
-            let relative_path = entry
-                .path()
-                .display()
-                .to_string()
-                .trim_start_matches(&self.template_path.display().to_string())
-                .trim_start_matches('/') // for unix
-                .trim_start_matches('\\') // for windows
-                .to_string();
-
-            let relative_path = tera.render_str(&relative_path, &self.context)?;
-            let dest_path = self.output_path.join(&relative_path);
-            if entry.path_is_symlink() {
-                if !dest_path.parent().unwrap().exists() {
-                    fs::create_dir_all(dest_path.parent().unwrap()).unwrap();
-                }
-                symlink(&fs::read_link(entry.path()).unwrap(), dest_path);
+            Some(name) => Err(Error::UndefinedVariable {
+                path: path.into(),
+                name,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn is_dir_marker(&self, dest_path: &Path) -> bool {
+        match &self.strip_dir_marker {
+            Some(marker) => dest_path.file_name().is_some_and(|n| n == marker.as_str()),
+            None => false,
+        }
+    }
+
+    /// Renders the paths and contents of everything to generate, without
+    /// writing anything.
+    fn plan(&self) -> Result<Plan> {
+        let mut tera = self.build_tera();
+        let mut file_contents = HashMap::new();
+        let mut copied = HashSet::new();
+        let mut dirs = vec![];
+        let mut symlinks = vec![];
+        let mut escaping_symlinks = vec![];
+        let mut errors = vec![];
+        let exclude_render_paths = self.exclude_globs(&mut tera)?;
+        let entry_dir_name = tera
+            .render_str(&self.entry_dir_name, &self.context)
+            .map_err(render_error(&self.entry_dir_name))?;
+        let output_subdir = match &self.output_subdir {
+            Some(output_subdir) if !self.in_place => Some(checked_subdir(
+                tera.render_str(output_subdir, &self.context)
+                    .map_err(render_error("petridish.toml"))?,
+            )?),
+            _ => None,
+        };
+        let project_path = match &output_subdir {
+            _ if self.in_place => self.output_path.clone(),
+            Some(output_subdir) => self.output_path.join(output_subdir),
+            None => self.output_path.join(&entry_dir_name),
+        };
+        if let Source::Dir(template_path) = &self.source {
+            check_no_overlap(template_path, &project_path)?;
+        }
+
+        // first render templates into file_contents, the files of this
+        // template replace the ones of its bases
+        let sources = self
+            .bases
+            .iter()
+            .map(|(source, entry_dir_name)| (source, entry_dir_name.as_str(), true))
+            .chain([(&self.source, self.entry_dir_name.as_str(), false)]);
+        for (source, source_entry_dir_name, is_base) in sources {
+            if is_base && self.changed.is_some() {
                 continue;
             }
+            let entries = source.entries(source_entry_dir_name, self.keep_empty_dirs);
+            // the dirs which are not empty in the template, they are only
+            // created for their contents when pruning empty dirs
+            let non_empty_dirs = entries
+                .iter()
+                .filter_map(|(path, _)| Path::new(path).parent())
+                .map(Path::to_path_buf)
+                .collect::<HashSet<_>>();
+            for (relative_path, entry) in entries {
+                if self.prune_empty_dirs
+                    && matches!(entry, TemplateEntry::Dir)
+                    && non_empty_dirs.contains(Path::new(&relative_path))
+                {
+                    continue;
+                }
+                if let Some(changed) = &self.changed {
+                    if !changed.contains(Path::new(&relative_path)) {
+                        continue;
+                    }
+                }
+                // the project dir of a base may use another variable
+                let relative_path = match relative_path.strip_prefix(source_entry_dir_name) {
+                    Some(rest) => format!("{}{}", self.entry_dir_name, rest),
+                    None => relative_path,
+                };
+                let rendered_path = self.render_str(&mut tera, &relative_path, &relative_path);
+                let Some(relative_path) = self.keep_going(rendered_path, &mut errors)? else {
+                    continue;
+                };
+                // a path segment like `{% if use_docker %}Dockerfile{% endif %}`
+                // renders to empty when the file should not be generated
+                if relative_path
+                    .split(['/', '\\'])
+                    .any(|c| c.trim().is_empty())
+                {
+                    continue;
+                }
+                if let Some(only) = &self.only {
+                    let project_relative_path = Path::new(&relative_path)
+                        .strip_prefix(&entry_dir_name)
+                        .unwrap();
+                    if !only.is_match(project_relative_path) {
+                        continue;
+                    }
+                }
+                // only the paths of the template are translated, not the
+                // project dir or `output_subdir`, e.g. for a `dot_net` project
+                let project_relative_path = translate_dot_prefix(
+                    Path::new(&relative_path)
+                        .strip_prefix(&entry_dir_name)
+                        .unwrap(),
+                );
+                let mut dest_path = if project_relative_path.as_os_str().is_empty() {
+                    project_path.clone()
+                } else {
+                    project_path.join(project_relative_path)
+                };
+                let entry = match entry {
+                    TemplateEntry::Symlink { .. } if self.symlinks == Symlinks::Skip => continue,
+                    TemplateEntry::Symlink { target, path } => {
+                        let followed = path.parent().unwrap().join(&target);
+                        let follow = self.symlinks == Symlinks::Follow && followed.is_file();
+                        // a recreated link should stay inside the project, a
+                        // followed one inside the template
+                        let escapes = if follow {
+                            escapes_dir(Path::new(&relative_path), &target)
+                        } else {
+                            let project_relative_path = Path::new(&relative_path)
+                                .strip_prefix(&entry_dir_name)
+                                .unwrap();
+                            escapes_dir(project_relative_path, &target)
+                        };
+                        if escapes {
+                            escaping_symlinks.push(dest_path.clone());
+                        }
+                        if follow {
+                            TemplateEntry::File(FileContent::Path(followed))
+                        } else {
+                            TemplateEntry::Symlink { target, path }
+                        }
+                    }
+                    entry => entry,
+                };
+                let template_content = match entry {
+                    TemplateEntry::Dir => {
+                        dirs.push(dest_path);
+                        continue;
+                    }
+                    TemplateEntry::Symlink { target, .. } => {
+                        file_contents.remove(&dest_path);
+                        symlinks.push((target, dest_path));
+                        continue;
+                    }
+                    TemplateEntry::File(_) if self.is_dir_marker(&dest_path) => {
+                        dirs.push(dest_path.parent().unwrap().to_path_buf());
+                        continue;
+                    }
+                    TemplateEntry::File(content) => {
+                        symlinks.retain(|(_, symlink)| symlink != &dest_path);
+                        content
+                    }
+                };
+                if let Some(suffix) = &self.render_suffix {
+                    if let Some(file_name) = dest_path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .and_then(|n| n.strip_suffix(suffix.as_str()))
+                        .filter(|n| !n.is_empty())
+                        .map(String::from)
+                    {
+                        dest_path.set_file_name(file_name);
+                    }
+                }
+
+                // check whether relative path matches exclude_render_paths,
+                // binary files are copied as they are too
+                let project_relative_path = Path::new(&relative_path)
+                    .strip_prefix(&entry_dir_name)
+                    .unwrap();
+                if exclude_render_paths
+                    .is_match(project_relative_path.to_string_lossy().replace('\\', "/"))
+                {
+                    copied.insert(dest_path.clone());
+                    file_contents.insert(dest_path, template_content);
+                    continue;
+                }
+                let rendered_content = match std::str::from_utf8(&template_content.read()?) {
+                    Ok(content) => {
+                        copied.remove(&dest_path);
+                        let rendered = self.render_content(&mut tera, &relative_path, content);
+                        let Some(rendered) = self.keep_going(rendered, &mut errors)? else {
+                            // a failed file doesn't fall back to one of a base
+                            file_contents.remove(&dest_path);
+                            continue;
+                        };
+                        FileContent::Bytes(rendered.into_bytes())
+                    }
+                    Err(_) => {
+                        copied.insert(dest_path.clone());
+                        template_content
+                    }
+                };
+                file_contents.insert(dest_path, rendered_content);
+            }
+        }
 
-            let template_content = fs::read_to_string(entry.path()).unwrap();
+        Ok(Plan {
+            dirs,
+            symlinks,
+            file_contents,
+            copied,
+            project_path,
+            escaping_symlinks,
+            errors,
+        })
+    }
 
-            // check whether relative path is in exclude_render_paths
-            if self
-                .exclude_render_paths
-                .iter()
-                .any(|p| relative_path.eq(p))
-            {
-                file_contents.insert(dest_path, template_content);
+    /// Returns unified diffs between the files in the output dir and their
+    /// rendered contents, files which don't exist yet show up as additions.
+    /// Nothing is written.
+    pub fn diff(&self) -> Result<String> {
+        let Plan {
+            file_contents,
+            errors,
+            ..
+        } = self.plan()?;
+        if !errors.is_empty() {
+            return Err(Error::RenderErrors(errors));
+        }
+        let mut dest_paths = file_contents.keys().collect::<Vec<_>>();
+        dest_paths.sort();
+
+        let mut diff = String::new();
+        for dest_path in dest_paths {
+            let rendered_content = file_contents[dest_path].read()?;
+            let rendered_content = rendered_content.as_ref();
+            let exists = dest_path.exists();
+            let current_content = if exists {
+                fs::read(dest_path).map_err(|e| Error::PathNotFound {
+                    source: e,
+                    path: dest_path.clone(),
+                })?
             } else {
-                let rendered_content = tera.render_str(&template_content, &self.context)?;
-                file_contents.insert(dest_path, rendered_content);
+                vec![]
+            };
+            if current_content == rendered_content {
+                continue;
+            }
+
+            let name = dest_path
+                .strip_prefix(&self.output_path)
+                .unwrap_or(dest_path)
+                .display()
+                .to_string()
+                .replace('\\', "/");
+            let old_header = if exists {
+                format!("a/{}", name)
+            } else {
+                "/dev/null".to_string()
+            };
+            let new_header = format!("b/{}", name);
+            match (
+                std::str::from_utf8(&current_content),
+                std::str::from_utf8(rendered_content),
+            ) {
+                (Ok(current_content), Ok(rendered_content)) => diff.push_str(
+                    &similar::TextDiff::from_lines(current_content, rendered_content)
+                        .unified_diff()
+                        .header(&old_header, &new_header)
+                        .to_string(),
+                ),
+                _ => diff.push_str(&format!(
+                    "Binary files {} and {} differ\n",
+                    old_header, new_header
+                )),
             }
         }
 
-        if !self.overwrite_if_exists && !self.skip_if_exists {
+        Ok(diff)
+    }
+
+    /// Generates the project, returning which files were rendered, copied
+    /// as they are and which ones already existed and were kept.
+    pub fn render(&self) -> Result<RenderReport> {
+        let Plan {
+            dirs,
+            symlinks,
+            file_contents,
+            copied,
+            project_path,
+            escaping_symlinks,
+            errors,
+        } = self.plan()?;
+        if !errors.is_empty() && !self.write_partial {
+            return Err(Error::RenderErrors(errors));
+        }
+
+        if !self.overwrite_if_exists && !self.skip_if_exists && !self.merge {
             // check whether dest path exists
             for dest_path in file_contents.keys() {
                 if dest_path.exists() {
@@ -106,21 +963,486 @@ impl Render {
             }
         }
 
+        let planned = file_contents.keys().cloned().collect::<HashSet<_>>();
+        let mut report = RenderReport {
+            escaping_symlinks,
+            ..Default::default()
+        };
+        let file_contents = if self.merge {
+            let merged = merge_files(file_contents, &project_path)?;
+            report.skipped = merged.kept;
+            report.conflicts = merged.conflicts;
+            merged.write
+        } else {
+            let (file_contents, skipped): (Vec<_>, Vec<_>) = file_contents
+                .into_iter()
+                .partition(|(dest_path, _)| !dest_path.exists() || self.overwrite_if_exists);
+            report.skipped = skipped
+                .into_iter()
+                .map(|(dest_path, _)| dest_path)
+                .collect();
+            file_contents
+        };
+        // leaving out the `.new` files of conflicts and the manifest
+        for (dest_path, _) in file_contents.iter().filter(|(p, _)| planned.contains(p)) {
+            if copied.contains(dest_path) {
+                report.copied.push(dest_path.clone());
+            } else {
+                report.rendered.push(dest_path.clone());
+            }
+        }
+        report.rendered.sort();
+        report.copied.sort();
+        report.skipped.sort();
+        report.conflicts.sort();
+        report.escaping_symlinks.sort();
+        if self.atomic {
+            self.write_atomically(dirs, symlinks, file_contents)?;
+        } else {
+            self.write(dirs, symlinks, file_contents)?;
+        }
+
+        if errors.is_empty() {
+            Ok(report)
+        } else {
+            Err(Error::RenderErrors(errors))
+        }
+    }
+
+    /// Writes the files in place.
+    fn write(
+        &self,
+        dirs: Vec<PathBuf>,
+        symlinks: Vec<(PathBuf, PathBuf)>,
+        file_contents: Vec<(PathBuf, FileContent)>,
+    ) -> Result<()> {
+        for dir in dirs {
+            create_dir(&dir)?;
+        }
+        for (original, dest_path) in symlinks {
+            create_parent_dir(&dest_path)?;
+            symlink(original, dest_path);
+        }
+
         // dump files
         for (dest_path, rendered_content) in file_contents {
-            let parent = dest_path.parent().unwrap();
-            if !parent.exists() {
-                fs::create_dir_all(parent).unwrap();
+            rendered_content.write_to(&dest_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the files into a staging dir inside the output dir first, and
+    /// moves them into place only after all of them are written. When moving
+    /// one fails, the ones moved before are undone, so a failed render leaves
+    /// the output dir as it was.
+    fn write_atomically(
+        &self,
+        dirs: Vec<PathBuf>,
+        symlinks: Vec<(PathBuf, PathBuf)>,
+        file_contents: Vec<(PathBuf, FileContent)>,
+    ) -> Result<()> {
+        let output_existed = self.output_path.exists();
+        create_dir(&self.output_path)?;
+        let staging_dir =
+            tempdir::TempDir::new_in(&self.output_path, ".petridish").map_err(|e| {
+                Error::CannotCreateDir {
+                    source: e,
+                    path: self.output_path.clone(),
+                }
+            })?;
+
+        let mut staged = file_contents
+            .into_iter()
+            .map(|(dest_path, rendered_content)| {
+                let staged_path = staging_dir
+                    .path()
+                    .join(dest_path.strip_prefix(&self.output_path).unwrap());
+                rendered_content.write_to(&staged_path)?;
+                Ok((staged_path, dest_path))
+            })
+            .collect::<Result<Vec<_>>>();
+        let mut journal = Journal::default();
+        let moved = match &mut staged {
+            Ok(staged) => {
+                staged.sort_by(|(_, a), (_, b)| a.cmp(b));
+                move_into_place(dirs, symlinks, staged, staging_dir.path(), &mut journal)
             }
-            if !dest_path.exists() || self.overwrite_if_exists {
-                fs::write(dest_path, rendered_content).unwrap();
+            Err(_) => Ok(()),
+        };
+        if let Err(e) = staged.and(moved) {
+            journal.rollback();
+            drop(staging_dir);
+            if !output_existed {
+                let _ = fs::remove_dir_all(&self.output_path);
             }
+            return Err(e);
         }
 
         Ok(())
     }
 }
 
+/// Moves the staged files of `write_atomically` into place along with the
+/// dirs and symlinks, recording every step in `journal`. Replaced files are
+/// kept in `backup_dir` until then.
+fn move_into_place(
+    dirs: Vec<PathBuf>,
+    symlinks: Vec<(PathBuf, PathBuf)>,
+    staged: &[(PathBuf, PathBuf)],
+    staging_dir: &Path,
+    journal: &mut Journal,
+) -> Result<()> {
+    let backup_dir = staging_dir.join(".petridish-backup");
+    for dir in dirs {
+        journal.create_dir(&dir)?;
+    }
+    for (original, dest_path) in symlinks {
+        journal.create_parent_dir(&dest_path)?;
+        symlink(original, &dest_path);
+        journal.symlinks.push(dest_path);
+    }
+    for (index, (staged_path, dest_path)) in staged.iter().enumerate() {
+        journal.create_parent_dir(dest_path)?;
+        let cannot_write = |e| Error::CannotWriteFile {
+            source: e,
+            path: dest_path.clone(),
+        };
+        // a replaced dir fails the rename below instead
+        let backup = match fs::symlink_metadata(dest_path) {
+            Ok(metadata) if !metadata.is_dir() => {
+                let backup = backup_dir.join(index.to_string());
+                create_dir(&backup_dir)?;
+                fs::rename(dest_path, &backup).map_err(cannot_write)?;
+                Some(backup)
+            }
+            _ => None,
+        };
+        let moved = fs::rename(staged_path, dest_path).map_err(cannot_write);
+        journal.files.push((dest_path.clone(), backup));
+        moved?;
+    }
+
+    Ok(())
+}
+
+/// What `write_atomically` changed in the output dir, to undo it when a
+/// later step fails.
+#[derive(Default)]
+struct Journal {
+    dirs: Vec<PathBuf>,
+    symlinks: Vec<PathBuf>,
+    /// The moved files, along with the backup of the files they replaced.
+    files: Vec<(PathBuf, Option<PathBuf>)>,
+}
+
+impl Journal {
+    fn create_dir(&mut self, path: &Path) -> Result<()> {
+        let missing = path
+            .ancestors()
+            .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+            .map(Path::to_path_buf)
+            .collect::<Vec<_>>();
+        self.dirs.extend(missing.into_iter().rev());
+        create_dir(path)
+    }
+
+    fn create_parent_dir(&mut self, path: &Path) -> Result<()> {
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => self.create_dir(parent),
+            _ => Ok(()),
+        }
+    }
+
+    /// Undoes the changes from the last one on, as far as possible.
+    fn rollback(self) {
+        for (dest_path, backup) in self.files.into_iter().rev() {
+            match backup {
+                Some(backup) => {
+                    let _ = fs::rename(backup, &dest_path);
+                }
+                None => {
+                    let _ = fs::remove_file(&dest_path);
+                }
+            }
+        }
+        for link in self.symlinks.into_iter().rev() {
+            let _ = fs::remove_file(link);
+        }
+        for dir in self.dirs.into_iter().rev() {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+fn create_dir(path: &Path) -> Result<()> {
+    fs::create_dir_all(path).map_err(|e| Error::CannotCreateDir {
+        source: e,
+        path: path.to_path_buf(),
+    })
+}
+
+fn create_parent_dir(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.exists() => create_dir(parent),
+        _ => Ok(()),
+    }
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    create_parent_dir(path)?;
+    fs::write(path, content).map_err(|e| Error::CannotWriteFile {
+        source: e,
+        path: path.to_path_buf(),
+    })
+}
+
+/// The files of a render, by their destination paths.
+#[derive(Serialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct RenderReport {
+    /// Files written with their contents rendered.
+    pub rendered: Vec<PathBuf>,
+    /// Files written as they are, i.e. binary ones and the ones matching
+    /// `exclude_render_paths`.
+    pub copied: Vec<PathBuf>,
+    /// Files which already existed and were kept, see `skip_if_exists`.
+    pub skipped: Vec<PathBuf>,
+    /// Files changed both in the project and the template since the last
+    /// merge, see `with_merge`.
+    pub conflicts: Vec<PathBuf>,
+    /// Symlinks pointing outside of the project, or outside of the template
+    /// when followed, e.g. to an absolute path, see `with_symlinks`.
+    pub escaping_symlinks: Vec<PathBuf>,
+}
+
+/// The files of a `--merge`.
+struct Merge {
+    /// The files to write, including the `.new` files of conflicts and the
+    /// manifest.
+    write: Vec<(PathBuf, FileContent)>,
+    kept: Vec<PathBuf>,
+    conflicts: Vec<PathBuf>,
+}
+
+/// Decides per file whether to write it or keep the existing one, compared to
+/// the manifest of the last merge: files nobody changed since are written,
+/// the ones only changed in the project are kept. Files changed on both sides,
+/// or existing ones which were not recorded, keep their content and the new one
+/// is written next to them as `<file>.new`.
+fn merge_files(file_contents: HashMap<PathBuf, FileContent>, project_path: &Path) -> Result<Merge> {
+    let baseline = Manifest::load(project_path).unwrap_or_default();
+    let mut manifest = Manifest::default();
+    let mut merge = Merge {
+        write: vec![],
+        kept: vec![],
+        conflicts: vec![],
+    };
+    for (dest_path, content) in file_contents {
+        let new_hash = content_hash(&content.read()?);
+        let relative_path = dest_path
+            .strip_prefix(project_path)
+            .unwrap_or(&dest_path)
+            .to_path_buf();
+        let base_hash = baseline.files.get(&relative_path).cloned();
+        manifest.files.insert(relative_path, new_hash.clone());
+        if !dest_path.exists() {
+            merge.write.push((dest_path, content));
+            continue;
+        }
+
+        let existing = fs::read(&dest_path).map_err(|e| Error::PathNotFound {
+            source: e,
+            path: dest_path.clone(),
+        })?;
+        let existing_hash = content_hash(&existing);
+        if existing_hash == new_hash || base_hash.as_ref() == Some(&new_hash) {
+            merge.kept.push(dest_path);
+        } else if base_hash == Some(existing_hash) {
+            merge.write.push((dest_path, content));
+        } else {
+            let mut new_path = dest_path.clone().into_os_string();
+            new_path.push(".new");
+            merge.write.push((new_path.into(), content));
+            merge.conflicts.push(dest_path);
+        }
+    }
+    merge.write.push((
+        project_path.join(MANIFEST_FILE),
+        FileContent::Bytes(manifest.to_json()),
+    ));
+    Ok(merge)
+}
+
+/// Everything a render generates, keyed by the destination paths.
+struct Plan {
+    dirs: Vec<PathBuf>,
+    symlinks: Vec<(PathBuf, PathBuf)>,
+    file_contents: HashMap<PathBuf, FileContent>,
+    /// The files of `file_contents` which are not rendered.
+    copied: HashSet<PathBuf>,
+    /// The generated project dir, i.e. the output dir for `in_place`.
+    project_path: PathBuf,
+    escaping_symlinks: Vec<PathBuf>,
+    /// The errors of the files which failed to render with `keep_going`.
+    errors: Vec<Error>,
+}
+
+/// Resolves `path` relative to the template root, following symlinks,
+/// `None` when it ends up outside of the root, e.g. `../secrets`.
+pub fn template_file(root: &Path, path: impl AsRef<Path>) -> io::Result<Option<PathBuf>> {
+    let root = root.canonicalize()?;
+    let file = root.join(path).canonicalize()?;
+    Ok(file.starts_with(&root).then_some(file))
+}
+
+/// Builds a glob of `exclude_render_paths`, where `/` isn't matched by `*`.
+pub fn glob(pattern: &str) -> Result<Glob> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|source| Error::InvalidGlob {
+            pattern: pattern.to_string(),
+            source,
+        })
+}
+
+/// Wraps a Tera error with the template path which failed to render.
+fn render_error(path: impl Into<PathBuf>) -> impl FnOnce(tera::Error) -> Error {
+    move |source| Error::RenderError {
+        source,
+        path: path.into(),
+    }
+}
+
+/// Checks that the rendered `output_subdir` stays inside of the output dir.
+fn checked_subdir(output_subdir: String) -> Result<PathBuf> {
+    let path = PathBuf::from(output_subdir.trim());
+    let inside = path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if inside && path.components().any(|c| matches!(c, Component::Normal(_))) {
+        Ok(path)
+    } else {
+        Err(Error::InvalidOutputSubdir(output_subdir))
+    }
+}
+
+/// Fails when the project would be generated into the template it's read
+/// from. A template inside of the project dir is fine, e.g. `--in-place`
+/// into the current dir for `./my-template`, files of the same path are
+/// caught like any other existing file.
+fn check_no_overlap(template_path: &Path, project_path: &Path) -> Result<()> {
+    let template = absolute_path(template_path);
+    let project = absolute_path(project_path);
+    if project.starts_with(&template) {
+        return Err(Error::OutputOverlapsTemplate {
+            output: project_path.to_path_buf(),
+            template: template_path.to_path_buf(),
+        });
+    }
+    Ok(())
+}
+
+/// Whether `target` of a symlink at `link` leaves the dir `link` is relative
+/// to, i.e. it's absolute or has more `..` than `link` has parents.
+fn escapes_dir(link: &Path, target: &Path) -> bool {
+    if target.has_root() {
+        return true;
+    }
+    let mut depth = link.components().count() - 1;
+    for component in target.components() {
+        match component {
+            Component::ParentDir if depth == 0 => return true,
+            Component::ParentDir => depth -= 1,
+            Component::Normal(_) => depth += 1,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// The absolute path with symlinks resolved as far as it exists, the project
+/// dir usually doesn't yet.
+fn absolute_path(path: &Path) -> PathBuf {
+    let path = std::env::current_dir().unwrap_or_default().join(path);
+    path.ancestors()
+        .find_map(|ancestor| {
+            let rest = path.strip_prefix(ancestor).ok()?;
+            Some(ancestor.canonicalize().ok()?.join(rest))
+        })
+        .unwrap_or(path)
+}
+
+/// Turns a leading `dot_` of path components into `.`, e.g. `dot_gitignore`
+/// into `.gitignore`, for templates whose dotfiles get lost in transport.
+fn translate_dot_prefix(path: &Path) -> PathBuf {
+    path.components()
+        .map(|component| match component.as_os_str().to_str() {
+            Some(name) if name.len() > "dot_".len() && name.starts_with("dot_") => {
+                format!(".{}", &name["dot_".len()..]).into()
+            }
+            _ => component.as_os_str().to_owned(),
+        })
+        .collect()
+}
+
+/// Strips the indentation and the line break around block tags which are
+/// alone on their line, so they don't leave blank lines in the output.
+fn trim_block_lines(content: &str) -> String {
+    regex::Regex::new(r"(?m)^[ \t]*(\{%(?:[^%]|%[^}])*%\})[ \t]*\r?\n")
+        .unwrap()
+        .replace_all(content, "$1")
+        .into_owned()
+}
+
+/// Checks the output dir before rendering into it. A missing output dir is
+/// fine as long as its parent exists, missing parents are created only when
+/// `create_parents` is set.
+pub fn prepare_output_dir(output_path: &Path, create_parents: bool) -> Result<()> {
+    if output_path.is_file() {
+        return Err(Error::OutputDirIsFile(output_path.to_path_buf()));
+    }
+    if output_path.exists() {
+        return Ok(());
+    }
+
+    match output_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.exists() => {
+            if !create_parents {
+                return Err(Error::OutputDirParentNotFound(output_path.to_path_buf()));
+            }
+            fs::create_dir_all(parent).map_err(|e| Error::CannotCreateDir {
+                source: e,
+                path: parent.to_path_buf(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `include_file(path="LICENSE_HEADER.txt", render=true)` returns the content
+/// of a file relative to the template root, optionally rendered.
+fn include_file(source: Source, context: Context) -> impl tera::Function {
+    move |args: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+        let path = args
+            .get("path")
+            .and_then(|p| p.as_str())
+            .ok_or("`include_file` requires a string argument `path`")?;
+        let render = args
+            .get("render")
+            .and_then(|r| r.as_bool())
+            .unwrap_or(false);
+
+        let content = String::from_utf8(source.read(path)?)
+            .map_err(|e| tera::Error::chain(format!("cannot include '{}'", path), e))?;
+        if render {
+            Ok(Tera::one_off(&content, &context, false)?.into())
+        } else {
+            Ok(content.into())
+        }
+    }
+}
+
 #[cfg(windows)]
 fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(original: P, link: Q) {
     std::os::windows::fs::symlink_file(original, link).unwrap()
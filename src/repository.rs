@@ -1,9 +1,14 @@
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
+    process::Command,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
 };
 
 use regex::Regex;
+use serde::Serialize;
 
 use crate::{
     cache::Cache,
@@ -11,6 +16,10 @@ use crate::{
 };
 
 pub fn try_new_repo(uri: String, context: HashMap<String, String>) -> Result<Box<dyn Repository>> {
+    if Hg::check_match(&uri) {
+        let repo = Hg::try_new(uri, context)?;
+        return Ok(Box::new(repo));
+    }
     if Git::check_match(&uri) {
         let repo = Git::try_new(uri, context)?;
         return Ok(Box::new(repo));
@@ -25,6 +34,8 @@ pub trait Repository {
     fn repo_dir(&self) -> PathBuf;
     fn name(&self) -> &str;
     fn need_cache(&self) -> bool;
+    /// Whether the repo has been downloaded into the cache.
+    fn is_cached(&self) -> bool;
 }
 
 #[derive(Debug, PartialEq)]
@@ -33,19 +44,34 @@ struct Git {
     uri: String,
     branch: Option<String>,
     auth: Option<Auth>,
+    timeout: Option<Duration>,
+    /// The user of ssh urls, see `ssh_username`.
+    ssh_user: Option<String>,
 }
 
 impl Git {
+    /// Where the template is downloaded from, i.e. the uri and the checked
+    /// out ref if any.
+    fn source(&self) -> String {
+        match &self.branch {
+            Some(branch) => format!("{}#{}", self.uri, branch),
+            None => self.uri.clone(),
+        }
+    }
+
     fn check_match(uri: &str) -> bool {
-        uri.ends_with(".git") || Regex::new(r"^g(h|l).*:.*(\.git)?").unwrap().is_match(uri)
+        bare_url(uri).ends_with(".git")
+            || Regex::new(r"^(g(h|l)|github|gitlab).*:.*(\.git)?")
+                .unwrap()
+                .is_match(uri)
     }
 
     fn try_new(uri: String, context: HashMap<String, String>) -> Result<Self> {
-        if Regex::new("^gh.*:.*").unwrap().is_match(&uri) {
+        if Regex::new("^(gh|github).*:.*").unwrap().is_match(&uri) {
             return Git::new_alias_git(uri, context, "gh", "github", "github.com");
-        } else if Regex::new("^gl.*:.*").unwrap().is_match(&uri) {
+        } else if Regex::new("^(gl|gitlab).*:.*").unwrap().is_match(&uri) {
             return Git::new_alias_git(uri, context, "gl", "gitlab", "gitlab.com");
-        } else if uri.ends_with(".git") {
+        } else if bare_url(&uri).ends_with(".git") {
             return Git::new_git(uri, context);
         }
 
@@ -57,27 +83,17 @@ impl Git {
 
     fn new_git(uri: String, mut context: HashMap<String, String>) -> Result<Self> {
         let branch = context.remove("branch");
-        let username = context.remove("username");
-        let password = context.remove("password");
-
-        if username.is_some() && password.is_none() {
-            return Err(Error::AuthMissingPassword("git".into()));
-        }
-        if username.is_none() && password.is_some() {
-            return Err(Error::AuthMissingUsername("git".into()));
-        }
-        let auth = if let (Some(username), Some(password)) = (username, password) {
-            Some(Auth { username, password })
-        } else {
-            None
-        };
-
-        let name = uri
-            .trim_end_matches(".git")
-            .split('/')
-            .last()
-            .unwrap()
-            .to_string();
+        let timeout = context
+            .remove("timeout")
+            .map(|t| {
+                t.parse::<u64>()
+                    .map(Duration::from_secs)
+                    .map_err(|_| Error::ArgsError(format!("git timeout '{}' is invalid", t)))
+            })
+            .transpose()?;
+        let ssh_user = context.remove("ssh_user");
+        let auth = Auth::from_context(&mut context, "git")?;
+        let name = template_name(context.remove("name"), || repo_name(&uri))?;
 
         if uri.starts_with("https://") || uri.starts_with("http://") || uri.starts_with("git@") {
             Ok(Self {
@@ -85,6 +101,8 @@ impl Git {
                 branch,
                 auth,
                 name,
+                timeout,
+                ssh_user,
             })
         } else {
             Err(Error::InvalidRepo {
@@ -103,9 +121,31 @@ impl Git {
         provider_url: &str,
     ) -> Result<Self> {
         let head = alias_uri.split(':').collect::<Vec<&str>>()[0];
-        let tail = alias_uri
-            .trim_start_matches(&format!("{}:", head))
-            .trim_end_matches(".git");
+        let tail = alias_uri.trim_start_matches(&format!("{}:", head));
+        // `gh:owner/repo@v1.2.0` pins the template to a release tag
+        let (tail, tag) = match tail.rsplit_once('@') {
+            Some((tail, tag)) if !tag.is_empty() => (tail, Some(tag)),
+            _ => (tail, None),
+        };
+        let tail = tail.trim_end_matches(".git");
+        if let Some(tag) = tag {
+            match context.get("branch") {
+                Some(branch) if branch != tag => {
+                    return Err(Error::ArgsError(format!(
+                        "`{}` is pinned to '{}', it conflicts with branch '{}'",
+                        alias_uri, tag, branch
+                    )))
+                }
+                _ => {
+                    context.insert("branch".to_string(), tag.to_string());
+                }
+            }
+        }
+        // the provider name works as well as its alias, e.g. `github:`
+        let head = match head.strip_prefix(provider) {
+            Some(protocol) => format!("{}{}", alias, protocol),
+            None => head.to_string(),
+        };
 
         let provider_url = context
             .remove(&format!("{}_provider", alias))
@@ -130,41 +170,155 @@ impl Git {
 
 impl Repository for Git {
     fn download(&self) -> Result<()> {
-        let url = self.uri.clone();
-        let url = if url.starts_with("https://") || url.starts_with("http://") {
-            if let Some(Auth { username, password }) = &self.auth {
-                let prefix = url.split("://").collect::<Vec<&str>>()[0];
-                let tail = url.trim_start_matches(&format!("{}://", prefix));
-                format!("{}://{}:{}@{}", prefix, username, password, tail)
-            } else {
-                url
-            }
-        } else {
-            url
+        let url = url_with_auth(&self.uri, self.auth.as_ref());
+        let (tmp_dir, repo) = clone_repo(
+            &url,
+            download_dir(),
+            &self.name,
+            self.timeout,
+            self.ssh_user.as_deref(),
+        )?;
+        let tmp_repo = tmp_dir.path().join(&self.name);
+        if let Some(branch) = &self.branch {
+            checkout_ref(branch, repo).map_err(|_| Error::InvalidGitRef(branch.clone()))?;
+        }
+
+        Cache::add(&tmp_repo, &self.source())?;
+        Ok(())
+    }
+
+    fn repo_dir(&self) -> PathBuf {
+        Cache::lookup(&self.source()).unwrap()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn need_cache(&self) -> bool {
+        true
+    }
+
+    fn is_cached(&self) -> bool {
+        Cache::lookup(&self.source()).is_some()
+    }
+}
+
+/// A Mercurial repo, written as `hg+https://host/repo`, `hg+ssh://host/repo`
+/// or `hg:<url or path>`, and cloned by the `hg` command.
+#[derive(Debug, PartialEq)]
+struct Hg {
+    name: String,
+    uri: String,
+    branch: Option<String>,
+    auth: Option<Auth>,
+}
+
+impl Hg {
+    /// Where the template is downloaded from, like `Git::source`.
+    fn source(&self) -> String {
+        match &self.branch {
+            Some(branch) => format!("hg+{}#{}", self.uri, branch),
+            None => format!("hg+{}", self.uri),
+        }
+    }
+
+    fn check_match(uri: &str) -> bool {
+        Regex::new(r"^hg(\+(https?|ssh))?:").unwrap().is_match(uri)
+    }
+
+    fn try_new(uri: String, mut context: HashMap<String, String>) -> Result<Self> {
+        // `hg+https://host/repo` is `https://host/repo`, `hg:` takes any url
+        let url = match uri.strip_prefix("hg:") {
+            Some(url) => url,
+            None => uri.strip_prefix("hg+").unwrap_or(&uri),
         };
+        let invalid = || Error::InvalidRepo {
+            kind: "hg".into(),
+            uri: uri.clone(),
+        };
+        if url.is_empty() || url.starts_with('-') {
+            return Err(invalid());
+        }
 
-        let cache_dir = Cache::cache_dir();
-        let cache_tmp_dir = cache_dir.join(".tmp");
-        if !cache_tmp_dir.exists() {
-            std::fs::create_dir_all(&cache_tmp_dir).unwrap();
+        let branch = context.remove("branch");
+        let auth = Auth::from_context(&mut context, "hg")?;
+        let name = template_name(context.remove("name"), || {
+            repo_name(url).map_err(|_| invalid())
+        })?;
+        Ok(Self {
+            name,
+            uri: url.to_string(),
+            branch,
+            auth,
+        })
+    }
+
+    /// `hg clone` of the repo into `into`, updated to the branch, tag or
+    /// revision if any. The credentials are read from `hgrc`, see
+    /// `write_auth_config`, rather than passed in the url, where they would
+    /// show in the process list and be kept as the default path of the clone.
+    fn clone_command(&self, into: &Path, hgrc: Option<&Path>) -> Command {
+        let mut command = Command::new("hg");
+        if let Some(hgrc) = hgrc {
+            // the user's config is only read along with `HGRCPATH` if it's set
+            let paths = std::env::var_os("HGRCPATH")
+                .into_iter()
+                .flat_map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+                .chain([hgrc.to_path_buf()]);
+            command.env("HGRCPATH", std::env::join_paths(paths).unwrap());
         }
-        let tmp_dir = tempdir::TempDir::new_in(&cache_tmp_dir, "").unwrap();
-        let tmp_repo = tmp_dir.path().join(&self.name);
-        let repo = if url.starts_with("git") {
-            clone_ssh_repo(&url, &tmp_repo)
-        } else {
-            clone_http_repo(&url, &tmp_repo)
-        }?;
+        command.args(["clone", "--noninteractive"]);
         if let Some(branch) = &self.branch {
-            checkout_ref(branch, repo).map_err(|_| Error::InvalidGitRef(branch.clone()))?;
+            command.args(["--updaterev", branch]);
         }
+        command.arg("--").arg(&self.uri).arg(into);
+        command
+    }
 
-        Cache::add(&tmp_repo);
+    /// Writes the credentials of `--auth` for the http(s) url as an `[auth]`
+    /// section of an hgrc file in `dir`, outside of the clone.
+    fn write_auth_config(&self, dir: &Path) -> Result<Option<PathBuf>> {
+        let auth = match &self.auth {
+            Some(auth) if self.uri.starts_with("https://") || self.uri.starts_with("http://") => {
+                auth
+            }
+            _ => return Ok(None),
+        };
+        let path = dir.join("hgrc");
+        let config = format!(
+            "[auth]\npetridish.prefix = {}\npetridish.username = {}\npetridish.password = {}\n",
+            self.uri, auth.username, auth.password
+        );
+        std::fs::write(&path, config).map_err(|e| Error::CannotWriteFile {
+            source: e,
+            path: path.clone(),
+        })?;
+        Ok(Some(path))
+    }
+}
+
+impl Repository for Hg {
+    fn download(&self) -> Result<()> {
+        let tmp_dir = download_dir();
+        let tmp_repo = tmp_dir.path().join(&self.name);
+        let hgrc = self.write_auth_config(tmp_dir.path())?;
+        let output = self
+            .clone_command(&tmp_repo, hgrc.as_deref())
+            .output()
+            .map_err(|e| Error::HgError(e.to_string()))?;
+        if !output.status.success() {
+            return Err(Error::HgError(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        Cache::add(&tmp_repo, &self.source())?;
         Ok(())
     }
 
     fn repo_dir(&self) -> PathBuf {
-        Cache::get(&self.name).unwrap()
+        Cache::lookup(&self.source()).unwrap()
     }
 
     fn name(&self) -> &str {
@@ -174,28 +328,133 @@ impl Repository for Git {
     fn need_cache(&self) -> bool {
         true
     }
+
+    fn is_cached(&self) -> bool {
+        Cache::lookup(&self.source()).is_some()
+    }
 }
 
-fn clone_http_repo<P>(url: &str, into: P) -> Result<git2::Repository>
-where
-    P: AsRef<Path>,
-{
-    Ok(git2::Repository::clone(url, into)?)
+/// A temp dir in the cache dir to download a template into, so it can be
+/// moved into the cache atomically, see `Cache::add`.
+fn download_dir() -> tempdir::TempDir {
+    let cache_tmp_dir = Cache::cache_dir().join(".tmp");
+    if !cache_tmp_dir.exists() {
+        std::fs::create_dir_all(&cache_tmp_dir).unwrap();
+    }
+    tempdir::TempDir::new_in(&cache_tmp_dir, "").unwrap()
 }
 
-fn clone_ssh_repo<P>(url: &str, into: P) -> Result<git2::Repository>
-where
-    P: AsRef<Path>,
-{
-    let mut callbacks = git2::RemoteCallbacks::new();
-    callbacks.credentials(|_url, username_from_url, _allowed_types| {
-        git2::Cred::ssh_key(
-            username_from_url.unwrap(),
-            None,
-            Path::new(&format!("{}/.ssh/id_rsa", std::env::var("HOME").unwrap())),
-            None,
-        )
+/// The url with the credentials of `--auth` if it's http(s).
+fn url_with_auth(url: &str, auth: Option<&Auth>) -> String {
+    match (url.split_once("://"), auth) {
+        (Some((scheme @ ("https" | "http"), tail)), Some(Auth { username, password })) => {
+            format!("{}://{}:{}@{}", scheme, username, password, tail)
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// The name the template is cached under, `--name` or else the repo's.
+fn template_name(
+    name: Option<String>,
+    repo_name: impl FnOnce() -> Result<String>,
+) -> Result<String> {
+    match name {
+        Some(name) if matches!(name.as_str(), "" | "." | "..") || name.contains(['/', '\\']) => {
+            Err(Error::ArgsError(format!(
+                "template name '{}' is invalid, it should be a plain file name",
+                name
+            )))
+        }
+        Some(name) => Ok(name),
+        None => repo_name(),
+    }
+}
+
+/// The url without its query string, fragment and trailing slashes, e.g.
+/// `https://github.com/owner/repo.git` of `https://github.com/owner/repo.git/?ref=main`.
+fn bare_url(uri: &str) -> &str {
+    let end = uri.find(['?', '#']).unwrap_or(uri.len());
+    uri[..end].trim_end_matches('/')
+}
+
+/// The name of the repo at a git url, i.e. its last path segment without
+/// `.git`, e.g. `repo` of `git@github.com:owner/repo.git`.
+fn repo_name(uri: &str) -> Result<String> {
+    let path = bare_url(uri);
+    let path = path
+        .strip_suffix(".git")
+        .unwrap_or(path)
+        .trim_end_matches('/');
+    // without the scheme, a url of just the host has no name
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    match path.rsplit_once(['/', ':']) {
+        Some((_, name)) if !name.is_empty() => Ok(name.to_string()),
+        _ => Err(Error::InvalidRepo {
+            kind: "git".into(),
+            uri: uri.to_string(),
+        }),
+    }
+}
+
+/// Clones the repo into `name` in `tmp_dir`, giving up with
+/// `Error::GitTimeout` once `timeout` is exceeded, even if the network stalls
+/// without any progress. The temp dir is handed back along with the repo, on
+/// a timeout the worker keeps it until the abandoned clone stops writing.
+fn clone_repo(
+    url: &str,
+    tmp_dir: tempdir::TempDir,
+    name: &str,
+    timeout: Option<Duration>,
+    ssh_user: Option<&str>,
+) -> Result<(tempdir::TempDir, git2::Repository)> {
+    let into = tmp_dir.path().join(name);
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return clone(url, &into, None, ssh_user).map(|repo| (tmp_dir, repo)),
+    };
+
+    let deadline = Instant::now() + timeout;
+    let (tx, rx) = mpsc::channel();
+    let url = url.to_string();
+    let ssh_user = ssh_user.map(String::from);
+    thread::spawn(move || {
+        let result = clone(&url, &into, Some(deadline), ssh_user.as_deref());
+        // the temp dir is removed here if nobody waits for the clone anymore
+        let _ = tx.send(result.map(|repo| (tmp_dir, repo)));
     });
+    match rx.recv_timeout(timeout) {
+        Ok(Err(Error::GitError(e)))
+            if Instant::now() >= deadline && e.class() == git2::ErrorClass::Callback =>
+        {
+            Err(Error::GitTimeout(timeout.as_secs()))
+        }
+        Ok(result) => result,
+        Err(_) => Err(Error::GitTimeout(timeout.as_secs())),
+    }
+}
+
+fn clone(
+    url: &str,
+    into: &Path,
+    deadline: Option<Instant>,
+    ssh_user: Option<&str>,
+) -> Result<git2::Repository> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    if url.starts_with("git") {
+        callbacks.credentials(|_url, username_from_url, _allowed_types| {
+            git2::Cred::ssh_key(
+                ssh_username(ssh_user, username_from_url),
+                None,
+                Path::new(&format!("{}/.ssh/id_rsa", std::env::var("HOME").unwrap())),
+                None,
+            )
+        });
+    }
+    if let Some(deadline) = deadline {
+        // returning false aborts the transfer
+        callbacks.transfer_progress(move |_| Instant::now() < deadline);
+    }
 
     // Prepare fetch options.
     let mut fo = git2::FetchOptions::new();
@@ -205,7 +464,14 @@ where
     let mut builder = git2::build::RepoBuilder::new();
     builder.fetch_options(fo);
 
-    Ok(builder.clone(url, into.as_ref())?)
+    Ok(builder.clone(url, into)?)
+}
+
+/// The user to authenticate as over ssh, `--ssh-user` takes precedence over
+/// the one of the url, e.g. `git` of `git@github.com:owner/repo.git`, which
+/// is also the default.
+fn ssh_username<'a>(ssh_user: Option<&'a str>, username_from_url: Option<&'a str>) -> &'a str {
+    ssh_user.or(username_from_url).unwrap_or("git")
 }
 
 fn checkout_ref(branch: &str, repo: git2::Repository) -> std::result::Result<(), git2::Error> {
@@ -225,18 +491,66 @@ fn checkout_ref(branch: &str, repo: git2::Repository) -> std::result::Result<(),
     Ok(())
 }
 
+/// The checked out commit of a template.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    /// The branch, `None` for a detached head like a checked out tag.
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
+    pub sha: String,
+}
+
+/// The checked out commit of the template at `repo_dir`, `None` if it's not
+/// a git repo, e.g. a local template dir.
+pub fn head_revision(repo_dir: &Path) -> Option<Revision> {
+    let repo = git2::Repository::open(repo_dir).ok()?;
+    let head = repo.head().ok()?;
+    let sha = head.peel_to_commit().ok()?.id().to_string();
+    let reference = head
+        .is_branch()
+        .then(|| head.shorthand().map(String::from))
+        .flatten();
+    Some(Revision { reference, sha })
+}
+
 #[derive(Debug, PartialEq)]
 struct Auth {
     pub username: String,
     pub password: String,
 }
 
+impl Auth {
+    /// Takes the `username` and `password` from the context, which come
+    /// together or not at all.
+    fn from_context(context: &mut HashMap<String, String>, kind: &str) -> Result<Option<Self>> {
+        match (context.remove("username"), context.remove("password")) {
+            (Some(username), Some(password)) => Ok(Some(Auth { username, password })),
+            (Some(_), None) => Err(Error::AuthMissingPassword(kind.into())),
+            (None, Some(_)) => Err(Error::AuthMissingUsername(kind.into())),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
-struct LocalPath(PathBuf);
+struct LocalPath {
+    path: PathBuf,
+    name: String,
+}
 
 impl LocalPath {
+    /// The name is the one of the dir, also for paths like `.` or `..`
+    /// which have none themselves.
     fn new(path: PathBuf) -> Self {
-        Self(path)
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .or_else(|| {
+                let path = path.canonicalize().ok()?;
+                Some(path.file_name()?.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "template".to_string());
+        Self { path, name }
     }
 }
 
@@ -246,16 +560,20 @@ impl Repository for LocalPath {
     }
 
     fn repo_dir(&self) -> PathBuf {
-        self.0.clone()
+        self.path.clone()
     }
 
     fn name(&self) -> &str {
-        self.0.file_name().unwrap().to_str().unwrap()
+        &self.name
     }
 
     fn need_cache(&self) -> bool {
         false
     }
+
+    fn is_cached(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -272,11 +590,62 @@ mod tests {
                 name: "hello".to_string(),
                 uri: "http://abc/hello.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
 
+    #[test]
+    fn test_local_path_name() {
+        let dir = tempdir::TempDir::new("local").unwrap();
+        let template = dir.path().join("my-template");
+        std::fs::create_dir(&template).unwrap();
+        assert_eq!(LocalPath::new(template.clone()).name(), "my-template");
+        // `.` and `..` have no file name of their own
+        assert_eq!(LocalPath::new(template.join(".")).name(), "my-template");
+        assert_eq!(
+            LocalPath::new(template.join("sub").join("..")).name(),
+            "template"
+        );
+        std::fs::create_dir(template.join("sub")).unwrap();
+        assert_eq!(
+            LocalPath::new(template.join("sub").join("..")).name(),
+            "my-template"
+        );
+    }
+
+    #[test]
+    fn test_repo_name() {
+        for uri in [
+            "https://github.com/owner/rust.git",
+            "https://github.com/owner/rust.git/",
+            "https://github.com/owner/rust/.git",
+            "https://github.com/owner/rust.git?ref=main",
+            "https://github.com/owner/rust.git/?ref=main#readme",
+            "https://github.com/owner/rust.git#v1.0.0",
+            "git@github.com:owner/rust.git",
+            "git@github.com:rust.git",
+        ] {
+            assert_eq!(repo_name(uri).unwrap(), "rust", "{}", uri);
+        }
+        for uri in [
+            "https://.git",
+            "https://github.com/.git/",
+            "https:///?a=b.git",
+        ] {
+            assert!(
+                matches!(repo_name(uri), Err(Error::InvalidRepo { .. })),
+                "{}",
+                uri
+            );
+        }
+
+        let repo = Git::try_new("http://abc/hello.git/".into(), HashMap::new()).unwrap();
+        assert_eq!(repo.name, "hello");
+    }
+
     #[test]
     fn test_git_repo_with_specified_branch() {
         let uri = "http://abc/hello.git";
@@ -290,6 +659,8 @@ mod tests {
                 branch: Some("dev".into()),
                 auth: None,
                 name: "hello".to_string(),
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -311,6 +682,8 @@ mod tests {
                     password: "abc".into()
                 }),
                 name: "hello".to_string(),
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -332,11 +705,47 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "https://github.com/rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
 
+    #[test]
+    fn test_github_release_tag() {
+        for uri in [
+            "gh:rust-lang/rust@1.70.0",
+            "github:rust-lang/rust.git@1.70.0",
+        ] {
+            let repo = Git::try_new(uri.into(), HashMap::new()).unwrap();
+            assert_eq!(
+                repo,
+                Git {
+                    name: "rust".to_string(),
+                    uri: "https://github.com/rust-lang/rust.git".into(),
+                    branch: Some("1.70.0".into()),
+                    auth: None,
+                    timeout: None,
+                    ssh_user: None,
+                }
+            );
+            assert_eq!(
+                repo.source(),
+                "https://github.com/rust-lang/rust.git#1.70.0"
+            );
+        }
+
+        let repo = Git::try_new("github+ssh:rust-lang/rust".into(), HashMap::new()).unwrap();
+        assert_eq!(repo.uri, "git@github.com:rust-lang/rust.git");
+        assert_eq!(repo.branch, None);
+
+        let mut context = HashMap::new();
+        context.insert("branch".to_string(), "master".to_string());
+        let err = Git::try_new("gh:rust-lang/rust@1.70.0".into(), context).unwrap_err();
+        assert!(matches!(err, Error::ArgsError(_)));
+    }
+
     #[test]
     fn test_github_with_suffix() {
         let uri = "gh:rust-lang/rust";
@@ -347,7 +756,9 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "https://github.com/rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -362,7 +773,9 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "https://github.com/rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -377,7 +790,9 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "http://github.com/rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -392,7 +807,9 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "git@github.com:rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -407,7 +824,9 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "https://gitlab.com/rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -422,7 +841,9 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "https://gitlab.com/rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -437,7 +858,9 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "http://gitlab.com/rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -452,7 +875,9 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "git@gitlab.com:rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
@@ -469,8 +894,212 @@ mod tests {
                 name: "rust".to_string(),
                 uri: "git@gitlab.cn.com:rust-lang/rust.git".into(),
                 branch: None,
-                auth: None
+                auth: None,
+                timeout: None,
+                ssh_user: None,
             }
         );
     }
+
+    #[test]
+    fn test_git_repo_with_timeout() {
+        let uri = "http://abc/hello.git";
+        let mut context = HashMap::new();
+        context.insert("timeout".to_string(), "30".to_string());
+        let repo = Git::try_new(uri.into(), context).unwrap();
+        assert_eq!(repo.timeout, Some(Duration::from_secs(30)));
+
+        let mut context = HashMap::new();
+        context.insert("timeout".to_string(), "soon".to_string());
+        let err = Git::try_new(uri.into(), context).err().unwrap();
+        assert_eq!(err.to_string(), "git timeout 'soon' is invalid");
+    }
+
+    #[test]
+    fn test_clone_timeout_keeps_tmp_dir() {
+        // accepts connections but never answers
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hello.git", server.local_addr().unwrap());
+        let tmp_dir = tempdir::TempDir::new("clone").unwrap();
+        let path = tmp_dir.path().to_path_buf();
+
+        let err = clone_repo(
+            &url,
+            tmp_dir,
+            "hello",
+            Some(Duration::from_millis(200)),
+            None,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(err, Error::GitTimeout(_)), "{:?}", err);
+        // the abandoned clone may still write into it
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_ssh_username() {
+        assert_eq!(ssh_username(None, None), "git");
+        assert_eq!(ssh_username(None, Some("deploy")), "deploy");
+        assert_eq!(ssh_username(Some("svc"), None), "svc");
+        assert_eq!(ssh_username(Some("svc"), Some("deploy")), "svc");
+
+        let mut context = HashMap::new();
+        context.insert("ssh_user".to_string(), "svc".to_string());
+        let repo = Git::try_new("gl+ssh:owner/repo".into(), context).unwrap();
+        assert_eq!(repo.ssh_user.as_deref(), Some("svc"));
+    }
+
+    #[test]
+    fn test_head_revision() {
+        let dir = tempdir::TempDir::new("revision").unwrap();
+        assert_eq!(head_revision(dir.path()), None);
+
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let signature = git2::Signature::now("petridish", "petridish@example.com").unwrap();
+        let commit = repo
+            .commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        let branch = repo.head().unwrap().shorthand().unwrap().to_string();
+        assert_eq!(
+            head_revision(dir.path()),
+            Some(Revision {
+                reference: Some(branch),
+                sha: commit.to_string(),
+            })
+        );
+
+        repo.set_head_detached(commit).unwrap();
+        assert_eq!(head_revision(dir.path()).unwrap().reference, None);
+    }
+
+    #[test]
+    fn test_git_repo_with_name() {
+        let uri = "gh:my-org/service-template";
+        let repo = Git::try_new(uri.into(), HashMap::new()).unwrap();
+        assert_eq!(repo.name, "service-template");
+
+        let mut context = HashMap::new();
+        context.insert("name".to_string(), "service".to_string());
+        let repo = Git::try_new(uri.into(), context).unwrap();
+        assert_eq!(repo.name, "service");
+        assert_eq!(repo.uri, "https://github.com/my-org/service-template.git");
+
+        for invalid in ["", "my-org/service", ".."] {
+            let mut context = HashMap::new();
+            context.insert("name".to_string(), invalid.to_string());
+            let err = Git::try_new(uri.into(), context).unwrap_err();
+            assert!(matches!(err, Error::ArgsError(_)));
+        }
+    }
+
+    #[test]
+    fn test_hg_repo() {
+        for (uri, url) in [
+            (
+                "hg+https://hg.example.com/repo",
+                "https://hg.example.com/repo",
+            ),
+            (
+                "hg+http://hg.example.com/repo/",
+                "http://hg.example.com/repo/",
+            ),
+            (
+                "hg+ssh://hg@example.com//srv/repo",
+                "ssh://hg@example.com//srv/repo",
+            ),
+            (
+                "hg:https://hg.example.com/repo",
+                "https://hg.example.com/repo",
+            ),
+            ("hg:../templates/repo", "../templates/repo"),
+        ] {
+            assert!(Hg::check_match(uri), "{}", uri);
+            let repo = Hg::try_new(uri.into(), HashMap::new()).unwrap();
+            assert_eq!(repo.uri, url);
+            assert_eq!(repo.name, "repo");
+        }
+        for uri in ["gh:owner/repo", "https://hg.example.com/repo.git", "hgrepo"] {
+            assert!(!Hg::check_match(uri), "{}", uri);
+        }
+        for uri in ["hg:", "hg:--config=x", "hg+https://"] {
+            assert!(
+                matches!(
+                    Hg::try_new(uri.into(), HashMap::new()),
+                    Err(Error::InvalidRepo { .. })
+                ),
+                "{}",
+                uri
+            );
+        }
+    }
+
+    #[test]
+    fn test_hg_clone_command() {
+        let args = |repo: &Hg| {
+            repo.clone_command(Path::new("/tmp/repo"), None)
+                .get_args()
+                .map(|arg| arg.to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let repo = Hg::try_new("hg+https://hg.example.com/repo".into(), HashMap::new()).unwrap();
+        assert_eq!(
+            args(&repo),
+            [
+                "clone",
+                "--noninteractive",
+                "--",
+                "https://hg.example.com/repo",
+                "/tmp/repo"
+            ]
+        );
+
+        let mut context = HashMap::new();
+        context.insert("branch".to_string(), "stable".to_string());
+        context.insert("username".to_string(), "user1".to_string());
+        context.insert("password".to_string(), "abc".to_string());
+        let repo = Hg::try_new("hg+https://hg.example.com/repo".into(), context).unwrap();
+        assert_eq!(repo.source(), "hg+https://hg.example.com/repo#stable");
+        let dir = tempdir::TempDir::new("hg").unwrap();
+        let hgrc = repo.write_auth_config(dir.path()).unwrap().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&hgrc).unwrap(),
+            "[auth]\npetridish.prefix = https://hg.example.com/repo\n\
+             petridish.username = user1\npetridish.password = abc\n"
+        );
+        let command = repo.clone_command(Path::new("/tmp/repo"), Some(&hgrc));
+        let args = command
+            .get_args()
+            .map(|arg| arg.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            args,
+            [
+                "clone",
+                "--noninteractive",
+                "--updaterev",
+                "stable",
+                "--",
+                "https://hg.example.com/repo",
+                "/tmp/repo"
+            ]
+        );
+        assert!(!args.iter().any(|arg| arg.contains("abc")));
+        let (_, hgrc_path) = command
+            .get_envs()
+            .find(|(key, _)| *key == "HGRCPATH")
+            .unwrap();
+        assert!(std::env::split_paths(hgrc_path.unwrap()).any(|path| path == hgrc));
+
+        // ssh urls take their credentials from ssh
+        let mut context = HashMap::new();
+        context.insert("username".to_string(), "user1".to_string());
+        context.insert("password".to_string(), "abc".to_string());
+        let repo = Hg::try_new("hg+ssh://hg.example.com/repo".into(), context).unwrap();
+        assert_eq!(repo.write_auth_config(dir.path()).unwrap(), None);
+    }
 }
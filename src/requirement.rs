@@ -0,0 +1,248 @@
+use std::{
+    cmp::Ordering,
+    ffi::OsStr,
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+
+/// An external binary the template needs, e.g. for its post-gen hooks,
+/// written as `"git"` or with a minimum version as `"node>=18"`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub struct Requirement {
+    pub binary: String,
+    pub min_version: Option<Version>,
+}
+
+impl FromStr for Requirement {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (binary, min_version) = match s.split_once(">=") {
+            Some((binary, version)) => (binary.trim(), Some(version.parse::<Version>()?)),
+            None => (s.trim(), None),
+        };
+        if binary.is_empty() || binary.contains(char::is_whitespace) {
+            return Err(format!(
+                "invalid requirement '{}', should be like `git` or `node>=18`",
+                s
+            ));
+        }
+
+        Ok(Self {
+            binary: binary.to_string(),
+            min_version,
+        })
+    }
+}
+
+impl TryFrom<String> for Requirement {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Written back the way it's declared, e.g. `"node>=18"`.
+impl Serialize for Requirement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.min_version {
+            Some(version) => write!(f, "{}>={}", self.binary, version),
+            None => write!(f, "{}", self.binary),
+        }
+    }
+}
+
+/// A dotted version like `18.17.0`, missing components count as `0`.
+#[derive(Debug, Clone, Eq)]
+pub struct Version(Vec<u64>);
+
+impl Version {
+    /// Finds the first version in the output of `<binary> --version`, e.g.
+    /// `2.39.2` in `git version 2.39.2` or `18.17.0` in `v18.17.0`.
+    pub fn find(output: &str) -> Option<Self> {
+        regex::Regex::new(r"\d+(\.\d+)*")
+            .unwrap()
+            .find(output)
+            .and_then(|m| m.as_str().parse().ok())
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        s.trim()
+            .split('.')
+            .map(|c| c.parse::<u64>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map(Self)
+            .map_err(|_| format!("invalid version '{}', should be like 18 or 1.2.3", s))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let components = self.0.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", components.join("."))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        let component = |v: &Version, i: usize| v.0.get(i).copied().unwrap_or(0);
+        (0..len)
+            .map(|i| component(self, i).cmp(&component(other, i)))
+            .find(|o| o.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+/// Returns the path of `binary` in the directories of `path` (formatted like
+/// `$PATH`).
+pub fn find_in_path(binary: &str, path: &OsStr) -> Option<PathBuf> {
+    std::env::split_paths(path)
+        .flat_map(|dir| {
+            let candidate = dir.join(binary);
+            let exe = cfg!(windows).then(|| candidate.with_extension("exe"));
+            [Some(candidate), exe]
+        })
+        .flatten()
+        .find(|candidate| candidate.is_file())
+}
+
+/// Checks that every required binary is on `$PATH` and new enough, the
+/// error lists all of the unmet requirements at once.
+pub fn check_requirements(requirements: &[Requirement]) -> Result<()> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let unmet = requirements
+        .iter()
+        .filter_map(|requirement| {
+            let binary = match find_in_path(&requirement.binary, &path) {
+                Some(binary) => binary,
+                None => return Some(format!("`{}` (not found on PATH)", requirement)),
+            };
+            let min_version = requirement.min_version.as_ref()?;
+            match installed_version(&binary) {
+                Some(version) if &version >= min_version => None,
+                Some(version) => Some(format!("`{}` (found {})", requirement, version)),
+                None => Some(format!("`{}` (unknown version)", requirement)),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if unmet.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::MissingRequirements(unmet))
+    }
+}
+
+fn installed_version(binary: &Path) -> Option<Version> {
+    let output = Command::new(binary).arg("--version").output().ok()?;
+    Version::find(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| Version::find(&String::from_utf8_lossy(&output.stderr)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requirement() {
+        assert_eq!(
+            "git".parse::<Requirement>().unwrap(),
+            Requirement {
+                binary: "git".into(),
+                min_version: None,
+            }
+        );
+        let node = "node >= 18".parse::<Requirement>().unwrap();
+        assert_eq!(node.binary, "node");
+        assert_eq!(node.min_version, Some(Version(vec![18])));
+        assert_eq!(node.to_string(), "node>=18");
+        assert!("node>=latest".parse::<Requirement>().is_err());
+        assert!(">=18".parse::<Requirement>().is_err());
+
+        #[derive(Deserialize)]
+        struct Requires {
+            requires: Vec<Requirement>,
+        }
+        let requires = toml::from_str::<Requires>(r#"requires = ["git", "node>=18"]"#).unwrap();
+        assert_eq!(requires.requires[1], node);
+        assert!(toml::from_str::<Requires>(r#"requires = ["node>=x"]"#).is_err());
+    }
+
+    #[test]
+    fn test_version() {
+        assert_eq!(
+            Version::find("git version 2.39.2"),
+            Some(Version(vec![2, 39, 2]))
+        );
+        assert_eq!(Version::find("v18.17.0\n"), Some(Version(vec![18, 17, 0])));
+        assert_eq!(
+            Version::find("Docker version 24.0.5, build ced0996"),
+            Some(Version(vec![24, 0, 5]))
+        );
+        assert_eq!(Version::find("no version here"), None);
+
+        let version = |v: &str| v.parse::<Version>().unwrap();
+        assert!(version("18.17.0") >= version("18"));
+        assert!(version("16.20.1") < version("18"));
+        assert!(version("1.10") > version("1.9"));
+        assert_eq!(version("18"), version("18.0.0"));
+    }
+
+    #[test]
+    fn test_find_in_path() {
+        let first = tempdir::TempDir::new("bin").unwrap();
+        let second = tempdir::TempDir::new("bin").unwrap();
+        let binary = if cfg!(windows) { "tool.exe" } else { "tool" };
+        std::fs::write(second.path().join(binary), "").unwrap();
+        std::fs::create_dir(first.path().join("dir")).unwrap();
+        let path = std::env::join_paths([first.path(), second.path()]).unwrap();
+
+        assert_eq!(
+            find_in_path("tool", &path),
+            Some(second.path().join(binary))
+        );
+        assert_eq!(find_in_path("dir", &path), None);
+        assert_eq!(find_in_path("missing", &path), None);
+    }
+
+    #[test]
+    fn test_check_requirements() {
+        assert!(check_requirements(&[]).is_ok());
+        let err = check_requirements(&["petridish-missing-tool>=1".parse().unwrap()]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the template requires `petridish-missing-tool>=1` (not found on PATH)"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{cache::Cache, requirement::Version};
+
+const CRATES_IO_URL: &str = "https://crates.io/api/v1/crates/petridish";
+
+/// How long a looked up version is reused before asking crates.io again.
+pub const CHECK_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The latest published version as of `checked_at` (seconds since the unix
+/// epoch), cached in `update-check.json` of the cache dir.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+struct UpdateCheck {
+    checked_at: u64,
+    latest: String,
+}
+
+pub fn check_path() -> PathBuf {
+    Cache::cache_dir().join("update-check.json")
+}
+
+/// Returns an upgrade hint when a newer petridish than the running one is
+/// published. Nothing is printed or failed when offline.
+pub fn update_hint() -> Option<String> {
+    let latest = latest_version(&check_path(), SystemTime::now(), fetch_latest_version)?;
+    let current = env!("CARGO_PKG_VERSION");
+    newer_version(current, &latest).map(|latest| {
+        format!(
+            "petridish {} is available (current {}), upgrade with `cargo install petridish`",
+            latest, current
+        )
+    })
+}
+
+/// `latest` if it's newer than `current`, versions which cannot be compared
+/// are never newer.
+pub fn newer_version(current: &str, latest: &str) -> Option<Version> {
+    let current = current.parse::<Version>().ok()?;
+    let latest = latest.parse::<Version>().ok()?;
+    (latest > current).then_some(latest)
+}
+
+/// The latest published version, looked up by `fetch` unless the one cached at
+/// `path` is younger than `CHECK_TTL`. A failed lookup falls back to the
+/// cached version.
+fn latest_version(
+    path: &Path,
+    now: SystemTime,
+    fetch: impl FnOnce() -> Result<String, String>,
+) -> Option<String> {
+    let now = now.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let cached = fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<UpdateCheck>(&content).ok());
+    if let Some(cached) = &cached {
+        if now.saturating_sub(cached.checked_at) < CHECK_TTL.as_secs() {
+            return Some(cached.latest.clone());
+        }
+    }
+
+    match fetch() {
+        Ok(latest) => {
+            let check = UpdateCheck {
+                checked_at: now,
+                latest,
+            };
+            // not being able to cache the check only costs another lookup
+            let _ = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(path, serde_json::to_string(&check).unwrap()));
+            Some(check.latest)
+        }
+        Err(_) => cached.map(|cached| cached.latest),
+    }
+}
+
+fn fetch_latest_version() -> Result<String, String> {
+    #[derive(Deserialize)]
+    struct Response {
+        #[serde(rename = "crate")]
+        krate: Crate,
+    }
+    #[derive(Deserialize)]
+    struct Crate {
+        max_stable_version: String,
+    }
+
+    let response = ureq::get(CRATES_IO_URL)
+        .timeout(Duration::from_secs(3))
+        // crates.io rejects requests without a user agent
+        .set(
+            "User-Agent",
+            concat!("petridish/", env!("CARGO_PKG_VERSION")),
+        )
+        .call()
+        .map_err(|e| e.to_string())?;
+    let content = response.into_string().map_err(|e| e.to_string())?;
+    serde_json::from_str::<Response>(&content)
+        .map(|response| response.krate.max_stable_version)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_newer_version() {
+        assert_eq!(newer_version("0.3.1", "0.4.0"), "0.4.0".parse().ok());
+        assert_eq!(newer_version("0.3.1", "0.3.10"), "0.3.10".parse().ok());
+        assert_eq!(newer_version("0.3.1", "0.3.1"), None);
+        assert_eq!(newer_version("0.4.0", "0.3.9"), None);
+        assert_eq!(newer_version("0.3.1", "not a version"), None);
+    }
+
+    #[test]
+    fn test_latest_version_cache_ttl() {
+        let dir = tempdir::TempDir::new("update").unwrap();
+        let path = dir.path().join("update-check.json");
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        // nothing cached and offline
+        assert_eq!(latest_version(&path, now, || Err("offline".into())), None);
+
+        assert_eq!(
+            latest_version(&path, now, || Ok("0.4.0".into())).as_deref(),
+            Some("0.4.0")
+        );
+        // within the ttl the cached version is used without fetching
+        let later = now + CHECK_TTL - Duration::from_secs(1);
+        assert_eq!(
+            latest_version(&path, later, || panic!("fetched")).as_deref(),
+            Some("0.4.0")
+        );
+        // after it, the version is fetched again
+        let expired = now + CHECK_TTL;
+        assert_eq!(
+            latest_version(&path, expired, || Ok("0.5.0".into())).as_deref(),
+            Some("0.5.0")
+        );
+        // a failed lookup falls back to the stale version
+        let expired = expired + CHECK_TTL;
+        assert_eq!(
+            latest_version(&path, expired, || Err("offline".into())).as_deref(),
+            Some("0.5.0")
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use globset::GlobSetBuilder;
+use tera::{
+    ast::{Expr, ExprVal, Node},
+    Tera,
+};
+use walkdir::WalkDir;
+
+use crate::{
+    error::{Error, Result},
+    render::{glob, Delimiters},
+};
+
+/// Collects the variables the project dir of a template reads in its paths
+/// and the contents of its files, mapped to the files (relative to the
+/// template root) which read them. Files matching `exclude_render_paths` and
+/// binary files are not rendered, so only their paths count.
+pub fn template_variables(
+    template_dir: &Path,
+    entry_dir_name: &str,
+    exclude_render_paths: &[String],
+    delimiters: &Delimiters,
+) -> Result<BTreeMap<String, BTreeSet<PathBuf>>> {
+    let mut excludes = GlobSetBuilder::new();
+    for pattern in exclude_render_paths {
+        excludes.add(glob(pattern)?);
+    }
+    let excludes = excludes.build().unwrap();
+
+    let mut variables = BTreeMap::<String, BTreeSet<PathBuf>>::new();
+    let entry_dir = template_dir.join(entry_dir_name);
+    for entry in WalkDir::new(&entry_dir).into_iter().filter_map(|e| e.ok()) {
+        let relative_path = entry.path().strip_prefix(template_dir).unwrap();
+        let render_error = |source| Error::RenderError {
+            source,
+            path: relative_path.to_path_buf(),
+        };
+        let path = relative_path.to_string_lossy().replace('\\', "/");
+        let mut names = referenced_variables(&path).map_err(render_error)?;
+        let project_path = entry.path().strip_prefix(&entry_dir).unwrap();
+        if entry.file_type().is_file() && !excludes.is_match(project_path) {
+            if let Ok(content) = String::from_utf8(fs::read(entry.path()).unwrap_or_default()) {
+                names.extend(
+                    referenced_variables(&delimiters.translate(&content)).map_err(render_error)?,
+                );
+            }
+        }
+        for name in names {
+            variables
+                .entry(name)
+                .or_default()
+                .insert(relative_path.to_path_buf());
+        }
+    }
+    Ok(variables)
+}
+
+/// Returns the context variables a template reads, i.e. the first segment of
+/// identifiers like `author.name`. Variables declared in the template itself
+/// (`set`, loop variables, macro arguments) are left out, as well as the ones
+/// guarded by the `default` filter or an `is defined` test.
+pub fn referenced_variables(template: &str) -> tera::Result<BTreeSet<String>> {
+    let mut tera = Tera::default();
+    tera.add_raw_template("__petridish_variables", template)?;
+    let ast = &tera.get_template("__petridish_variables")?.ast;
+
+    let mut collector = Collector::default();
+    collector.nodes(ast);
+    Ok(collector.variables)
+}
+
+#[derive(Default)]
+struct Collector {
+    variables: BTreeSet<String>,
+    locals: Vec<String>,
+}
+
+impl Collector {
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    /// Walks `nodes` with `locals` in scope.
+    fn scoped<'a>(&mut self, locals: impl IntoIterator<Item = &'a String>, nodes: &[Node]) {
+        let len = self.locals.len();
+        self.locals.extend(locals.into_iter().cloned());
+        self.nodes(nodes);
+        self.locals.truncate(len);
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::VariableBlock(_, expr) => self.expr(expr),
+            Node::Set(_, set) => {
+                self.expr(&set.value);
+                self.locals.push(set.key.clone());
+            }
+            Node::Forloop(_, forloop, _) => {
+                self.expr(&forloop.container);
+                let locals = forloop.key.iter().chain([&forloop.value]);
+                self.scoped(locals.clone(), &forloop.body);
+                if let Some(empty_body) = &forloop.empty_body {
+                    self.scoped(locals, empty_body);
+                }
+            }
+            Node::If(if_node, _) => {
+                for (_, condition, body) in &if_node.conditions {
+                    self.expr(condition);
+                    // `{% if license is defined %}{{ license }}{% endif %}`
+                    let guarded = match &condition.val {
+                        ExprVal::Test(test) if test.name == "defined" && !test.negated => {
+                            Some(&test.ident)
+                        }
+                        _ => None,
+                    };
+                    self.scoped(guarded, body);
+                }
+                if let Some((_, body)) = &if_node.otherwise {
+                    self.scoped([], body);
+                }
+            }
+            Node::FilterSection(_, section, _) => {
+                section.filter.args.values().for_each(|arg| self.expr(arg));
+                self.scoped([], &section.body);
+            }
+            Node::Block(_, block, _) => self.scoped([], &block.body),
+            Node::MacroDefinition(_, definition, _) => {
+                definition
+                    .args
+                    .values()
+                    .flatten()
+                    .for_each(|arg| self.expr(arg));
+                self.scoped(definition.args.keys(), &definition.body);
+            }
+            _ => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        for filter in &expr.filters {
+            filter.args.values().for_each(|arg| self.expr(arg));
+        }
+        if !expr.has_default_filter() {
+            self.expr_val(&expr.val);
+        }
+    }
+
+    fn expr_val(&mut self, val: &ExprVal) {
+        match val {
+            ExprVal::Ident(ident) => self.ident(ident),
+            ExprVal::Math(math) => {
+                self.expr(&math.lhs);
+                self.expr(&math.rhs);
+            }
+            ExprVal::Logic(logic) => {
+                self.expr(&logic.lhs);
+                self.expr(&logic.rhs);
+            }
+            ExprVal::In(in_expr) => {
+                self.expr(&in_expr.lhs);
+                self.expr(&in_expr.rhs);
+            }
+            ExprVal::Test(test) => {
+                if test.name != "defined" && test.name != "undefined" {
+                    self.ident(&test.ident);
+                }
+                test.args.iter().for_each(|arg| self.expr(arg));
+            }
+            ExprVal::MacroCall(call) => call.args.values().for_each(|arg| self.expr(arg)),
+            ExprVal::FunctionCall(call) => call.args.values().for_each(|arg| self.expr(arg)),
+            ExprVal::Array(items) => items.iter().for_each(|item| self.expr(item)),
+            ExprVal::StringConcat(concat) => concat.values.iter().for_each(|v| self.expr_val(v)),
+            ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => {}
+        }
+    }
+
+    fn ident(&mut self, ident: &str) {
+        let root = ident.split(['.', '[']).next().unwrap_or(ident).trim();
+        if root.is_empty()
+            || root == "loop"
+            || root == "__tera_context"
+            || self.locals.iter().any(|local| local == root)
+        {
+            return;
+        }
+        self.variables.insert(root.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(template: &str) -> Vec<String> {
+        referenced_variables(template)
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_template_variables() {
+        let template = tempdir::TempDir::new("template").unwrap();
+        let entry = template.path().join("{{ project_name }}");
+        fs::create_dir_all(entry.join("{% if use_docker %}docker{% endif %}")).unwrap();
+        fs::write(
+            entry.join("README.md"),
+            "# <[ project_name ]>\n{% for dep in deps %}<[ dep ]>{% endfor %}<[ author.name ]>",
+        )
+        .unwrap();
+        fs::write(
+            entry.join("{% if use_docker %}docker{% endif %}/Dockerfile"),
+            "EXPOSE <[ port ]>",
+        )
+        .unwrap();
+        fs::write(entry.join("raw.html"), "{{ not_rendered }}").unwrap();
+        fs::write(entry.join("logo.png"), [0x89, 0xff, 0xfe]).unwrap();
+
+        let delimiters = Delimiters {
+            variable_start: "<[".into(),
+            variable_end: "]>".into(),
+            ..Delimiters::default()
+        };
+        let variables = template_variables(
+            template.path(),
+            "{{ project_name }}",
+            &["*.html".into()],
+            &delimiters,
+        )
+        .unwrap();
+        assert_eq!(
+            variables.keys().collect::<Vec<_>>(),
+            ["author", "deps", "port", "project_name", "use_docker"]
+        );
+        assert_eq!(
+            variables["port"],
+            BTreeSet::from([PathBuf::from(
+                "{{ project_name }}/{% if use_docker %}docker{% endif %}/Dockerfile"
+            )])
+        );
+        assert_eq!(variables["use_docker"].len(), 2);
+    }
+
+    #[test]
+    fn test_referenced_variables() {
+        assert_eq!(
+            variables("{{ project_name | upper }} by {{ author.name }} {{ years[0] }}"),
+            ["author", "project_name", "years"]
+        );
+        assert_eq!(
+            variables("{% if use_docker and port > min_port %}{{ 'x' ~ image }}{% endif %}"),
+            ["image", "min_port", "port", "use_docker"]
+        );
+        assert_eq!(
+            variables("{% for k, v in deps %}{{ k }}={{ v }}{{ loop.index }}{% endfor %}{{ v }}"),
+            ["deps", "v"]
+        );
+        assert_eq!(
+            variables("{% set name = user | default(value=fallback) %}{{ name }}"),
+            ["fallback"]
+        );
+        assert!(variables("{% if license is defined %}{{ license }}{% endif %}").is_empty());
+        assert_eq!(
+            variables("{% if license is undefined %}{% else %}{{ license }}{% endif %}"),
+            ["license"]
+        );
+        assert!(referenced_variables("{{ unclosed").is_err());
+    }
+}
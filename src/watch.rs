@@ -0,0 +1,129 @@
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
+/// What to render again after files of a template changed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Rerender {
+    /// The whole template, e.g. after `petridish.toml` or a file included by
+    /// others changed.
+    All,
+    /// Just these files, relative to the template dir like
+    /// `{{ project_name }}/README.md`.
+    Files(BTreeSet<PathBuf>),
+}
+
+/// Maps the changed paths to what has to be rendered again, `None` when no
+/// generated file depends on them, e.g. for the metadata of git. Changed files
+/// of the project dir are rendered again by themselves, deleted ones are left
+/// alone, and any other file of the template renders everything.
+pub fn rerender(
+    template_dir: &Path,
+    entry_dir_name: &str,
+    changed: impl IntoIterator<Item = PathBuf>,
+) -> Option<Rerender> {
+    let mut files = BTreeSet::new();
+    for path in changed {
+        let Ok(relative_path) = path.strip_prefix(template_dir) else {
+            continue;
+        };
+        if relative_path
+            .components()
+            .any(|c| matches!(c, Component::Normal(name) if name == ".git" || name == ".hg"))
+        {
+            continue;
+        }
+        if !relative_path.starts_with(entry_dir_name) {
+            return Some(Rerender::All);
+        }
+        if path.is_file() {
+            files.insert(relative_path.to_path_buf());
+        }
+    }
+
+    (!files.is_empty()).then_some(Rerender::Files(files))
+}
+
+/// Waits for the next changes, and then for them to settle until none came
+/// for `quiet`, so saving several files at once renders them together. `None`
+/// once `changes` is closed.
+pub fn debounce<T>(changes: &Receiver<T>, quiet: Duration) -> Option<Vec<T>> {
+    let mut batch = vec![changes.recv().ok()?];
+    loop {
+        match changes.recv_timeout(quiet) {
+            Ok(change) => batch.push(change),
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return Some(batch),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, sync::mpsc};
+
+    #[test]
+    fn test_rerender() {
+        let template = tempdir::TempDir::new("watch").unwrap();
+        let template_dir = template.path();
+        let entry_dir = template_dir.join("{{ project_name }}");
+        fs::create_dir_all(entry_dir.join("src")).unwrap();
+        fs::write(entry_dir.join("README.md"), "").unwrap();
+        fs::write(entry_dir.join("src").join("main.rs"), "").unwrap();
+        let rerender =
+            |changed: &[PathBuf]| rerender(template_dir, "{{ project_name }}", changed.to_vec());
+
+        assert_eq!(
+            rerender(&[
+                entry_dir.join("README.md"),
+                entry_dir.join("src").join("main.rs"),
+                entry_dir.join("README.md"),
+                // a dir is rendered along with its files
+                entry_dir.join("src"),
+            ]),
+            Some(Rerender::Files(BTreeSet::from([
+                PathBuf::from("{{ project_name }}/README.md"),
+                PathBuf::from("{{ project_name }}/src/main.rs"),
+            ])))
+        );
+        assert_eq!(
+            rerender(&[
+                entry_dir.join("README.md"),
+                template_dir.join("petridish.toml")
+            ]),
+            Some(Rerender::All)
+        );
+        assert_eq!(
+            rerender(&[template_dir.join("partials").join("header.md")]),
+            Some(Rerender::All)
+        );
+        assert_eq!(
+            rerender(&[
+                template_dir.join(".git").join("index"),
+                entry_dir.join("deleted.md"),
+                PathBuf::from("/elsewhere/file"),
+            ]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_debounce() {
+        let (tx, rx) = mpsc::channel();
+        for change in 0..3 {
+            tx.send(change).unwrap();
+        }
+        assert_eq!(
+            debounce(&rx, Duration::from_millis(10)),
+            Some(vec![0, 1, 2])
+        );
+
+        tx.send(3).unwrap();
+        drop(tx);
+        assert_eq!(debounce(&rx, Duration::from_millis(10)), Some(vec![3]));
+        assert_eq!(debounce(&rx, Duration::from_millis(10)), None);
+    }
+}
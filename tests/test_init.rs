@@ -0,0 +1,31 @@
+use petridish::{config::Config, error::Error, init::init_template};
+use std::fs;
+
+#[test]
+fn test_init_template() {
+    let dir = tempdir::TempDir::new("template").unwrap();
+    init_template(dir.path(), false).unwrap();
+
+    assert!(dir.path().join("README.md").exists());
+    assert!(dir
+        .path()
+        .join("{{ project_name }}")
+        .join("README.md")
+        .exists());
+    let config = Config::load(dir.path()).unwrap();
+    assert_eq!(config.petridish_config.project_var_name, "project_name");
+    assert_eq!(config.prompts.len(), 3);
+}
+
+#[test]
+fn test_init_template_not_empty() {
+    let dir = tempdir::TempDir::new("template").unwrap();
+    fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+    assert!(matches!(
+        init_template(dir.path(), false),
+        Err(Error::DirNotEmpty(_))
+    ));
+    init_template(dir.path(), true).unwrap();
+    assert!(dir.path().join("petridish.toml").exists());
+}
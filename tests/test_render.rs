@@ -1,6 +1,15 @@
-use petridish::render::Render;
-use std::fs;
-use tera::Context;
+use petridish::{
+    config::{Config, PetridishConfig, PromptValues},
+    error::Error,
+    manifest::MANIFEST_FILE,
+    render::{prepare_output_dir, Delimiters, LineEndings, Render, RenderReport, Symlinks},
+};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use tera::{Context, Value};
 
 #[test]
 fn test_render() {
@@ -51,3 +60,1639 @@ fn test_render() {
         "Secret"
     );
 }
+
+#[test]
+fn test_render_with_copy_without_render() {
+    let config = toml::from_str::<Config>(
+        &fs::read_to_string("tests/exclude_template/petridish.toml").unwrap(),
+    )
+    .unwrap();
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    let render = Render::new(
+        "tests/exclude_template",
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        config.petridish_config.exclude_render_paths,
+    );
+    render.render().unwrap();
+
+    let project = output.path().join("awesome");
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "awesome\n"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("LICENSE")).unwrap(),
+        "Copyright {{ author }}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("assets").join("index.html")).unwrap(),
+        "<p>{{ user.name }}</p>\n"
+    );
+}
+
+#[test]
+fn test_render_streams_excluded_files() {
+    let template = tempdir::TempDir::new("template").unwrap();
+    let entry = template.path().join("{{ project_name }}");
+    fs::create_dir_all(entry.join("assets")).unwrap();
+    // 16 MiB which is neither rendered nor valid utf-8
+    let data = (0..16 * 1024 * 1024)
+        .map(|i| (i % 251) as u8 | 0x80)
+        .collect::<Vec<_>>();
+    fs::write(entry.join("assets").join("data.bin"), &data).unwrap();
+    fs::write(entry.join("assets").join("raw.txt"), "{{ not rendered").unwrap();
+    fs::write(entry.join("README.md"), "{{ project_name }}").unwrap();
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    let report = Render::new(
+        template.path(),
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec!["assets/**".into()],
+    )
+    .with_atomic(true)
+    .render()
+    .unwrap();
+
+    let project = output.path().join("awesome");
+    assert_eq!(report.rendered, [project.join("README.md")]);
+    assert_eq!(
+        report.copied,
+        [
+            project.join("assets").join("data.bin"),
+            project.join("assets").join("raw.txt")
+        ]
+    );
+    assert!(fs::read(project.join("assets").join("data.bin")).unwrap() == data);
+    assert_eq!(
+        fs::read_to_string(project.join("assets").join("raw.txt")).unwrap(),
+        "{{ not rendered"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "awesome"
+    );
+}
+
+#[test]
+fn test_render_keep_empty_dirs() {
+    let template = tempdir::TempDir::new("template").unwrap();
+    let entry = template.path().join("{{ project_name }}");
+    fs::create_dir_all(entry.join("logs")).unwrap();
+    fs::write(entry.join("README.md"), "{{ project_name }}").unwrap();
+
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::new(
+        template.path(),
+        "{{ project_name }}",
+        output.path(),
+        context.clone(),
+        false,
+        false,
+        vec![],
+    )
+    .render()
+    .unwrap();
+    assert!(output.path().join("awesome").join("README.md").exists());
+    assert!(!output.path().join("awesome").join("logs").exists());
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::new(
+        template.path(),
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .with_keep_empty_dirs(true)
+    .render()
+    .unwrap();
+    assert!(output.path().join("awesome").join("logs").is_dir());
+}
+
+#[test]
+fn test_render_include_file() {
+    let template = tempdir::TempDir::new("template").unwrap();
+    let entry = template.path().join("{{ project_name }}");
+    fs::create_dir_all(&entry).unwrap();
+    fs::write(
+        template.path().join("LICENSE_HEADER.txt"),
+        "// Copyright {{ author }}",
+    )
+    .unwrap();
+    fs::write(
+        entry.join("main.rs"),
+        "{{ include_file(path=\"LICENSE_HEADER.txt\", render=true) }}\nfn main() {}",
+    )
+    .unwrap();
+    fs::write(
+        entry.join("raw.rs"),
+        "{{ include_file(path=\"LICENSE_HEADER.txt\") }}",
+    )
+    .unwrap();
+
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+    context.insert("author", "JoJo");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::new(
+        template.path(),
+        "{{ project_name }}",
+        output.path(),
+        context.clone(),
+        false,
+        false,
+        vec![],
+    )
+    .render()
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(output.path().join("awesome").join("main.rs")).unwrap(),
+        "// Copyright JoJo\nfn main() {}"
+    );
+    assert_eq!(
+        fs::read_to_string(output.path().join("awesome").join("raw.rs")).unwrap(),
+        "// Copyright {{ author }}"
+    );
+
+    // files outside of the template cannot be included
+    fs::write(
+        entry.join("main.rs"),
+        "{{ include_file(path=\"../../etc/passwd\") }}",
+    )
+    .unwrap();
+    let output = tempdir::TempDir::new("test").unwrap();
+    assert!(Render::new(
+        template.path(),
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .render()
+    .is_err());
+}
+
+#[test]
+fn test_render_in_place() {
+    let mut context = Context::new();
+    context.insert("project", "awesome");
+    context.insert("abc", "ABC");
+    context.insert("dir_name", "my_dir");
+    context.insert("inner", "Bingo");
+    context.insert("name", "JoJo");
+    context.insert("inner_value", "Secret");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    fs::write(output.path().join("ABC.txt"), "existing").unwrap();
+    Render::new(
+        "tests/templates",
+        "{{ project }}",
+        output.path(),
+        context,
+        false,
+        true,
+        vec![],
+    )
+    .with_in_place(true)
+    .render()
+    .unwrap();
+
+    assert!(!output.path().join("awesome").exists());
+    assert_eq!(
+        fs::read_to_string(output.path().join("ABC.txt")).unwrap(),
+        "existing"
+    );
+    assert_eq!(
+        fs::read_to_string(output.path().join("my_dir").join("Bingo.txt"))
+            .unwrap()
+            .trim_end(),
+        "Secret"
+    );
+}
+
+#[test]
+fn test_render_with_render_suffix() {
+    let template = tempdir::TempDir::new("template").unwrap();
+    let entry = template.path().join("{{ project_name }}");
+    fs::create_dir_all(&entry).unwrap();
+    fs::write(
+        entry.join("Cargo.toml.tmpl"),
+        "name = \"{{ project_name }}\"",
+    )
+    .unwrap();
+    fs::write(entry.join("README.md"), "# {{ project_name }}").unwrap();
+
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::new(
+        template.path(),
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .with_render_suffix(Some(".tmpl".into()))
+    .render()
+    .unwrap();
+
+    let project = output.path().join("awesome");
+    assert!(!project.join("Cargo.toml.tmpl").exists());
+    assert_eq!(
+        fs::read_to_string(project.join("Cargo.toml")).unwrap(),
+        "name = \"awesome\""
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "# awesome"
+    );
+}
+
+#[test]
+fn test_render_from_files() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/README.md"),
+            b"{{ include_file(path='HEADER.md', render=true) }}".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/{{ module }}/lib.rs"),
+            b"// {{ module }}".to_vec(),
+        ),
+        (PathBuf::from("HEADER.md"), b"# {{ project_name }}".to_vec()),
+    ]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+    context.insert("module", "core");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .render()
+    .unwrap();
+
+    let project = output.path().join("awesome");
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "# awesome"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("core").join("lib.rs")).unwrap(),
+        "// core"
+    );
+    assert!(!output.path().join("HEADER.md").exists());
+}
+
+#[test]
+fn test_render_default_excludes() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/.editorconfig"),
+            b"[*.{js,json}]\n# {{ not a tera tag\n".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/.vscode/settings.json"),
+            b"{\"a\": {{\"b\": 1}}}".to_vec(),
+        ),
+    ]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::from_files(
+        files.clone(),
+        "{{ project_name }}",
+        output.path(),
+        context.clone(),
+        false,
+        false,
+        PetridishConfig::default().render_excludes(),
+    )
+    .render()
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(output.path().join("awesome").join(".editorconfig")).unwrap(),
+        "[*.{js,json}]\n# {{ not a tera tag\n"
+    );
+
+    let config = PetridishConfig {
+        exclude_render_defaults: false,
+        ..Default::default()
+    };
+    let err = Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        true,
+        false,
+        config.render_excludes(),
+    )
+    .render()
+    .unwrap_err();
+    assert!(matches!(err, Error::RenderError { .. }));
+}
+
+#[test]
+fn test_render_excludes_with_glob_characters_in_project_name() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/.editorconfig"),
+            b"[*.{js,json}]\n".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/cliff.toml"),
+            b"{{ not a tera tag".to_vec(),
+        ),
+    ]);
+    let mut excludes = PetridishConfig::default().render_excludes();
+    excludes.push("cliff.toml".into());
+
+    for project_name in ["a{b", "foo[1]", "*"] {
+        let mut context = Context::new();
+        context.insert("project_name", project_name);
+        let output = tempdir::TempDir::new("test").unwrap();
+        Render::from_files(
+            files.clone(),
+            "{{ project_name }}",
+            output.path(),
+            context,
+            false,
+            false,
+            excludes.clone(),
+        )
+        .render()
+        .unwrap();
+        let project = output.path().join(project_name);
+        assert_eq!(
+            fs::read_to_string(project.join(".editorconfig")).unwrap(),
+            "[*.{js,json}]\n"
+        );
+        assert_eq!(
+            fs::read_to_string(project.join("cliff.toml")).unwrap(),
+            "{{ not a tera tag"
+        );
+    }
+}
+
+#[test]
+fn test_render_invalid_exclude_glob() {
+    let files = HashMap::from([(
+        PathBuf::from("{{ project_name }}/README.md"),
+        b"# {{ project_name }}".to_vec(),
+    )]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+    context.insert("assets", "static/{a,b");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    let err = Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec!["{{ assets }}/**".into()],
+    )
+    .render()
+    .unwrap_err();
+    assert!(matches!(err, Error::InvalidGlob { .. }), "{:?}", err);
+}
+
+#[test]
+fn test_render_error_names_file() {
+    let files = HashMap::from([(
+        PathBuf::from("{{ project_name }}/src/broken.rs"),
+        b"{{ unclosed".to_vec(),
+    )]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    let err = Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .render()
+    .unwrap_err();
+    assert!(
+        matches!(&err, Error::RenderError { path, .. } if path.ends_with("src/broken.rs")),
+        "{:?}",
+        err
+    );
+    assert!(err.to_string().contains("broken.rs"));
+}
+
+#[test]
+fn test_render_keep_going() {
+    let render = |write_partial: bool| {
+        let files = HashMap::from([
+            (
+                PathBuf::from("{{ project_name }}/broken.md"),
+                b"{{ unclosed".to_vec(),
+            ),
+            (
+                PathBuf::from("{{ project_name }}/src/broken.rs"),
+                b"{% if %}".to_vec(),
+            ),
+            (
+                PathBuf::from("{{ project_name }}/README.md"),
+                b"# {{ project_name }}".to_vec(),
+            ),
+        ]);
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+
+        let output = tempdir::TempDir::new("test").unwrap();
+        let err = Render::from_files(
+            files,
+            "{{ project_name }}",
+            output.path(),
+            context,
+            false,
+            false,
+            vec![],
+        )
+        .with_keep_going(true)
+        .with_write_partial(write_partial)
+        .render()
+        .unwrap_err();
+        let readme = output.path().join("awesome").join("README.md");
+        (err, readme.exists())
+    };
+
+    let (err, written) = render(false);
+    let Error::RenderErrors(errors) = &err else {
+        panic!("{:?}", err);
+    };
+    let mut paths = errors
+        .iter()
+        .map(|err| match err {
+            Error::RenderError { path, .. } => path.clone(),
+            err => panic!("{:?}", err),
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            PathBuf::from("awesome/broken.md"),
+            PathBuf::from("awesome/src/broken.rs"),
+        ]
+    );
+    assert_eq!(err.to_string(), "cannot render 2 files");
+    assert!(!written);
+
+    let (err, written) = render(true);
+    assert!(matches!(&err, Error::RenderErrors(errors) if errors.len() == 2));
+    assert!(written);
+}
+
+#[test]
+fn test_render_tera_setup() {
+    let files = HashMap::from([(
+        PathBuf::from("{{ project_name }}/OWNERS"),
+        b"{{ employee_id(name=\"alice\") }} {{ project_name | kebab_case }}".to_vec(),
+    )]);
+    let mut context = Context::new();
+    context.insert("project_name", "Awesome App");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .with_tera_setup(|tera| {
+        tera.register_function("employee_id", |args: &HashMap<String, Value>| {
+            let name = args["name"].as_str().unwrap();
+            Ok(Value::String(format!("E-{}", name.len())))
+        });
+        // overrides the built-in filter
+        tera.register_filter("kebab_case", |value: &Value, _: &HashMap<String, Value>| {
+            Ok(Value::String(value.as_str().unwrap().to_uppercase()))
+        });
+    })
+    .render()
+    .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(output.path().join("Awesome App/OWNERS")).unwrap(),
+        "E-5 AWESOME APP"
+    );
+}
+
+#[test]
+fn test_render_only() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/README.md"),
+            b"# {{ project_name }}".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/src/main.rs"),
+            b"// {{ project_name }}".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/src/cli/args.rs"),
+            b"".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/tests/main.rs"),
+            b"".to_vec(),
+        ),
+    ]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .with_only(vec!["src/**".into()])
+    .render()
+    .unwrap();
+
+    let project = output.path().join("awesome");
+    assert_eq!(
+        fs::read_to_string(project.join("src/main.rs")).unwrap(),
+        "// awesome"
+    );
+    assert!(project.join("src/cli/args.rs").exists());
+    assert!(!project.join("README.md").exists());
+    assert!(!project.join("tests").exists());
+}
+
+#[test]
+fn test_render_changed() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/README.md"),
+            b"# {{ project_name }}".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/src/main.rs"),
+            b"// {{ project_name }}".to_vec(),
+        ),
+    ]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    let project = output.path().join("awesome");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("README.md"), "edited").unwrap();
+    let report = Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        true,
+        false,
+        vec![],
+    )
+    .with_changed([PathBuf::from("{{ project_name }}/src/main.rs")])
+    .render()
+    .unwrap();
+
+    assert_eq!(report.rendered, vec![project.join("src/main.rs")]);
+    assert_eq!(
+        fs::read_to_string(project.join("src/main.rs")).unwrap(),
+        "// awesome"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "edited"
+    );
+}
+
+#[test]
+fn test_render_into_template_is_rejected() {
+    let outer = tempdir::TempDir::new("outer").unwrap();
+    let template = outer.path().join("my-template");
+    fs::create_dir_all(template.join("{{ project_name }}")).unwrap();
+    fs::write(template.join("{{ project_name }}/README.md"), "").unwrap();
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+    let render = |output: PathBuf, in_place: bool| {
+        Render::new(
+            &template,
+            "{{ project_name }}",
+            output,
+            context.clone(),
+            false,
+            false,
+            vec![],
+        )
+        .with_in_place(in_place)
+        .render()
+    };
+
+    for (output, in_place) in [
+        (template.clone(), false),
+        (template.join("out"), false),
+        (template.join("{{ project_name }}"), true),
+    ] {
+        assert!(
+            matches!(
+                render(output.clone(), in_place),
+                Err(Error::OutputOverlapsTemplate { .. })
+            ),
+            "{:?}",
+            output
+        );
+    }
+    assert!(!template.join("awesome").exists());
+
+    let output = tempdir::TempDir::new("output").unwrap();
+    render(output.path().to_path_buf(), false).unwrap();
+    assert!(output.path().join("awesome/README.md").exists());
+
+    // the template is inside of the project dir
+    render(outer.path().to_path_buf(), true).unwrap();
+    assert!(outer.path().join("README.md").exists());
+}
+
+#[test]
+fn test_render_report() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/README.md"),
+            b"# {{ project_name }}".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/.editorconfig"),
+            b"{{ raw }}".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/logo.png"),
+            vec![0x89, 0x50, 0xff, 0xfe],
+        ),
+        (PathBuf::from("{{ project_name }}/LICENSE"), b"MIT".to_vec()),
+    ]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    let project = output.path().join("awesome");
+    fs::create_dir(&project).unwrap();
+    fs::write(project.join("LICENSE"), "kept").unwrap();
+    let report = Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        true,
+        vec![".editorconfig".into()],
+    )
+    .render()
+    .unwrap();
+
+    assert_eq!(report.rendered, [project.join("README.md")]);
+    assert_eq!(
+        report.copied,
+        [project.join(".editorconfig"), project.join("logo.png")]
+    );
+    assert_eq!(report.skipped, [project.join("LICENSE")]);
+    assert_eq!(
+        fs::read_to_string(project.join(".editorconfig")).unwrap(),
+        "{{ raw }}"
+    );
+}
+
+fn merge_template(output: &Path, files: &[(&str, &str)]) -> RenderReport {
+    let files = files
+        .iter()
+        .map(|(path, content)| {
+            (
+                PathBuf::from("{{ project_name }}").join(path),
+                content.as_bytes().to_vec(),
+            )
+        })
+        .collect();
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+    Render::from_files(
+        files,
+        "{{ project_name }}",
+        output,
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .with_merge(true)
+    .render()
+    .unwrap()
+}
+
+#[test]
+fn test_render_merge() {
+    let output = tempdir::TempDir::new("test").unwrap();
+    let project = output.path().join("awesome");
+    let report = merge_template(
+        output.path(),
+        &[
+            ("README.md", "# v1"),
+            ("config.toml", "debug = false"),
+            ("src/main.rs", "fn main() {}"),
+        ],
+    );
+    assert_eq!(report.rendered.len(), 3);
+    assert!(project.join(MANIFEST_FILE).exists());
+
+    fs::write(project.join("config.toml"), "debug = true").unwrap();
+    fs::write(project.join("src/main.rs"), "fn main() { mine() }").unwrap();
+    let report = merge_template(
+        output.path(),
+        &[
+            ("README.md", "# v2"),
+            ("config.toml", "debug = false"),
+            ("src/main.rs", "fn main() { v2() }"),
+            ("CHANGELOG.md", "v2"),
+        ],
+    );
+
+    // unchanged in the project, so updated
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "# v2"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("CHANGELOG.md")).unwrap(),
+        "v2"
+    );
+    // only changed in the project
+    assert_eq!(
+        fs::read_to_string(project.join("config.toml")).unwrap(),
+        "debug = true"
+    );
+    // changed on both sides
+    assert_eq!(
+        fs::read_to_string(project.join("src/main.rs")).unwrap(),
+        "fn main() { mine() }"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("src/main.rs.new")).unwrap(),
+        "fn main() { v2() }"
+    );
+    assert_eq!(
+        report.rendered,
+        [project.join("CHANGELOG.md"), project.join("README.md")]
+    );
+    assert_eq!(report.skipped, [project.join("config.toml")]);
+    assert_eq!(report.conflicts, [project.join("src/main.rs")]);
+
+    // once resolved, the same template has nothing to merge
+    fs::remove_file(project.join("src/main.rs.new")).unwrap();
+    fs::write(project.join("src/main.rs"), "fn main() { mine(); v2() }").unwrap();
+    let report = merge_template(
+        output.path(),
+        &[
+            ("README.md", "# v2"),
+            ("config.toml", "debug = false"),
+            ("src/main.rs", "fn main() { v2() }"),
+            ("CHANGELOG.md", "v2"),
+        ],
+    );
+    assert!(report.rendered.is_empty());
+    assert!(report.conflicts.is_empty());
+    assert_eq!(report.skipped.len(), 4);
+    assert!(!project.join("src/main.rs.new").exists());
+}
+
+#[test]
+fn test_render_merge_unrecorded_file() {
+    let output = tempdir::TempDir::new("test").unwrap();
+    let project = output.path().join("awesome");
+    fs::create_dir(&project).unwrap();
+    fs::write(project.join("README.md"), "# mine").unwrap();
+
+    let report = merge_template(output.path(), &[("README.md", "# v1")]);
+    assert_eq!(report.conflicts, [project.join("README.md")]);
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "# mine"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("README.md.new")).unwrap(),
+        "# v1"
+    );
+}
+
+#[test]
+fn test_prepare_output_dir_is_file() {
+    let output = tempdir::TempDir::new("test").unwrap();
+    let file = output.path().join("file");
+    fs::write(&file, "").unwrap();
+
+    assert!(matches!(
+        prepare_output_dir(&file, true),
+        Err(Error::OutputDirIsFile(path)) if path == file
+    ));
+    prepare_output_dir(output.path(), false).unwrap();
+}
+
+#[test]
+fn test_prepare_output_dir_missing_parent() {
+    let output = tempdir::TempDir::new("test").unwrap();
+    prepare_output_dir(&output.path().join("new"), false).unwrap();
+
+    let nested = output.path().join("a").join("b");
+    assert!(matches!(
+        prepare_output_dir(&nested, false),
+        Err(Error::OutputDirParentNotFound(path)) if path == nested
+    ));
+    prepare_output_dir(&nested, true).unwrap();
+    assert!(output.path().join("a").is_dir());
+}
+
+#[test]
+fn test_render_with_delimiters() {
+    let files = HashMap::from([(
+        PathBuf::from("{{ project_name }}/ci.yml"),
+        b"name: [[ project_name ]]\ntoken: ${{ secrets.TOKEN }}\n[% if docker %]docker: true[% endif %]\n"
+            .to_vec(),
+    )]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+    context.insert("docker", &true);
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .with_delimiters(Delimiters {
+        variable_start: "[[".into(),
+        variable_end: "]]".into(),
+        block_start: "[%".into(),
+        block_end: "%]".into(),
+    })
+    .render()
+    .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(output.path().join("awesome").join("ci.yml")).unwrap(),
+        "name: awesome\ntoken: ${{ secrets.TOKEN }}\ndocker: true\n"
+    );
+}
+
+#[test]
+fn test_render_skips_empty_path_segments() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/{% if use_docker %}Dockerfile{% endif %}"),
+            b"FROM rust".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/{% if use_ci %}.github{% endif %}/ci.yml"),
+            b"on: push".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/README.md"),
+            b"readme".to_vec(),
+        ),
+    ]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+    context.insert("use_docker", &false);
+    context.insert("use_ci", &true);
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .render()
+    .unwrap();
+
+    let project = output.path().join("awesome");
+    assert!(project.join("README.md").exists());
+    assert!(project.join(".github").join("ci.yml").exists());
+    assert_eq!(fs::read_dir(&project).unwrap().count(), 2);
+}
+
+#[test]
+fn test_render_trim_whitespace() {
+    let template = "services:\n  {% for service in services %}\n  - {{ service }}\n  {% endfor %}\nend: {% if true %}yes{% endif %}\n";
+    let render = |trim_whitespace: bool| {
+        let files = HashMap::from([(
+            PathBuf::from("{{ project_name }}/config.yml"),
+            template.as_bytes().to_vec(),
+        )]);
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+        context.insert("services", &["web", "db"]);
+
+        let output = tempdir::TempDir::new("test").unwrap();
+        Render::from_files(
+            files,
+            "{{ project_name }}",
+            output.path(),
+            context,
+            false,
+            false,
+            vec![],
+        )
+        .with_trim_whitespace(trim_whitespace)
+        .render()
+        .unwrap();
+        fs::read_to_string(output.path().join("awesome").join("config.yml")).unwrap()
+    };
+
+    assert_eq!(
+        render(false),
+        "services:\n  \n  - web\n  \n  - db\n  \nend: yes\n"
+    );
+    assert_eq!(render(true), "services:\n  - web\n  - db\nend: yes\n");
+}
+
+#[test]
+fn test_render_file() {
+    let config = toml::from_str::<Config>(
+        &fs::read_to_string("tests/exclude_template/petridish.toml").unwrap(),
+    )
+    .unwrap();
+    let context = config
+        .context_from(&PromptValues {
+            extra_context: HashMap::from([("project_name".to_string(), "awesome".to_string())]),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    let rendered = Render::new(
+        "tests/exclude_template",
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .render_file("README.md")
+    .unwrap();
+
+    assert_eq!(
+        rendered,
+        fs::read_to_string("tests/exclude_template/{{ project_name }}/README.md")
+            .unwrap()
+            .replace("{{ project_name }}", "awesome")
+    );
+    assert_eq!(fs::read_dir(output.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_render_line_endings() {
+    let render = |line_endings: LineEndings| {
+        let files = HashMap::from([
+            (
+                PathBuf::from("{{ project_name }}/mixed.txt"),
+                b"{{ project_name }}\r\nunix\nwindows\r\n".to_vec(),
+            ),
+            (
+                PathBuf::from("{{ project_name }}/verbatim.txt"),
+                b"a\r\nb\n".to_vec(),
+            ),
+        ]);
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+
+        let output = tempdir::TempDir::new("test").unwrap();
+        Render::from_files(
+            files,
+            "{{ project_name }}",
+            output.path(),
+            context,
+            false,
+            false,
+            vec!["verbatim.txt".into()],
+        )
+        .with_line_endings(line_endings)
+        .render()
+        .unwrap();
+        let project = output.path().join("awesome");
+        let verbatim = fs::read_to_string(project.join("verbatim.txt")).unwrap();
+        assert_eq!(verbatim, "a\r\nb\n");
+        fs::read_to_string(project.join("mixed.txt")).unwrap()
+    };
+
+    assert_eq!(
+        render(LineEndings::Preserve),
+        "awesome\r\nunix\nwindows\r\n"
+    );
+    assert_eq!(render(LineEndings::Lf), "awesome\nunix\nwindows\n");
+    assert_eq!(render(LineEndings::Crlf), "awesome\r\nunix\r\nwindows\r\n");
+}
+
+#[test]
+fn test_render_atomic() {
+    let render = |too_long_name: bool| {
+        let mut files = HashMap::from([
+            (
+                PathBuf::from("{{ project_name }}/README.md"),
+                b"# {{ project_name }}".to_vec(),
+            ),
+            (
+                PathBuf::from("{{ project_name }}/src/main.rs"),
+                b"fn main() {}".to_vec(),
+            ),
+        ]);
+        if too_long_name {
+            // the file system refuses to write it, after the other files
+            // may have been written
+            files.insert(
+                PathBuf::from(format!("{{{{ project_name }}}}/{}", "x".repeat(300))),
+                b"".to_vec(),
+            );
+        }
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+
+        let output = tempdir::TempDir::new("test").unwrap();
+        let result = Render::from_files(
+            files,
+            "{{ project_name }}",
+            output.path(),
+            context,
+            false,
+            false,
+            vec![],
+        )
+        .with_atomic(true)
+        .render();
+        (result, output)
+    };
+
+    let (result, output) = render(false);
+    result.unwrap();
+    let entries = fs::read_dir(output.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(entries, ["awesome"]);
+    let project = output.path().join("awesome");
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "# awesome"
+    );
+    assert!(project.join("src").join("main.rs").exists());
+
+    let (result, output) = render(true);
+    assert!(matches!(result, Err(Error::CannotWriteFile { .. })));
+    assert_eq!(fs::read_dir(output.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_render_atomic_rollback() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/README.md"),
+            b"# {{ project_name }}".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/src/main.rs"),
+            b"fn main() {}".to_vec(),
+        ),
+        // a dir of the same name is in the way, after the other files moved
+        (PathBuf::from("{{ project_name }}/zzz"), b"".to_vec()),
+    ]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    let project = output.path().join("awesome");
+    fs::create_dir_all(project.join("zzz")).unwrap();
+    fs::write(project.join("zzz").join("keep.txt"), "").unwrap();
+    fs::write(project.join("README.md"), "old").unwrap();
+
+    let result = Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        true,
+        false,
+        vec![],
+    )
+    .with_atomic(true)
+    .render();
+    assert!(matches!(result, Err(Error::CannotWriteFile { path, .. }) if path.ends_with("zzz")));
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "old"
+    );
+    assert!(!project.join("src").exists());
+    assert!(project.join("zzz").join("keep.txt").exists());
+    let entries = fs::read_dir(output.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(entries, ["awesome"]);
+}
+
+#[test]
+fn test_render_dot_prefix() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/dot_gitignore"),
+            b"target/".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/dot_github/workflows/ci.yml"),
+            b"name: {{ project_name }}".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/docs/not_dot_prefixed.md"),
+            b"".to_vec(),
+        ),
+    ]);
+    // the project dir and `output_subdir` are not translated
+    let mut context = Context::new();
+    context.insert("project_name", "dot_net");
+    let render = |output_subdir: Option<&str>| {
+        let output = tempdir::TempDir::new("test").unwrap();
+        Render::from_files(
+            files.clone(),
+            "{{ project_name }}",
+            output.path(),
+            context.clone(),
+            false,
+            false,
+            vec![],
+        )
+        .with_output_subdir(output_subdir.map(String::from))
+        .render()
+        .unwrap();
+        output
+    };
+
+    let output = render(Some("dot_apps/{{ project_name }}"));
+    let project = output.path().join("dot_apps").join("dot_net");
+    assert!(project.join(".gitignore").exists());
+
+    let output = render(None);
+    let project = output.path().join("dot_net");
+    assert_eq!(
+        fs::read_to_string(project.join(".gitignore")).unwrap(),
+        "target/"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join(".github/workflows/ci.yml")).unwrap(),
+        "name: dot_net"
+    );
+    assert!(project.join("docs/not_dot_prefixed.md").exists());
+    assert!(!project.join("dot_gitignore").exists());
+}
+
+#[test]
+fn test_render_diff() {
+    let files = HashMap::from([
+        (
+            PathBuf::from("{{ project_name }}/README.md"),
+            b"# {{ project_name }}\n\nversion 2\n".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/LICENSE"),
+            b"MIT\n".to_vec(),
+        ),
+        (
+            PathBuf::from("{{ project_name }}/CHANGELOG.md"),
+            b"new\n".to_vec(),
+        ),
+    ]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    let project = output.path().join("awesome");
+    fs::create_dir(&project).unwrap();
+    fs::write(project.join("README.md"), "# awesome\n\nversion 1\n").unwrap();
+    fs::write(project.join("LICENSE"), "MIT\n").unwrap();
+
+    let diff = Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .diff()
+    .unwrap();
+
+    assert_eq!(
+        diff,
+        "--- /dev/null\n\
+         +++ b/awesome/CHANGELOG.md\n\
+         @@ -0,0 +1 @@\n\
+         +new\n\
+         --- a/awesome/README.md\n\
+         +++ b/awesome/README.md\n\
+         @@ -1,3 +1,3 @@\n \
+         # awesome\n \n\
+         -version 1\n\
+         +version 2\n"
+    );
+    assert_eq!(
+        fs::read_to_string(project.join("README.md")).unwrap(),
+        "# awesome\n\nversion 1\n"
+    );
+    assert!(!project.join("CHANGELOG.md").exists());
+}
+
+#[test]
+fn test_render_strip_dir_marker() {
+    let render = |marker: Option<String>| {
+        let files = HashMap::from([
+            (
+                PathBuf::from("{{ project_name }}/logs/.gitkeep"),
+                b"".to_vec(),
+            ),
+            (
+                PathBuf::from("{{ project_name }}/README.md"),
+                b"readme".to_vec(),
+            ),
+        ]);
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+
+        let output = tempdir::TempDir::new("test").unwrap();
+        Render::from_files(
+            files,
+            "{{ project_name }}",
+            output.path(),
+            context,
+            false,
+            false,
+            vec![],
+        )
+        .with_strip_dir_marker(marker)
+        .render()
+        .unwrap();
+        output
+    };
+
+    let output = render(Some(".gitkeep".into()));
+    let logs = output.path().join("awesome").join("logs");
+    assert!(logs.is_dir());
+    assert!(!logs.join(".gitkeep").exists());
+    assert!(output.path().join("awesome").join("README.md").exists());
+
+    let output = render(None);
+    assert!(output.path().join("awesome/logs/.gitkeep").exists());
+}
+
+#[test]
+fn test_render_output_subdir() {
+    let render = |output_subdir: &str| {
+        let files = HashMap::from([(
+            PathBuf::from("{{ project_name }}/src/lib.rs"),
+            b"// {{ org }}".to_vec(),
+        )]);
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+        context.insert("org", "acme");
+
+        let output = tempdir::TempDir::new("test").unwrap();
+        let result = Render::from_files(
+            files,
+            "{{ project_name }}",
+            output.path(),
+            context,
+            false,
+            false,
+            vec![],
+        )
+        .with_output_subdir(Some(output_subdir.into()))
+        .render();
+        (result, output)
+    };
+
+    let (result, output) = render("services/{{ org }}/{{ project_name }}");
+    result.unwrap();
+    assert_eq!(
+        fs::read_to_string(output.path().join("services/acme/awesome/src/lib.rs")).unwrap(),
+        "// acme"
+    );
+    assert!(!output.path().join("awesome").exists());
+
+    for escaping in [
+        "../{{ project_name }}",
+        "/tmp/{{ project_name }}",
+        "{{ '' }}",
+    ] {
+        let (result, output) = render(escaping);
+        assert!(matches!(result, Err(Error::InvalidOutputSubdir(_))));
+        assert_eq!(fs::read_dir(output.path()).unwrap().count(), 0);
+    }
+}
+
+#[test]
+fn test_render_strict_vars() {
+    let render = |content: &str, strict_vars: bool| {
+        let files = HashMap::from([(
+            PathBuf::from("{{ project_name }}/README.md"),
+            content.as_bytes().to_vec(),
+        )]);
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+
+        let output = tempdir::TempDir::new("test").unwrap();
+        Render::from_files(
+            files,
+            "{{ project_name }}",
+            output.path(),
+            context,
+            false,
+            false,
+            vec![],
+        )
+        .with_strict_vars(strict_vars)
+        .render()
+    };
+
+    // Tera takes the typo as falsy and silently skips the block
+    let typo = "{% if projct_name %}# {{ project_name }}{% endif %}";
+    render(typo, false).unwrap();
+    let err = render(typo, true).unwrap_err();
+    assert!(matches!(&err, Error::UndefinedVariable { path, name }
+            if path == &PathBuf::from("awesome/README.md") && name == "projct_name"));
+    assert_eq!(
+        err.to_string(),
+        "'awesome/README.md' references undefined variable `projct_name`"
+    );
+
+    render(
+        "# {{ project_name }} {{ license | default(value='MIT') }}",
+        true,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_render_undefined_placeholder() {
+    let files = HashMap::from([(
+        PathBuf::from("{{ project_name }}/README.md"),
+        b"# {{ project_name }} by {{ author }}, {{ license | default(value='MIT') }}".to_vec(),
+    )]);
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::from_files(
+        files,
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .with_undefined_placeholder(true)
+    .render()
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(output.path().join("awesome").join("README.md")).unwrap(),
+        "# awesome by <<MISSING:author>>, MIT"
+    );
+}
+
+#[test]
+fn test_render_with_base() {
+    let templates = tempdir::TempDir::new("templates").unwrap();
+    let write = |path: &str, content: &str| {
+        let path = templates.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    };
+    write("base/{{ name }}/LICENSE", "(c) {{ author }}");
+    write("base/{{ name }}/.github/ci.yml", "base ci");
+    write("base/{{ name }}/README.md", "base readme");
+    write("child/{{ project_name }}/README.md", "# {{ project_name }}");
+    write("child/{{ project_name }}/src/lib.rs", "");
+
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+    context.insert("author", "JoJo");
+    let output = tempdir::TempDir::new("test").unwrap();
+    Render::new(
+        templates.path().join("child"),
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .with_bases(vec![(
+        templates.path().join("base"),
+        "{{ name }}".to_string(),
+    )])
+    .render()
+    .unwrap();
+
+    let project = output.path().join("awesome");
+    let read = |path: &str| fs::read_to_string(project.join(path)).unwrap();
+    assert_eq!(read("LICENSE"), "(c) JoJo");
+    assert_eq!(read(".github/ci.yml"), "base ci");
+    assert_eq!(read("README.md"), "# awesome");
+    assert_eq!(read("src/lib.rs"), "");
+    assert_eq!(fs::read_dir(output.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_render_prune_empty_dirs() {
+    let template = tempdir::TempDir::new("template").unwrap();
+    let entry = template.path().join("{{ project_name }}");
+    fs::create_dir_all(entry.join("logs")).unwrap();
+    fs::create_dir_all(entry.join("docker")).unwrap();
+    fs::write(
+        entry.join("docker/{% if use_docker %}Dockerfile{% endif %}"),
+        "FROM rust",
+    )
+    .unwrap();
+    fs::write(entry.join("README.md"), "{{ project_name }}").unwrap();
+
+    let render = |prune_empty_dirs: bool| {
+        let mut context = Context::new();
+        context.insert("project_name", "awesome");
+        context.insert("use_docker", &false);
+        let output = tempdir::TempDir::new("test").unwrap();
+        // not created by petridish
+        fs::create_dir(output.path().join("existing")).unwrap();
+        Render::new(
+            template.path(),
+            "{{ project_name }}",
+            output.path(),
+            context,
+            false,
+            false,
+            vec![],
+        )
+        .with_keep_empty_dirs(true)
+        .with_prune_empty_dirs(prune_empty_dirs)
+        .render()
+        .unwrap();
+        output
+    };
+
+    let output = render(false);
+    assert!(output.path().join("awesome/docker").is_dir());
+
+    let output = render(true);
+    let project = output.path().join("awesome");
+    assert!(!project.join("docker").exists());
+    assert!(project.join("logs").is_dir());
+    assert!(project.join("README.md").exists());
+    assert!(output.path().join("existing").is_dir());
+}
+
+/// A template with a link to a shared file of the template, which escapes
+/// the project dir, and one to an absolute path.
+#[cfg(unix)]
+fn symlink_template(template: &Path) {
+    use std::os::unix::fs::symlink;
+
+    let project = template.join("{{ project_name }}");
+    fs::create_dir_all(&project).unwrap();
+    fs::create_dir_all(template.join("shared")).unwrap();
+    fs::write(
+        template.join("shared").join("LICENSE"),
+        "MIT {{ project_name }}",
+    )
+    .unwrap();
+    fs::write(template.join("outside"), "outside").unwrap();
+    symlink("../shared/LICENSE", project.join("LICENSE")).unwrap();
+    symlink(template.join("outside"), project.join("absolute")).unwrap();
+}
+
+#[cfg(unix)]
+fn render_symlinks(symlinks: Symlinks) -> (tempdir::TempDir, RenderReport) {
+    let template = tempdir::TempDir::new("template").unwrap();
+    symlink_template(template.path());
+    let output = tempdir::TempDir::new("output").unwrap();
+    let mut context = Context::new();
+    context.insert("project_name", "awesome");
+    let report = Render::new(
+        template.path(),
+        "{{ project_name }}",
+        output.path(),
+        context,
+        false,
+        false,
+        vec![],
+    )
+    .with_symlinks(symlinks)
+    .render()
+    .unwrap();
+    (output, report)
+}
+
+#[cfg(unix)]
+#[test]
+fn test_render_symlinks_copy() {
+    let (output, report) = render_symlinks(Symlinks::Copy);
+    let project = output.path().join("awesome");
+    assert_eq!(
+        fs::read_link(project.join("LICENSE")).unwrap(),
+        PathBuf::from("../shared/LICENSE")
+    );
+    assert!(fs::symlink_metadata(project.join("absolute"))
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(
+        report.escaping_symlinks,
+        vec![project.join("LICENSE"), project.join("absolute")]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_render_symlinks_follow() {
+    let (output, report) = render_symlinks(Symlinks::Follow);
+    let project = output.path().join("awesome");
+    let license = fs::symlink_metadata(project.join("LICENSE")).unwrap();
+    assert!(license.is_file());
+    // followed files are rendered like the others
+    assert_eq!(
+        fs::read_to_string(project.join("LICENSE")).unwrap(),
+        "MIT awesome"
+    );
+    assert!(fs::symlink_metadata(project.join("absolute"))
+        .unwrap()
+        .is_file());
+    // the shared file stays inside of the template
+    assert_eq!(report.escaping_symlinks, vec![project.join("absolute")]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_render_symlinks_skip() {
+    let (output, report) = render_symlinks(Symlinks::Skip);
+    let project = output.path().join("awesome");
+    assert!(fs::symlink_metadata(project.join("LICENSE")).is_err());
+    assert!(fs::symlink_metadata(project.join("absolute")).is_err());
+    assert!(report.escaping_symlinks.is_empty());
+}